@@ -0,0 +1,74 @@
+//! Measure how long the audio callback takes to render songs with many MIDI events.
+//!
+//! `cargo bench -p audio --features bench`
+//!
+//! Each song is rendered for the same number of buffers, so the time per callback shouldn't depend on the length of the song.
+//! The callback is compared to the baseline: the callback before the MIDI event queue had a cursor.
+
+use audio::bench::{BaselineCallbackBench, CallbackBench, RenderBench};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The number of frames per buffer.
+const BUFFER_SIZE: usize = 512;
+/// The number of buffers to render per song.
+const NUM_BUFFERS: usize = 2000;
+/// The framerate that the songs are rendered at.
+const FRAMERATE: f64 = 44100.0;
+/// The duration of each note in number of samples.
+const NOTE_LENGTH: u64 = 441;
+/// The number of notes per song.
+const NUM_NOTES: [u64; 4] = [1000, 10000, 100000, 1000000];
+
+fn main() {
+    let soundfont = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/CT1MBGMRSV1.06.sf2");
+    let budget = Duration::from_secs_f64(BUFFER_SIZE as f64 / FRAMERATE);
+    println!(
+        "{} frames per buffer. Real-time budget per callback: {:?}",
+        BUFFER_SIZE, budget
+    );
+    for soundfont in [None, Some(soundfont.as_path())] {
+        println!(
+            "{}",
+            if soundfont.is_some() {
+                "With a SoundFont:"
+            } else {
+                "Without a SoundFont:"
+            }
+        );
+        for num_notes in NUM_NOTES {
+            println!("\t{} notes ({} events):", num_notes, num_notes * 2);
+            let baseline = BaselineCallbackBench::new(num_notes, NOTE_LENGTH, soundfont)
+                .unwrap_or_else(|error| panic!("{}", error));
+            bench("Baseline", baseline);
+            let callback = CallbackBench::new(num_notes, NOTE_LENGTH, soundfont)
+                .unwrap_or_else(|error| panic!("{}", error));
+            bench("Callback", callback);
+        }
+    }
+}
+
+/// Render a song and print the mean and max time per callback.
+fn bench<T: RenderBench>(label: &str, mut callback: T) {
+    let mut output = vec![0.0; BUFFER_SIZE * 2];
+    let mut total = Duration::ZERO;
+    let mut max = Duration::ZERO;
+    let mut num_buffers = 0;
+    for _ in 0..NUM_BUFFERS {
+        let now = Instant::now();
+        let playing = callback.render(&mut output);
+        let elapsed = now.elapsed();
+        total += elapsed;
+        max = max.max(elapsed);
+        num_buffers += 1;
+        if !playing {
+            break;
+        }
+    }
+    println!(
+        "\t\t{}: mean {:?}, max {:?} per callback",
+        label,
+        total / num_buffers,
+        max
+    );
+}
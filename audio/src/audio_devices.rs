@@ -0,0 +1,215 @@
+use common::{AudioDeviceSettings, Index, IndexedValues};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::*;
+use serde::{Deserialize, Serialize};
+
+/// Framerates that the user can choose from if the device supports them.
+const FRAMERATES: [u32; 6] = [22050, 44100, 48000, 88200, 96000, 192000];
+/// Buffer sizes that the user can choose from if the device supports them.
+const BUFFER_SIZES: [u32; 8] = [32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+/// Enum values for audio device settings.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Deserialize, Serialize)]
+pub enum AudioDeviceSetting {
+    #[default]
+    Host,
+    Device,
+    Framerate,
+    BufferSize,
+}
+
+/// The user-defined audio device settings, and the values that the user can choose from.
+pub struct AudioDevices {
+    /// The user-defined settings.
+    pub settings: AudioDeviceSettings,
+    /// The selected setting.
+    pub setting: IndexedValues<AudioDeviceSetting, 4>,
+    /// The names of the available audio hosts.
+    pub hosts: Vec<String>,
+    /// The names of the host's output devices.
+    pub devices: Vec<String>,
+    /// The framerates that the output device supports.
+    pub framerates: Vec<u32>,
+    /// The buffer sizes that the output device supports.
+    pub buffer_sizes: Vec<u32>,
+}
+
+impl AudioDevices {
+    pub fn new(settings: AudioDeviceSettings) -> Self {
+        Self {
+            settings,
+            setting: IndexedValues::new(
+                0,
+                [
+                    AudioDeviceSetting::Host,
+                    AudioDeviceSetting::Device,
+                    AudioDeviceSetting::Framerate,
+                    AudioDeviceSetting::BufferSize,
+                ],
+            ),
+            hosts: vec![],
+            devices: vec![],
+            framerates: vec![],
+            buffer_sizes: vec![],
+        }
+    }
+
+    /// Enumerate the hosts, the output devices, and the values that the output device supports.
+    /// This can be slow, so it should only be called when the user wants to change the settings.
+    pub fn refresh(&mut self) {
+        self.hosts = available_hosts()
+            .iter()
+            .map(|id| id.name().to_string())
+            .collect();
+        let host = get_host(&self.settings);
+        self.devices = match host.output_devices() {
+            Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+            Err(_) => vec![],
+        };
+        let ranges: Vec<SupportedStreamConfigRange> = match get_device(&host, &self.settings) {
+            Some(device) => match device.supported_output_configs() {
+                Ok(ranges) => ranges.collect(),
+                Err(_) => vec![],
+            },
+            None => vec![],
+        };
+        self.framerates = FRAMERATES
+            .iter()
+            .filter(|framerate| ranges.iter().any(|r| supports_framerate(r, **framerate)))
+            .copied()
+            .collect();
+        self.buffer_sizes = BUFFER_SIZES
+            .iter()
+            .filter(|buffer_size| {
+                ranges
+                    .iter()
+                    .any(|r| supports_buffer_size(r, **buffer_size))
+            })
+            .copied()
+            .collect();
+    }
+
+    /// Returns the user-defined value of a setting as a string, or None if the setting uses the default value.
+    pub fn get_value(&self, setting: &AudioDeviceSetting) -> Option<String> {
+        match setting {
+            AudioDeviceSetting::Host => self.settings.host.clone(),
+            AudioDeviceSetting::Device => self.settings.device.clone(),
+            AudioDeviceSetting::Framerate => self.settings.framerate.map(|f| f.to_string()),
+            AudioDeviceSetting::BufferSize => self.settings.buffer_size.map(|b| b.to_string()),
+        }
+    }
+
+    /// Set the value of the selected setting to the previous or next value. Returns true if the value changed.
+    ///
+    /// The first value is always None, which means "use the default value".
+    pub fn cycle(&mut self, up: bool) -> bool {
+        let changed = match self.setting.get() {
+            AudioDeviceSetting::Host => {
+                let changed = Self::cycle_value(&mut self.settings.host, &self.hosts, up);
+                // Device names are per-host.
+                if changed {
+                    self.settings.device = None;
+                }
+                changed
+            }
+            AudioDeviceSetting::Device => {
+                Self::cycle_value(&mut self.settings.device, &self.devices, up)
+            }
+            AudioDeviceSetting::Framerate => {
+                Self::cycle_value(&mut self.settings.framerate, &self.framerates, up)
+            }
+            AudioDeviceSetting::BufferSize => {
+                Self::cycle_value(&mut self.settings.buffer_size, &self.buffer_sizes, up)
+            }
+        };
+        if changed {
+            self.refresh();
+        }
+        changed
+    }
+
+    /// Set `value` to the previous or next element in `values`, or to None. Returns true if the value changed.
+    fn cycle_value<T: Clone + PartialEq>(value: &mut Option<T>, values: &[T], up: bool) -> bool {
+        if values.is_empty() {
+            return false;
+        }
+        // Index 0 is None.
+        let index = match value {
+            Some(v) => values.iter().position(|w| w == v).map_or(0, |i| i + 1),
+            None => 0,
+        };
+        let mut index = Index::new(index, values.len() + 1);
+        index.increment(up);
+        let v = match index.get() {
+            0 => None,
+            i => Some(values[i - 1].clone()),
+        };
+        let changed = v != *value;
+        *value = v;
+        changed
+    }
+}
+
+/// Returns the user-defined host, or the default host.
+pub(crate) fn get_host(settings: &AudioDeviceSettings) -> Host {
+    settings
+        .host
+        .as_ref()
+        .and_then(|name| available_hosts().into_iter().find(|id| id.name() == name))
+        .and_then(|id| host_from_id(id).ok())
+        .unwrap_or_else(default_host)
+}
+
+/// Returns the user-defined output device, or the default output device.
+pub(crate) fn get_device(host: &Host, settings: &AudioDeviceSettings) -> Option<Device> {
+    settings
+        .device
+        .as_ref()
+        .and_then(|name| {
+            host.output_devices().ok().and_then(|mut devices| {
+                devices.find(|device| device.name().is_ok_and(|n| n == *name))
+            })
+        })
+        .or_else(|| host.default_output_device())
+}
+
+/// Returns the output stream config.
+/// The user-defined framerate and buffer size are used only if the device supports them.
+pub(crate) fn get_stream_config(
+    device: &Device,
+    settings: &AudioDeviceSettings,
+) -> Result<StreamConfig, DefaultStreamConfigError> {
+    let config = device.default_output_config()?;
+    let sample_format = config.sample_format();
+    let mut stream_config: StreamConfig = config.into();
+    let ranges: Vec<SupportedStreamConfigRange> = match device.supported_output_configs() {
+        Ok(ranges) => ranges
+            .filter(|r| {
+                r.channels() == stream_config.channels && r.sample_format() == sample_format
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    if let Some(framerate) = settings.framerate {
+        if ranges.iter().any(|r| supports_framerate(r, framerate)) {
+            stream_config.sample_rate = SampleRate(framerate);
+        }
+    }
+    if let Some(buffer_size) = settings.buffer_size {
+        if ranges.iter().any(|r| supports_buffer_size(r, buffer_size)) {
+            stream_config.buffer_size = BufferSize::Fixed(buffer_size);
+        }
+    }
+    Ok(stream_config)
+}
+
+fn supports_framerate(range: &SupportedStreamConfigRange, framerate: u32) -> bool {
+    range.min_sample_rate().0 <= framerate && framerate <= range.max_sample_rate().0
+}
+
+fn supports_buffer_size(range: &SupportedStreamConfigRange, buffer_size: u32) -> bool {
+    match range.buffer_size() {
+        SupportedBufferSize::Range { min, max } => *min <= buffer_size && buffer_size <= *max,
+        SupportedBufferSize::Unknown => false,
+    }
+}
@@ -0,0 +1,153 @@
+//! Hooks for benchmarking the audio callback. This module is only compiled with the `bench` feature.
+
+use crate::midi_event_queue::MidiEventQueue;
+use crate::play_state::PlayState;
+use crate::timed_midi_event::TimedMidiEvent;
+use crate::{Metronome, Player, SharedPlayState};
+use common::DEFAULT_FRAMERATE;
+use oxisynth::{MidiEvent, SoundFont, Synth};
+use parking_lot::Mutex;
+use std::fs::File;
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+/// Render a generated song one buffer at a time.
+pub trait RenderBench {
+    /// Write the next buffer of interleaved stereo frames. Returns false if the song is done.
+    fn render(&mut self, output: &mut [f32]) -> bool;
+}
+
+/// Play a generated song with the same callback that the `Player` uses.
+pub struct CallbackBench {
+    /// The data callback.
+    callback: Box<dyn FnMut(&mut [f32]) + Send>,
+    /// The play state. The callback sets this to `Decaying` when there are no more events.
+    play_state: SharedPlayState,
+}
+
+impl CallbackBench {
+    /// - `num_notes` The number of notes in the song. Each note has a note-on and a note-off event.
+    /// - `note_length` The duration of each note and the time between notes in number of samples.
+    /// - `soundfont` If not None, load this SoundFont. Otherwise, the synthesizer still receives every event but doesn't make any sound.
+    ///
+    /// Returns an error if the SoundFont couldn't be loaded.
+    pub fn new(num_notes: u64, note_length: u64, soundfont: Option<&Path>) -> Result<Self, String> {
+        let synth = get_synth(soundfont)?;
+        let mut queue = MidiEventQueue::default();
+        for (time, event) in get_events(num_notes, note_length) {
+            queue.enqueue(time, event);
+        }
+        queue.sort();
+        let (sender, receiver) = channel();
+        let _ = sender.send(queue);
+        let play_state = Arc::new(Mutex::new(PlayState::Playing(0)));
+        let callback = Player::get_data_callback(
+            2,
+            receiver,
+            Arc::new(Mutex::new(synth)),
+            Arc::new(Mutex::new((0.0, 0.0))),
+            Arc::clone(&play_state),
+            Arc::new(Mutex::new(Metronome::default())),
+        );
+        Ok(Self {
+            callback: Box::new(callback),
+            play_state,
+        })
+    }
+}
+
+impl RenderBench for CallbackBench {
+    fn render(&mut self, output: &mut [f32]) -> bool {
+        (self.callback)(output);
+        matches!(*self.play_state.lock(), PlayState::Playing(_))
+    }
+}
+
+/// The audio callback before the MIDI event queue had a cursor. This is the baseline that `CallbackBench` is compared to.
+///
+/// The queue and the synth are locked per callback, the time of the next event is checked per frame, and each dequeued event is removed from the front of a `Vec`, which is O(n).
+pub struct BaselineCallbackBench {
+    /// The synthesizer.
+    synth: Arc<Mutex<Synth>>,
+    /// The events. Assume that this is sorted.
+    events: Arc<Mutex<Vec<TimedMidiEvent>>>,
+    /// The time of the next frame in number of samples.
+    time: u64,
+}
+
+impl BaselineCallbackBench {
+    /// See `CallbackBench::new`.
+    pub fn new(num_notes: u64, note_length: u64, soundfont: Option<&Path>) -> Result<Self, String> {
+        let synth = get_synth(soundfont)?;
+        let mut events: Vec<TimedMidiEvent> = get_events(num_notes, note_length)
+            .into_iter()
+            .map(|(time, event)| TimedMidiEvent { time, event })
+            .collect();
+        events.sort();
+        Ok(Self {
+            synth: Arc::new(Mutex::new(synth)),
+            events: Arc::new(Mutex::new(events)),
+            time: 0,
+        })
+    }
+}
+
+impl RenderBench for BaselineCallbackBench {
+    fn render(&mut self, output: &mut [f32]) -> bool {
+        let mut synth = self.synth.lock();
+        let mut events = self.events.lock();
+        for frame in output.chunks_mut(2) {
+            match events.first().map(|e| e.time) {
+                Some(next_time) => {
+                    // Dequeue events.
+                    if self.time == next_time {
+                        while !events.is_empty() && events[0].time == self.time {
+                            let _ = synth.send_event(events.remove(0).event);
+                        }
+                    }
+                    synth.write(frame);
+                    self.time += 1;
+                }
+                // There are no more events.
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Returns a synthesizer. If `soundfont` is not None, load the SoundFont.
+fn get_synth(soundfont: Option<&Path>) -> Result<Synth, String> {
+    let mut synth = Synth::default();
+    synth.set_gain(1.0);
+    synth.set_sample_rate(DEFAULT_FRAMERATE as f32);
+    if let Some(path) = soundfont {
+        let mut file =
+            File::open(path).map_err(|error| format!("Failed to open {:?}: {}", path, error))?;
+        let font = SoundFont::load(&mut file)
+            .map_err(|error| format!("Failed to load {:?}: {:?}", path, error))?;
+        synth.add_font(font, true);
+    }
+    Ok(synth)
+}
+
+/// Returns the unsorted note-on and note-off events of a generated song.
+fn get_events(num_notes: u64, note_length: u64) -> Vec<(u64, MidiEvent)> {
+    let mut events = vec![];
+    for i in 0..num_notes {
+        let channel = (i % 16) as u8;
+        let key = 36 + (i % 48) as u8;
+        let start = i * note_length;
+        events.push((
+            start,
+            MidiEvent::NoteOn {
+                channel,
+                key,
+                vel: 100,
+            },
+        ));
+        events.push((start + note_length, MidiEvent::NoteOff { channel, key }));
+    }
+    events
+}
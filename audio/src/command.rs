@@ -3,8 +3,12 @@ use std::path::PathBuf;
 /// A command for the synthesizer.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Command {
-    /// Load a SoundFont file.
-    LoadSoundFont { channel: u8, path: PathBuf },
+    /// Load a SoundFont file. If `percussion` is true, the default program is in the percussion bank.
+    LoadSoundFont {
+        channel: u8,
+        path: PathBuf,
+        percussion: bool,
+    },
     /// Set a program.
     SetProgram {
         channel: u8,
@@ -14,6 +18,12 @@ pub enum Command {
     },
     /// Set the program to None.
     UnsetProgram { channel: u8 },
+    /// Load a Scala scale and/or keyboard mapping and retune a channel. If both paths are None, reset the channel to 12-tone equal temperament.
+    SetTuning {
+        channel: u8,
+        scale: Option<PathBuf>,
+        keyboard_mapping: Option<PathBuf>,
+    },
     /// Set the overall gain.
     SetGain { gain: u8 },
 }
@@ -1,3 +1,4 @@
+use crate::audio_devices::AudioDevices;
 use crate::decayer::Decayer;
 use crate::export::{ExportState, ExportType, Exportable, MultiFileSuffix};
 use crate::exporter::Exporter;
@@ -5,18 +6,23 @@ use crate::play_state::PlayState;
 use crate::types::SharedPlayState;
 use crate::SharedExportState;
 use crate::{
-    midi_event_queue::MidiEventQueue, types::SharedSample, Command, Player, Program,
-    SharedMidiEventQueue, SharedSynth, SynthState,
+    midi_event_queue::MidiEventQueue, types::SharedSample, Command, LoadError, Metronome,
+    MidiEventQueueSender, Player, Program, SharedMetronome, SharedSynth, SoundFontError,
+    SynthState, TuningError,
 };
 use common::open_file::Extension;
-use common::{MidiTrack, Music, PathsState, State, Time, MAX_VOLUME};
+use common::{
+    get_content_hash, AudioDeviceSettings, AutomationType, CountIn, MidiTrack, Music, PathsState,
+    State, Time, Tuning, DEFAULT_FRAMERATE, MAX_VOLUME, PAN_CONTROLLER, PERCUSSION_BANK,
+};
 use hashbrown::HashMap;
 use oxisynth::{MidiEvent, SoundFont, SoundFontId, Synth};
 use parking_lot::Mutex;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
 use std::sync::Arc;
-use std::thread::spawn;
+use std::thread::{spawn, JoinHandle};
 
 /// A convenient wrapper for a SoundFont.
 struct SoundFontBanks {
@@ -49,7 +55,9 @@ pub struct Conn {
     /// The playback framerate.
     pub framerate: f32,
     /// The audio player. This is here so we don't drop it.
-    _player: Option<Player>,
+    player: Option<Player>,
+    /// The audio device settings.
+    pub audio_devices: AudioDevices,
     /// The most recent sample.
     /// `render::MainMenu` uses this to for its power bars.
     pub sample: SharedSample,
@@ -57,10 +65,9 @@ pub struct Conn {
     /// The `Conn` uses this to send MIDI events and export.
     /// The `Player` uses this to write samples to the output buffer.
     synth: SharedSynth,
-    /// A queue of scheduled MIDI events.
-    /// The `Conn` can add to this.
-    /// The `Player` can read this and remove events.
-    midi_event_queue: SharedMidiEventQueue,
+    /// Sends a queue of scheduled MIDI events to the `Player` when music starts to play.
+    /// This is None if there is no `Player`.
+    midi_event_queue: Option<MidiEventQueueSender>,
     /// A HashMap of loaded SoundFonts. Key = The path to a .sf2 file.
     soundfonts: HashMap<PathBuf, SoundFontBanks>,
     /// Metadata for all SoundFont programs.
@@ -69,59 +76,76 @@ pub struct Conn {
     pub exporter: Exporter,
     /// A flag that `Player` uses to decide how to write samples to the output buffer.
     pub play_state: SharedPlayState,
+    /// The metronome. Its settings aren't saved, so the metronome can be toggled per session.
+    /// The `Player` uses this to add clicks to the output buffer.
+    pub metronome: SharedMetronome,
 }
 
 impl Default for Conn {
     fn default() -> Self {
+        Self::new(Some(AudioDeviceSettings::default()))
+    }
+}
+
+impl Conn {
+    /// - `audio_device` The audio output device settings. If None, this connection doesn't open an audio device. It can't play music but it can still export.
+    pub fn new(audio_device: Option<AudioDeviceSettings>) -> Self {
         // Set the synthesizer.
         let mut synth = Synth::default();
         synth.set_gain(1.0);
         let synth = Arc::new(Mutex::new(synth));
 
         // Create other shared data.
-        let midi_event_queue = Arc::new(Mutex::new(MidiEventQueue::default()));
         let sample = Arc::new(Mutex::new((0.0, 0.0)));
         let play_state = Arc::new(Mutex::new(PlayState::NotPlaying));
+        let metronome = Arc::new(Mutex::new(Metronome::default()));
 
-        // Create the player.
-        let player_synth = Arc::clone(&synth);
-        let player_midi_event_queue = Arc::clone(&midi_event_queue);
-        let player_sample = Arc::clone(&sample);
-        let player_play_state = Arc::clone(&play_state);
-        let player = Player::new(
-            player_midi_event_queue,
-            player_synth,
-            player_sample,
-            player_play_state,
-        );
-
-        // Get the framerate.
-        let framerate = match &player {
-            Some(player) => player.framerate as f32,
-            None => 0.0,
-        };
-        Self {
+        let open_audio_device = audio_device.is_some();
+        let mut conn = Self {
             export_state: Arc::new(Mutex::new(ExportState::NotExporting)),
-            _player: player,
-            framerate,
+            player: None,
+            framerate: DEFAULT_FRAMERATE as f32,
+            audio_devices: AudioDevices::new(audio_device.unwrap_or_default()),
             sample,
             synth,
-            midi_event_queue,
+            midi_event_queue: None,
             soundfonts: HashMap::default(),
             state: SynthState::default(),
             exporter: Exporter::default(),
             play_state,
+            metronome,
+        };
+        // Create the player.
+        if open_audio_device {
+            conn.set_player();
         }
+        conn
+    }
+
+    /// Rebuild the audio output stream with the current audio device settings.
+    /// If music is playing, this stops it.
+    pub fn set_audio_device(&mut self, music: &Music) {
+        let play_state = *self.play_state.lock();
+        if let PlayState::Playing(_) = play_state {
+            self.stop_music(music);
+        }
+        self.set_player();
+    }
+
+    /// Returns true if audio is playing through an audio output device.
+    pub fn has_audio_device(&self) -> bool {
+        self.player
+            .as_ref()
+            .is_some_and(|player| player.has_device())
     }
-}
 
-impl Conn {
     /// Do all note-on events created by user input on this app frame.
     pub fn note_ons(&mut self, state: &State, note_ons: &[[u8; 3]]) {
         if let Some(track) = state.music.get_selected_track() {
             if !note_ons.is_empty() {
                 let mut synth = self.synth.lock();
                 let gain = track.gain as f32 / MAX_VOLUME as f32;
+                let _ = synth.send_event(Self::get_pan_event(track));
                 for note_on in note_ons.iter() {
                     let _ = synth.send_event(MidiEvent::NoteOn {
                         channel: track.channel,
@@ -129,9 +153,11 @@ impl Conn {
                         vel: (note_on[2] as f32 * gain) as u8,
                     });
                 }
-                // Play audio.
+                // Play audio. If music is already playing, the player is already writing audio.
                 let mut play_state = self.play_state.lock();
-                *play_state = PlayState::Decaying;
+                if !matches!(*play_state, PlayState::Playing(_)) {
+                    *play_state = PlayState::Decaying;
+                }
             }
         }
     }
@@ -151,60 +177,106 @@ impl Conn {
         }
     }
 
+    /// Send controller, channel pressure, and pitch bend messages created by user input on this app frame to the selected track's channel.
+    pub fn control_messages(&mut self, state: &State, messages: &[[u8; 3]]) {
+        if let Some(track) = state.music.get_selected_track() {
+            let mut synth = self.synth.lock();
+            for (automation_type, value) in messages
+                .iter()
+                .filter_map(AutomationType::from_midi_message)
+            {
+                let _ = synth.send_event(Self::get_automation_event(
+                    track.channel,
+                    &automation_type,
+                    value,
+                ));
+            }
+        }
+    }
+
     /// Execute a slice of commands sent from `io`.
-    pub fn do_commands(&mut self, commands: &[Command]) {
+    ///
+    /// Returns a list of SoundFonts and tuning files that couldn't be loaded. The channels of the SoundFonts are set to a missing-program state.
+    pub fn do_commands(&mut self, commands: &[Command]) -> Vec<LoadError> {
+        let mut errors = vec![];
         for command in commands.iter() {
             match command {
-                Command::LoadSoundFont { channel, path } => {
-                    match &self.soundfonts.get(path) {
-                        // We already loaded this font.
-                        Some(_) => {
-                            self.set_program_default(*channel, path);
+                Command::LoadSoundFont {
+                    channel,
+                    path,
+                    percussion,
+                } => match self.load_soundfont(*channel, path) {
+                    Ok(()) => {
+                        self.set_program_default(*channel, path, *percussion);
+                    }
+                    Err(error) => {
+                        // Keep the current program, if any. Otherwise, remember the missing program so that the track can be re-pointed to another file.
+                        if !self.state.programs.contains_key(channel) {
+                            self.state.missing_programs.insert(*channel, path.clone());
                         }
-                        // Load the font.
-                        None => match SoundFont::load(&mut File::open(path).unwrap()) {
-                            Ok(font) => {
-                                let banks = SoundFontBanks::new(font, &mut self.synth);
-                                self.soundfonts.insert(path.clone(), banks);
-                                // Set the default program.
-                                self.set_program_default(*channel, path);
-                                // Restore the other programs.
-                                let programs = self.state.programs.clone();
-                                for program in programs.iter().filter(|p| p.0 != channel) {
-                                    if self.soundfonts.contains_key(&program.1.path) {
-                                        let mut synth = self.synth.lock();
-                                        synth
-                                            .program_select(
-                                                *program.0,
-                                                self.soundfonts[&program.1.path].id,
-                                                program.1.bank,
-                                                program.1.preset,
-                                            )
-                                            .unwrap();
-                                    }
-                                }
-                            }
-                            Err(error) => {
-                                panic!("Failed to load SoundFont: {:?}", error)
-                            }
-                        },
+                        errors.push(LoadError::SoundFont(error));
                     }
-                }
+                },
                 Command::SetProgram {
                     channel,
                     path,
                     bank_index,
                     preset_index,
                 } => {
-                    let soundfont = &self.soundfonts[path];
-                    let banks = soundfont.banks.keys().copied().collect::<Vec<u32>>();
-                    let bank = banks[*bank_index];
-                    let preset = soundfont.banks[&bank][*preset_index];
-                    let channel = *channel;
-                    self.set_program(channel, path, bank, preset, soundfont.id);
+                    // The SoundFont might not have been loaded.
+                    if let Some(soundfont) = self.soundfonts.get(path) {
+                        let banks = soundfont.banks.keys().copied().collect::<Vec<u32>>();
+                        // The indices might be from a different version of the SoundFont.
+                        if let Some(bank) = banks.get(*bank_index) {
+                            if let Some(preset) = soundfont.banks[bank].get(*preset_index) {
+                                let channel = *channel;
+                                self.set_program(channel, path, *bank, *preset, soundfont.id);
+                            }
+                        }
+                    }
                 }
                 Command::UnsetProgram { channel } => {
                     self.state.programs.remove(channel);
+                    self.state.missing_programs.remove(channel);
+                }
+                Command::SetTuning {
+                    channel,
+                    scale,
+                    keyboard_mapping,
+                } => {
+                    if scale.is_none() && keyboard_mapping.is_none() {
+                        self.state.tunings.remove(channel);
+                    } else {
+                        match Tuning::new(scale.as_deref(), keyboard_mapping.as_deref()) {
+                            Ok(tuning) => {
+                                self.state.tunings.insert(*channel, tuning);
+                            }
+                            Err((path, error)) => {
+                                let error = if scale.as_ref() == Some(&path) {
+                                    TuningError::Scale {
+                                        channel: *channel,
+                                        path,
+                                        error,
+                                    }
+                                } else {
+                                    TuningError::KeyboardMapping {
+                                        channel: *channel,
+                                        path,
+                                        error,
+                                    }
+                                };
+                                errors.push(LoadError::Tuning(error));
+                                // If the files can't be read but the tuning was loaded from a save file, keep using it.
+                                if !self.state.tunings.get(channel).is_some_and(|t| {
+                                    t.scale_path == *scale
+                                        && t.keyboard_mapping_path == *keyboard_mapping
+                                }) {
+                                    self.state.tunings.remove(channel);
+                                }
+                            }
+                        }
+                    }
+                    self.set_tuning(*channel);
                 }
                 Command::SetGain { gain } => {
                     let mut synth = self.synth.lock();
@@ -213,13 +285,17 @@ impl Conn {
                 }
             }
         }
+        errors
     }
 
     /// Start to play music if music isn't playing. Stop music if music is playing.
-    pub fn set_music(&mut self, state: &State) {
+    ///
+    /// - `state` The state of the app.
+    /// - `count_in` Count-in and pre-roll settings. These are used only if the selected track is armed for real-time recording.
+    pub fn set_music(&mut self, state: &State, count_in: &CountIn) {
         let play_state = *self.play_state.lock();
         match play_state {
-            PlayState::NotPlaying | PlayState::Decaying => self.start_music(state),
+            PlayState::NotPlaying | PlayState::Decaying => self.start_music(state, count_in),
             _ => self.stop_music(&state.music),
         }
     }
@@ -237,23 +313,65 @@ impl Conn {
     }
 
     /// Schedule MIDI events and start to play music.
-    fn start_music(&mut self, state: &State) {
+    fn start_music(&mut self, state: &State, count_in: &CountIn) {
         // Get the start time.
-        let start = state
-            .time
-            .ppq_to_samples(state.time.playback, self.framerate);
+        let playback_start = state.get_playback_start();
+        // If we're about to record, start to play a few beats early.
+        let recording = state.input.armed && state.input.record;
+        let pre_roll_start = if recording {
+            playback_start.saturating_sub(
+                count_in.pre_roll * state.time.get_time_signature(playback_start).get_beat_ppq(),
+            )
+        } else {
+            playback_start
+        };
+        let start = state.time.ppq_to_samples(pre_roll_start, self.framerate);
 
         // Set the playback framerate.
         let mut synth = self.synth.lock();
         synth.set_sample_rate(self.framerate);
+        // Set the pan of each track.
+        for track in state.music.get_playable_tracks().iter() {
+            let _ = synth.send_event(Self::get_pan_event(track));
+        }
         drop(synth);
 
-        let mut midi_event_queue = self.midi_event_queue.lock();
-        // Clear the queue before adding new events.
-        midi_event_queue.clear();
-        // Enqueue note events.
+        let loop_range = state.get_loop_range();
+        let mut midi_event_queue = MidiEventQueue::default();
+        // The time of the last note-off event in PPQ.
+        let mut end = playback_start;
+        // Enqueue automation and note events.
         for track in state.music.get_playable_tracks().iter() {
-            for note in track.get_playback_notes(state.time.playback) {
+            // Enqueue automation events.
+            for automation in track.automation.iter() {
+                for point in automation.get_playback_points(pre_roll_start) {
+                    midi_event_queue.enqueue(
+                        state.time.ppq_to_samples(point.time, self.framerate),
+                        Self::get_automation_event(
+                            track.channel,
+                            &automation.automation_type,
+                            point.value,
+                        ),
+                    );
+                }
+                // Reset the value at the start of each pass through the loop.
+                if let Some(loop_range) = loop_range {
+                    if loop_range[0] > pre_roll_start {
+                        midi_event_queue.enqueue(
+                            state.time.ppq_to_samples(loop_range[0], self.framerate),
+                            Self::get_automation_event(
+                                track.channel,
+                                &automation.automation_type,
+                                automation.get_value(loop_range[0]),
+                            ),
+                        );
+                    }
+                }
+            }
+            for note in track.get_playback_notes(pre_roll_start) {
+                if end < note.end {
+                    end = note.end;
+                }
                 // Note-on event.
                 midi_event_queue.enqueue(
                     state.time.ppq_to_samples(note.start, self.framerate),
@@ -275,7 +393,44 @@ impl Conn {
         }
         // Sort the events by start time.
         midi_event_queue.sort();
-        drop(midi_event_queue);
+        // Loop playback.
+        if let Some(loop_range) = loop_range {
+            let start = state.time.ppq_to_samples(loop_range[0], self.framerate);
+            let end = state.time.ppq_to_samples(loop_range[1], self.framerate);
+            let channels = state
+                .music
+                .get_playable_tracks()
+                .iter()
+                .map(|t| t.channel)
+                .collect();
+            midi_event_queue.set_loop(start, end, channels);
+        }
+        // Keep playing after the last note while recording.
+        if recording {
+            midi_event_queue.set_record();
+        }
+        // Send the queue to the player.
+        if let Some(sender) = self.midi_event_queue.as_ref() {
+            let _ = sender.send(midi_event_queue);
+        }
+
+        // Schedule the metronome clicks. While recording, the metronome clicks until the user stops playback.
+        let end = match loop_range {
+            Some(loop_range) => Some(loop_range[1]),
+            None if recording => None,
+            None => Some(end),
+        };
+        let mut metronome = self.metronome.lock();
+        metronome.schedule(&state.time, pre_roll_start, end, self.framerate);
+        // If we're about to record, count in.
+        metronome.schedule_count_in(
+            &state.time,
+            pre_roll_start,
+            if recording { count_in.count_in } else { 0 },
+            count_in.count_in_bars,
+            self.framerate,
+        );
+        drop(metronome);
 
         // Play music.
         let mut play_state = self.play_state.lock();
@@ -297,20 +452,80 @@ impl Conn {
                     })
                     .is_ok()
             {}
+            // Reset the automated controllers and pitch bend.
+            for automation in track.automation.iter() {
+                let _ = synth.send_event(Self::get_automation_event(
+                    track.channel,
+                    &automation.automation_type,
+                    automation.automation_type.get_default(),
+                ));
+            }
         }
         drop(synth);
+        self.metronome.lock().clear();
         // Let the audio decay.
         let mut play_state = self.play_state.lock();
         *play_state = PlayState::Decaying;
     }
 
+    /// Load a SoundFont if it hasn't been loaded already.
+    fn load_soundfont(&mut self, channel: u8, path: &Path) -> Result<(), SoundFontError> {
+        // We already loaded this font.
+        if self.soundfonts.contains_key(path) {
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(SoundFontError::Missing {
+                channel,
+                path: path.to_path_buf(),
+            });
+        }
+        let mut file = File::open(path).map_err(|error| SoundFontError::Open {
+            channel,
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        })?;
+        let font = SoundFont::load(&mut file).map_err(|error| SoundFontError::Invalid {
+            channel,
+            path: path.to_path_buf(),
+            error: format!("{:?}", error),
+        })?;
+        let banks = SoundFontBanks::new(font, &mut self.synth);
+        self.soundfonts.insert(path.to_path_buf(), banks);
+        // Remember the hash in case the file goes missing.
+        if let Some(hash) = get_content_hash(path) {
+            self.state.hashes.insert(path.to_path_buf(), hash);
+        }
+        // Restore the other programs.
+        let programs = self.state.programs.clone();
+        for program in programs.iter().filter(|p| *p.0 != channel) {
+            if self.soundfonts.contains_key(&program.1.path) {
+                let mut synth = self.synth.lock();
+                synth
+                    .program_select(
+                        *program.0,
+                        self.soundfonts[&program.1.path].id,
+                        program.1.bank,
+                        program.1.preset,
+                    )
+                    .unwrap();
+            }
+        }
+        Ok(())
+    }
+
     /// Set the synthesizer program to a default program.
-    fn set_program_default(&mut self, channel: u8, path: &Path) {
+    /// If `percussion` is true and the SoundFont has a percussion bank, the program is in the percussion bank.
+    fn set_program_default(&mut self, channel: u8, path: &Path, percussion: bool) {
         let soundfont = &self.soundfonts[path];
         // Get the bank info.
         let mut banks: Vec<u32> = soundfont.banks.keys().copied().collect();
         banks.sort();
-        let bank = banks[0];
+        let bank = if percussion && banks.contains(&PERCUSSION_BANK) {
+            PERCUSSION_BANK
+        } else {
+            banks[0]
+        };
         let preset = soundfont.banks[&bank][0];
         // Select the default program.
         let id = self.soundfonts[path].id;
@@ -344,16 +559,67 @@ impl Conn {
             };
             // Remember the program.
             self.state.programs.insert(channel, program);
+            self.state.missing_programs.remove(&channel);
+        }
+    }
+
+    /// Returns a command that sets a channel's tuning to its current tuning.
+    pub fn get_tuning_command(&self, channel: u8) -> Command {
+        match self.state.tunings.get(&channel) {
+            Some(tuning) => Command::SetTuning {
+                channel,
+                scale: tuning.scale_path.clone(),
+                keyboard_mapping: tuning.keyboard_mapping_path.clone(),
+            },
+            None => Command::SetTuning {
+                channel,
+                scale: None,
+                keyboard_mapping: None,
+            },
         }
     }
 
-    pub fn start_export(&mut self, state: &State, paths_state: &PathsState) {
+    /// Retune a synthesizer channel. If the channel doesn't have a tuning, it is set to 12-tone equal temperament.
+    fn set_tuning(&mut self, channel: u8) {
+        let pitches = self
+            .state
+            .tunings
+            .get(&channel)
+            .cloned()
+            .unwrap_or_default()
+            .get_pitches();
+        // Each channel has its own tuning program so that channels can be retuned independently.
+        if let Ok(mut tuning) = oxisynth::Tuning::new(0, channel as u32) {
+            tuning.set_all(&pitches);
+            let mut synth = self.synth.lock();
+            let _ = synth.channel_set_tuning(channel, tuning);
+        }
+    }
+
+    /// Start exporting audio on a separate thread. Returns the handle of the thread.
+    /// The thread returns an error message if a file couldn't be written.
+    pub fn start_export(
+        &mut self,
+        state: &State,
+        paths_state: &PathsState,
+    ) -> JoinHandle<Result<(), String>> {
         let mut exportables = vec![];
         let tracks = state.music.get_playable_tracks();
         self.set_export_framerate();
 
+        let mut metronome = self.metronome.lock().clone();
+        let export_metronome = metronome.enabled && metronome.export;
+        // The time of the last note-off event in PPQ.
+        let end = tracks
+            .iter()
+            .filter_map(|t| t.notes.iter().map(|n| n.end).max())
+            .max()
+            .unwrap_or(0);
+        metronome.schedule(&state.time, 0, Some(end), self.exporter.framerate.get_f());
+
         // Export each track as a separate file.
         if self.exporter.multi_file {
+            let mut total_samples = 0;
             for track in tracks {
                 let mut events = MidiEventQueue::default();
                 let mut t1 = 0;
@@ -361,11 +627,24 @@ impl Conn {
                 self.enqueue_track_events(track, &state.time, &mut events, &mut t1, gain);
                 events.sort();
                 let suffix = Some(self.get_export_file_suffix(track));
+                if total_samples < t1 {
+                    total_samples = t1;
+                }
                 // Add an exportable.
                 exportables.push(Exportable {
                     events,
                     total_samples: t1,
                     suffix,
+                    metronome: None,
+                });
+            }
+            // Export the metronome as a click track.
+            if export_metronome {
+                exportables.push(Exportable {
+                    events: MidiEventQueue::default(),
+                    total_samples,
+                    suffix: Some("metronome".to_string()),
+                    metronome: Some(metronome),
                 });
             }
         }
@@ -383,6 +662,11 @@ impl Conn {
                 events,
                 total_samples: t1,
                 suffix: None,
+                metronome: if export_metronome {
+                    Some(metronome)
+                } else {
+                    None
+                },
             });
         }
 
@@ -412,6 +696,21 @@ impl Conn {
         gain: f32,
     ) {
         let framerate = self.exporter.framerate.get_f();
+        // Set the pan.
+        events.enqueue(0, Self::get_pan_event(track));
+        // Set the automation.
+        for automation in track.automation.iter() {
+            for point in automation.get_playback_points(0) {
+                events.enqueue(
+                    time.ppq_to_samples(point.time, framerate),
+                    Self::get_automation_event(
+                        track.channel,
+                        &automation.automation_type,
+                        point.value,
+                    ),
+                );
+            }
+        }
         for note in track.notes.iter() {
             // Note-on.
             events.enqueue(
@@ -437,6 +736,7 @@ impl Conn {
         }
     }
 
+    /// Export audio, stop exporting, and reset the synth's sample rate.
     fn export(
         mut exportables: Vec<Exportable>,
         export_state: SharedExportState,
@@ -444,7 +744,29 @@ impl Conn {
         exporter: Exporter,
         path: PathBuf,
         player_framerate: f32,
-    ) {
+    ) -> Result<(), String> {
+        let result =
+            Self::write_exportables(&mut exportables, &export_state, &synth, &exporter, &path);
+        Self::set_export_state(&export_state, ExportState::NotExporting);
+        synth.lock().set_sample_rate(player_framerate);
+        result
+    }
+
+    /// Synthesize each exportable and write it to disk. Returns an error message if a file couldn't be written.
+    fn write_exportables(
+        exportables: &mut [Exportable],
+        export_state: &SharedExportState,
+        synth: &SharedSynth,
+        exporter: &Exporter,
+        path: &Path,
+    ) -> Result<(), String> {
+        let directory = path
+            .parent()
+            .ok_or_else(|| format!("Invalid export path: {}", path.display()))?;
+        let filename = path
+            .file_stem()
+            .and_then(|filename| filename.to_str())
+            .ok_or_else(|| format!("Invalid export filename: {}", path.display()))?;
         let mut decayer = Decayer::default();
         let extension: Extension = exporter.export_type.get().into();
         for exportable in exportables.iter_mut() {
@@ -453,65 +775,73 @@ impl Conn {
             let mut left = vec![0.0f32; total_samples as usize];
             let mut right = vec![0.0f32; total_samples as usize];
             // Set the initial wav export state.
-            Self::set_export_state_wav(exportable, &export_state, 0);
+            Self::set_export_state_wav(exportable, export_state, 0);
             let mut synth = synth.lock();
             for t in 0..=total_samples {
                 // Get and send each event at this time.
-                for event in exportable.events.dequeue(t).iter() {
-                    let _ = synth.send_event(*event);
+                for event in exportable.events.dequeue(t) {
+                    let _ = synth.send_event(event.event);
                 }
                 // Set the export state.
-                Self::set_export_state_wav(exportable, &export_state, t);
+                Self::set_export_state_wav(exportable, export_state, t);
                 // We are iterating to `total_samples` in order to get events at t=1.
                 if t < total_samples {
+                    let (mut l, mut r) = synth.read_next();
+                    // Add the metronome click.
+                    if let Some(metronome) = exportable.metronome.as_mut() {
+                        let click = metronome.next_sample(t);
+                        l += click;
+                        r += click;
+                    }
                     let t = t as usize;
-                    (left[t], right[t]) = synth.read_next();
+                    (left[t], right[t]) = (l, r);
                 }
             }
             // Append decaying silence.
-            Self::set_export_state(&export_state, ExportState::AppendingDecay);
+            Self::set_export_state(export_state, ExportState::AppendingDecay);
             decayer.decaying = true;
             while decayer.decaying {
                 decayer.decay_two_channels(&mut left, &mut right, &mut synth);
             }
             // Convert.
-            Self::set_export_state(&export_state, ExportState::WritingToDisk);
-            let filename = path.file_stem().unwrap().to_str().unwrap();
+            Self::set_export_state(export_state, ExportState::WritingToDisk);
             let extension = extension.to_str(true);
             let path = match &exportable.suffix {
-                Some(suffix) => path
-                    .parent()
-                    .unwrap()
-                    .join(format!("{}_{}{}", filename, suffix, extension))
-                    .to_path_buf(),
-                None => path
-                    .parent()
-                    .unwrap()
-                    .join(format!("{}{}", filename, extension))
-                    .to_path_buf(),
+                Some(suffix) => directory.join(format!("{}_{}{}", filename, suffix, extension)),
+                None => directory.join(format!("{}{}", filename, extension)),
             };
             let audio = [left, right];
             match &exporter.export_type.get() {
-                ExportType::Mid => {
-                    panic!("Tried exporting a .mid from the synthesizer")
-                }
+                ExportType::Mid => Err("Can't export a .mid file from the synthesizer".to_string()),
                 // Export to a .wav file.
-                ExportType::Wav => {
-                    exporter.wav(&path, &audio);
-                }
-                ExportType::MP3 => {
-                    exporter.mp3(&path, &audio);
-                }
-                ExportType::Ogg => {
-                    exporter.ogg(&path, &audio);
-                }
+                ExportType::Wav => exporter.wav(&path, &audio),
+                ExportType::MP3 => exporter.mp3(&path, &audio),
+                ExportType::Ogg => exporter.ogg(&path, &audio),
                 ExportType::Flac => exporter.flac(&path, &audio),
-            }
+            }?;
             // Done.
-            Self::set_export_state(&export_state, ExportState::Done);
+            Self::set_export_state(export_state, ExportState::Done);
         }
-        Self::set_export_state(&export_state, ExportState::NotExporting);
-        synth.lock().set_sample_rate(player_framerate);
+        Ok(())
+    }
+
+    /// Start a new audio output.
+    fn set_player(&mut self) {
+        // Drop the current stream before opening a new one.
+        self.player = None;
+        let (sender, receiver) = channel();
+        self.midi_event_queue = Some(sender);
+        let player = Player::new(
+            &self.audio_devices.settings,
+            receiver,
+            Arc::clone(&self.synth),
+            Arc::clone(&self.sample),
+            Arc::clone(&self.play_state),
+            Arc::clone(&self.metronome),
+        );
+        // Get the framerate.
+        self.framerate = player.framerate as f32;
+        self.player = Some(player);
     }
 
     /// Set the exporter's framerate.
@@ -540,6 +870,35 @@ impl Conn {
         *export_state = state;
     }
 
+    /// Returns a control change event that sets the pan of a track.
+    fn get_pan_event(track: &MidiTrack) -> MidiEvent {
+        MidiEvent::ControlChange {
+            channel: track.channel,
+            ctrl: PAN_CONTROLLER,
+            value: track.pan,
+        }
+    }
+
+    /// Returns a control change or pitch bend event that sets an automation value.
+    fn get_automation_event(
+        channel: u8,
+        automation_type: &AutomationType,
+        value: u16,
+    ) -> MidiEvent {
+        match automation_type {
+            AutomationType::Controller(ctrl) => MidiEvent::ControlChange {
+                channel,
+                ctrl: *ctrl,
+                value: value as u8,
+            },
+            AutomationType::ChannelPressure => MidiEvent::ChannelPressure {
+                channel,
+                value: value as u8,
+            },
+            AutomationType::PitchBend => MidiEvent::PitchBend { channel, value },
+        }
+    }
+
     fn get_export_file_suffix(&self, track: &MidiTrack) -> String {
         // Get the path for this track.
         match self.exporter.multi_file_suffix.get() {
@@ -26,10 +26,7 @@ impl Default for Decayer {
 
 impl Decayer {
     pub fn decay_shared(&mut self, synth: &SharedSynth, len: usize) {
-        for sample in self.buffer[0..Self::get_len(len)].chunks_mut(2) {
-            let mut synth = synth.lock();
-            synth.write(sample);
-        }
+        synth.lock().write(&mut self.buffer[0..Self::get_len(len)]);
         self.set_decaying(len);
     }
 
@@ -1,5 +1,6 @@
 use common::open_file::Extension;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 /// This determines what we're exporting to.
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize, Serialize, Default, Hash)]
@@ -23,3 +24,19 @@ impl From<ExportType> for Extension {
         }
     }
 }
+
+impl FromStr for ExportType {
+    type Err = String;
+
+    /// Parse a file extension, with or without a leading ".", e.g. "ogg" or ".ogg".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_start_matches('.').to_lowercase().as_str() {
+            "wav" => Ok(Self::Wav),
+            "mid" | "midi" => Ok(Self::Mid),
+            "mp3" => Ok(Self::MP3),
+            "ogg" => Ok(Self::Ogg),
+            "flac" => Ok(Self::Flac),
+            _ => Err(format!("Unknown export format: {}", s)),
+        }
+    }
+}
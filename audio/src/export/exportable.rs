@@ -1,7 +1,10 @@
 use crate::midi_event_queue::MidiEventQueue;
+use crate::Metronome;
 
 pub(crate) struct Exportable {
     pub events: MidiEventQueue,
     pub total_samples: u64,
     pub suffix: Option<String>,
+    /// If not None, the metronome clicks are mixed into the exported audio.
+    pub metronome: Option<Metronome>,
 }
@@ -3,7 +3,10 @@ use crate::{AudioBuffer, SynthState};
 use chrono::Datelike;
 use chrono::Local;
 use common::IndexedValues;
-use common::{Index, Music, Time, U64orF32, DEFAULT_FRAMERATE, PPQ_F, PPQ_U};
+use common::{
+    AutomationType, Index, MidiTrack, Music, Time, U64orF32, DEFAULT_FRAMERATE, PAN_CONTROLLER,
+    PERCUSSION_CHANNEL, PPQ_U,
+};
 use flacenc::bitsink::ByteSink;
 use flacenc::component::BitRepr;
 use flacenc::config::Encoder as FlacEncoder;
@@ -12,13 +15,15 @@ use flacenc::source::MemSource;
 use hound::{SampleFormat, WavSpec, WavWriter};
 use id3::{Tag, TagLike, Version};
 use metaflac::Tag as FlacTag;
-use midly::num::{u15, u24, u28, u4};
+use midly::num::{u14, u15, u24, u28, u4};
 use midly::{
-    write_std, Format, Header, MetaMessage, MidiMessage, Timing, Track, TrackEvent, TrackEventKind,
+    write_std, Format, Header, MetaMessage, MidiMessage, PitchBend, Timing, Track, TrackEvent,
+    TrackEventKind,
 };
 use mp3lame_encoder::*;
 use oggvorbismeta::*;
 use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::io::{Cursor, Write};
@@ -29,6 +34,8 @@ use vorbis_encoder::Encoder;
 const NUM_CHANNELS: usize = 2;
 /// Conversion factor for f32 to i16.
 const F32_TO_I16: f32 = 32767.5;
+/// All possible audio framerates.
+pub const FRAMERATES: [u64; 3] = [22050, 44100, 48000];
 /// An ordered list of MP3 bit rates. We can't use `IndexedValues` because this enum isn't serializable.
 pub const MP3_BIT_RATES: [Bitrate; 16] = [
     Bitrate::Kbps8,
@@ -192,130 +199,187 @@ impl Default for Exporter {
 }
 
 impl Exporter {
+    /// Set the export type.
+    pub fn set_export_type(&mut self, export_type: ExportType) {
+        if let Some(index) = self
+            .export_type
+            .get_values()
+            .0
+            .iter()
+            .position(|e| *e == export_type)
+        {
+            self.export_type.index.set(index);
+        }
+    }
+
+    /// Returns the .mid channel of a track.
+    /// Percussion tracks are on the General MIDI percussion channel.
+    /// Other tracks can't be on the percussion channel, so if needed they're moved to an unused channel.
+    fn get_mid_channel(midi_track: &MidiTrack, music: &Music) -> u4 {
+        if midi_track.percussion {
+            u4::from(PERCUSSION_CHANNEL)
+        } else if midi_track.channel != PERCUSSION_CHANNEL {
+            u4::from(midi_track.channel)
+        } else {
+            let channels: Vec<u8> = music
+                .midi_tracks
+                .iter()
+                .filter(|t| !t.percussion)
+                .map(|t| t.channel)
+                .collect();
+            u4::from(
+                (0..16)
+                    .find(|c| *c != PERCUSSION_CHANNEL && !channels.contains(c))
+                    .unwrap_or(midi_track.channel),
+            )
+        }
+    }
+
     /// Export to a .mid file.
     /// - `path` Output to this path.
     /// - `music` This is what we're saving.
+    /// - `time` This is used for the tempo map and the time signatures.
     /// - `synth_state` We need this for its present names.
-    /// - `text` This is is used for metadata.
-    /// - `export_settings` .mid export settings.
-    pub fn mid(&self, path: &Path, music: &Music, time: &Time, synth_state: &SynthState) {
+    ///
+    /// Returns an error message if the file couldn't be written.
+    pub fn mid(
+        &self,
+        path: &Path,
+        music: &Music,
+        time: &Time,
+        synth_state: &SynthState,
+    ) -> Result<(), String> {
         // Set the name of the music.
-        let mut meta_messages = vec![MetaMessage::Text(self.metadata.title.as_bytes())];
+        let mut meta_messages = vec![(0, MetaMessage::Text(self.metadata.title.as_bytes()))];
         let mut copyright = vec![];
-        // Set the tempo.
-        meta_messages.push(MetaMessage::Tempo(u24::from(
-            (60000000 / time.bpm.get_u()) as u32,
-        )));
-        // Set the time signature.
-        meta_messages.push(MetaMessage::TimeSignature(4, 2, 24, 8));
+        // Set the initial tempo.
+        meta_messages.push((0, Self::get_tempo(&time.bpm)));
+        // Set the tempo changes.
+        for tempo in time.tempos.iter() {
+            meta_messages.push((tempo.time, Self::get_tempo(&tempo.bpm)));
+        }
+        // Set the time signatures.
+        for time_signature in time.get_time_signatures().iter() {
+            meta_messages.push((
+                time_signature.time,
+                MetaMessage::TimeSignature(
+                    time_signature.numerator,
+                    time_signature.denominator.trailing_zeros() as u8,
+                    // MIDI clocks per metronome click. There are 24 MIDI clocks per quarter note.
+                    (time_signature.get_click_ppq() * 24 / PPQ_U) as u8,
+                    8,
+                ),
+            ));
+        }
         // Send copyright.
         if self.copyright {
             if let Some(artist) = &self.metadata.artist {
                 copyright.append(&mut self.get_copyright(artist).as_bytes().to_vec());
-                meta_messages.push(MetaMessage::Copyright(&copyright));
+                meta_messages.push((0, MetaMessage::Copyright(&copyright)));
             }
         }
 
-        let mut tracks = vec![];
-        let mut track_0 = Track::new();
-        for (i, midi_track) in music.midi_tracks.iter().enumerate() {
+        // The first track has all of the meta messages.
+        let mut tracks = vec![Self::get_track(
+            meta_messages
+                .into_iter()
+                .map(|(t, m)| (t, TrackEventKind::Meta(m)))
+                .collect(),
+        )];
+        for midi_track in music.midi_tracks.iter() {
             if let Some(program) = synth_state.programs.get(&midi_track.channel) {
-                // Get track 0 or start a new track.
-                let mut track = Vec::new();
-
-                if i == 0 {
-                    for meta_message in meta_messages.iter() {
-                        track_0.push(TrackEvent {
-                            delta: 0.into(),
-                            kind: TrackEventKind::Meta(*meta_message),
-                        })
-                    }
-                }
-
-                let channel = u4::from(midi_track.channel);
-
-                // Set the program name.
-                track.push(TrackEvent {
-                    delta: 0.into(),
-                    kind: TrackEventKind::Meta(MetaMessage::ProgramName(
-                        program.preset_name.as_bytes(),
-                    )),
-                });
-                // Change the program.
-                track.push(TrackEvent {
-                    delta: 0.into(),
-                    kind: TrackEventKind::Midi {
-                        channel,
-                        message: MidiMessage::ProgramChange {
-                            program: program.preset.into(),
+                let channel = Self::get_mid_channel(midi_track, music);
+                let mut events = vec![
+                    // Set the program name.
+                    (
+                        0,
+                        TrackEventKind::Meta(MetaMessage::ProgramName(
+                            program.preset_name.as_bytes(),
+                        )),
+                    ),
+                    // Change the program.
+                    (
+                        0,
+                        TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::ProgramChange {
+                                program: program.preset.into(),
+                            },
                         },
-                    },
-                });
-
-                // Iterate through the notes.
-                let mut notes = midi_track.notes.clone();
-                // Sort the notes by start time.
-                notes.sort_by(|a, b| a.start.cmp(&b.start));
-                // Get the start and end time.
-                let t0 = notes.iter().map(|n| n.start).min().unwrap();
-                // The delta is the first note.
-                let mut dt = t0;
-                let t1 = notes.iter().map(|n| n.end).max().unwrap();
-                // Iterate through all pulses.
-                for t in t0..t1 {
-                    // Get all note-on events.
-                    for note in notes.iter().filter(|n| n.start == t) {
-                        let delta = Self::get_delta_time(&mut dt);
-                        track.push(TrackEvent {
-                            delta,
-                            kind: TrackEventKind::Midi {
-                                channel,
-                                message: MidiMessage::NoteOn {
-                                    key: note.note.into(),
-                                    vel: note.velocity.into(),
-                                },
+                    ),
+                    // Set the pan.
+                    (
+                        0,
+                        TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::Controller {
+                                controller: PAN_CONTROLLER.into(),
+                                value: midi_track.pan.into(),
                             },
-                        });
-                    }
-                    // Get all note-off events.
-                    for note in notes.iter().filter(|n| n.end == t) {
-                        let delta = Self::get_delta_time(&mut dt);
-                        track.push(TrackEvent {
-                            delta,
-                            kind: TrackEventKind::Midi {
-                                channel,
-                                message: MidiMessage::NoteOff {
-                                    key: note.note.into(),
-                                    vel: note.velocity.into(),
-                                },
+                        },
+                    ),
+                ];
+                // Add the automation events first so that they apply to notes at the same time.
+                for automation in midi_track.automation.iter() {
+                    for point in automation.points.iter() {
+                        let message = match automation.automation_type {
+                            AutomationType::Controller(controller) => MidiMessage::Controller {
+                                controller: controller.into(),
+                                value: (point.value as u8).into(),
+                            },
+                            AutomationType::ChannelPressure => MidiMessage::ChannelAftertouch {
+                                vel: (point.value as u8).into(),
                             },
-                        });
+                            AutomationType::PitchBend => MidiMessage::PitchBend {
+                                bend: PitchBend(u14::from(point.value)),
+                            },
+                        };
+                        events.push((point.time, TrackEventKind::Midi { channel, message }));
                     }
                 }
-                // End the track.
-                track.push(TrackEvent {
-                    delta: 0.into(),
-                    kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
-                });
+                // Add the note-off events so that they sort before note-on events at the same time.
+                for note in midi_track.notes.iter() {
+                    events.push((
+                        note.end,
+                        TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::NoteOff {
+                                key: note.note.into(),
+                                vel: note.velocity.into(),
+                            },
+                        },
+                    ));
+                }
+                for note in midi_track.notes.iter() {
+                    events.push((
+                        note.start,
+                        TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::NoteOn {
+                                key: note.note.into(),
+                                vel: note.velocity.into(),
+                            },
+                        },
+                    ));
+                }
                 // Add the track.
-                tracks.push(track);
+                tracks.push(Self::get_track(events));
             }
         }
         // Create the header.
         let header = Header::new(Format::Parallel, Timing::Metrical(u15::from(PPQ_U as u16)));
         // Write the file.
         let mut buffer: Vec<u8> = vec![];
-        if let Err(error) = write_std(&header, tracks.iter(), &mut buffer) {
-            panic!("Error writing {:?} {:?}", path, error);
-        }
-        Self::write_file(path, &buffer);
+        write_std(&header, tracks.iter(), &mut buffer)
+            .map_err(|error| Self::get_error(path, error))?;
+        Self::write_file(path, &buffer)
     }
 
     /// Export to a .wav file.
     ///
     /// - `path` The output path.
     /// - `buffer` A buffer of wav data.
-    pub(crate) fn wav(&self, path: &Path, buffer: &AudioBuffer) {
+    pub(crate) fn wav(&self, path: &Path, buffer: &AudioBuffer) -> Result<(), String> {
         // Get the spec.
         let spec = WavSpec {
             channels: NUM_CHANNELS as u16,
@@ -324,40 +388,52 @@ impl Exporter {
             sample_format: SampleFormat::Int,
         };
         // Write.
-        let mut writer = WavWriter::create(path, spec).unwrap();
+        let mut writer =
+            WavWriter::create(path, spec).map_err(|error| Self::get_error(path, error))?;
         let mut i16_writer = writer.get_i16_writer(buffer[0].len() as u32 * (NUM_CHANNELS as u32));
         for (l, r) in buffer[0].iter().zip(buffer[1].iter()) {
             i16_writer.write_sample(Self::to_i16(l));
             i16_writer.write_sample(Self::to_i16(r));
         }
-        i16_writer.flush().unwrap();
-        writer.finalize().unwrap();
+        i16_writer
+            .flush()
+            .map_err(|error| Self::get_error(path, error))?;
+        writer
+            .finalize()
+            .map_err(|error| Self::get_error(path, error))
     }
 
     /// Export to a .mp3 file.
     ///
     /// - `path` The output path.
     /// - `buffer` A buffer of wav data.
-    pub(crate) fn mp3<'a, T: 'a>(&self, path: &Path, buffer: &'a [Vec<T>; NUM_CHANNELS])
+    pub(crate) fn mp3<'a, T: 'a>(
+        &self,
+        path: &Path,
+        buffer: &'a [Vec<T>; NUM_CHANNELS],
+    ) -> Result<(), String>
     where
         mp3lame_encoder::DualPcm<'a, T>: mp3lame_encoder::EncoderInput,
     {
         // Create the encoder.
-        let mut mp3_encoder = Builder::new().expect("Create LAME builder");
+        let mut mp3_encoder = Builder::new()
+            .ok_or_else(|| format!("Error writing {:?}: Couldn't create the LAME builder", path))?;
         mp3_encoder
             .set_num_channels(NUM_CHANNELS as u8)
-            .expect("Set channels");
+            .map_err(|error| Self::get_error(path, error))?;
         mp3_encoder
             .set_sample_rate(self.framerate.get_u() as u32)
-            .expect("Set sample rate");
+            .map_err(|error| Self::get_error(path, error))?;
         mp3_encoder
             .set_brate(MP3_BIT_RATES[self.mp3_bit_rate.get()])
-            .expect("Set bitrate");
+            .map_err(|error| Self::get_error(path, error))?;
         mp3_encoder
             .set_quality(MP3_QUALITIES[self.mp3_quality.get()])
-            .expect("Set quality");
+            .map_err(|error| Self::get_error(path, error))?;
         // Build the encoder.
-        let mut mp3_encoder = mp3_encoder.build().expect("To initialize LAME encoder");
+        let mut mp3_encoder = mp3_encoder
+            .build()
+            .map_err(|error| Self::get_error(path, error))?;
         // Get the input.
         let input = DualPcm {
             left: &buffer[0],
@@ -368,18 +444,18 @@ impl Exporter {
         // Get the size.
         let encoded_size = mp3_encoder
             .encode(input, mp3_out_buffer.spare_capacity_mut())
-            .expect("To encode");
+            .map_err(|error| Self::get_error(path, error))?;
         unsafe {
             mp3_out_buffer.set_len(mp3_out_buffer.len().wrapping_add(encoded_size));
         }
         let encoded_size = mp3_encoder
             .flush::<FlushNoGap>(mp3_out_buffer.spare_capacity_mut())
-            .expect("To flush");
+            .map_err(|error| Self::get_error(path, error))?;
         unsafe {
             mp3_out_buffer.set_len(mp3_out_buffer.len().wrapping_add(encoded_size));
         }
         // Write the file.
-        Self::write_file(path, &mp3_out_buffer);
+        Self::write_file(path, &mp3_out_buffer)?;
         // Write the tag.
         let time = Local::now();
         let mut tag = Tag::new();
@@ -400,16 +476,15 @@ impl Exporter {
         if let Some(track_number) = &self.metadata.track_number {
             tag.set_track(*track_number);
         }
-        if let Err(error) = tag.write_to_path(path, Version::Id3v24) {
-            panic!("Error writing ID3 tag to {:?}: {}", path, error);
-        }
+        tag.write_to_path(path, Version::Id3v24)
+            .map_err(|error| Self::get_error(path, error))
     }
 
     /// Export to an .ogg file.
     ///
     /// - `path` The output path.
     /// - `buffer` A buffer of wav data.
-    pub(crate) fn ogg(&self, path: &Path, buffer: &AudioBuffer) {
+    pub(crate) fn ogg(&self, path: &Path, buffer: &AudioBuffer) -> Result<(), String> {
         let mut samples = vec![];
         for (l, r) in buffer[0].iter().zip(buffer[1].iter()) {
             samples.push(Self::to_i16(l));
@@ -420,10 +495,10 @@ impl Exporter {
             self.framerate.get_u(),
             (self.ogg_quality.get() as f32 / 9.0) * 1.2 - 0.2,
         )
-        .expect("Error creating .ogg file encoder.");
+        .map_err(|error| Self::get_error(path, error))?;
         let samples = encoder
             .encode(&samples)
-            .expect("Error encoding .ogg samples.");
+            .map_err(|error| Self::get_error(path, error))?;
         // Get a cursor.
         let cursor = Cursor::new(&samples);
         // Write the comments.
@@ -453,13 +528,13 @@ impl Exporter {
         let mut out = vec![];
         replace_comment_header(cursor, comments)
             .read_to_end(&mut out)
-            .expect("Error reading cursor.");
+            .map_err(|error| Self::get_error(path, error))?;
         // Write the file.
-        Self::write_file(path, &out);
+        Self::write_file(path, &out)
     }
 
     /// Encode to flac.
-    pub(crate) fn flac(&self, path: &Path, buffer: &AudioBuffer) {
+    pub(crate) fn flac(&self, path: &Path, buffer: &AudioBuffer) -> Result<(), String> {
         // Convert to i32.
         let mut samples = vec![];
         for (left, right) in buffer[0].iter().zip(buffer[1].iter()) {
@@ -472,11 +547,14 @@ impl Exporter {
         match encode_with_fixed_block_size(&config, source, config.block_sizes[0]) {
             Ok(flac_stream) => {
                 let mut sink = ByteSink::new();
-                flac_stream.write(&mut sink).unwrap();
+                flac_stream
+                    .write(&mut sink)
+                    .map_err(|error| Self::get_error(path, error))?;
                 // Write the file.
-                Self::write_file(path, sink.as_slice());
+                Self::write_file(path, sink.as_slice())?;
                 // Write the tag.
-                let mut tag = FlacTag::read_from_path(path).unwrap();
+                let mut tag =
+                    FlacTag::read_from_path(path).map_err(|error| Self::get_error(path, error))?;
                 tag.set_vorbis("title", vec![self.metadata.title.clone()]);
                 tag.set_vorbis("date", vec![Local::now().year().to_string()]);
                 if let Some(artist) = &self.metadata.artist {
@@ -498,32 +576,54 @@ impl Exporter {
                     tag.set_vorbis("description", vec![comment.clone()]);
                 }
                 // Save the tag.
-                tag.save().unwrap();
+                tag.save().map_err(|error| Self::get_error(path, error))
             }
-            Err(error) => panic!("Error encoding flac: {:?}", error),
+            Err(error) => Err(Self::get_error(path, error)),
         }
     }
 
     /// Write samples to a file.
-    fn write_file(path: &Path, samples: &[u8]) {
+    fn write_file(path: &Path, samples: &[u8]) -> Result<(), String> {
         let mut file = OpenOptions::new()
             .write(true)
             .append(false)
             .truncate(true)
             .create(true)
             .open(path)
-            .expect("Error opening file {:?}");
+            .map_err(|error| Self::get_error(path, error))?;
         file.write_all(samples)
-            .expect("Failed to write samples to file.");
+            .map_err(|error| Self::get_error(path, error))
+    }
+
+    /// Returns an error message for a file that couldn't be written.
+    fn get_error<E: Debug>(path: &Path, error: E) -> String {
+        format!("Error writing {:?}: {:?}", path, error)
+    }
+
+    /// Converts a list of events with absolute times in PPQ into a track with delta times.
+    /// Events that occur at the same time stay in the same order.
+    fn get_track(mut events: Vec<(u64, TrackEventKind<'_>)>) -> Track<'_> {
+        events.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut t0 = 0;
+        let mut track = Track::new();
+        for (t, kind) in events {
+            track.push(TrackEvent {
+                delta: u28::from((t - t0) as u32),
+                kind,
+            });
+            t0 = t;
+        }
+        // End the track.
+        track.push(TrackEvent {
+            delta: 0.into(),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+        track
     }
 
-    /// Converts a PPQ value into a MIDI time delta and resets `ppq` to zero.
-    fn get_delta_time(ppq: &mut u64) -> u28 {
-        // Get the dt.
-        let dt = (*ppq as f32 / PPQ_F) as u32;
-        // Reset the PPQ value.
-        *ppq = 0;
-        u28::from(dt)
+    /// Returns a tempo meta message.
+    fn get_tempo(bpm: &U64orF32) -> MetaMessage<'static> {
+        MetaMessage::Tempo(u24::from((60000000 / bpm.get_u()) as u32))
     }
 
     /// Converts an f32 sample to an i16 sample.
@@ -1,29 +1,45 @@
 //! This crate handles all audio output in Cacophony:
 //!
-//! - `Player` handles the cpal audio output stream.
+//! - `Player` handles the cpal audio output stream, or plays audio without an audio device.
 //! - `Conn` manages the connection between external crates (command input), the synthesizer, and the audio player.
 //! - `Exporter` handles all exporting to disk.
+//! - `Metronome` generates click sounds on each beat.
 //!
 //! Various data structs are shared in a Arc<Mutex<T>> format. These aren't a unified struct because they need to be locked at different times.
+//! The queue of MIDI events is the exception: `Conn` sends it to the audio callback, which owns it, so the callback never waits for it.
 //!
 //! As far as external crates are concerned, it's only necessary to create a new Conn: `Conn::default()`.
 
+pub mod audio_devices;
+#[cfg(feature = "bench")]
+pub mod bench;
 mod command;
 mod conn;
 mod decayer;
 pub mod export;
 pub mod exporter;
+mod load_error;
+mod metronome;
 pub(crate) mod midi_event_queue;
 pub mod play_state;
 mod player;
 mod program;
+mod soundfont_error;
 mod synth_state;
+mod thread_output;
 pub(crate) mod timed_midi_event;
+mod tuning_error;
 mod types;
 pub use crate::command::Command;
 pub use crate::conn::Conn;
+pub use crate::load_error::LoadError;
+pub use crate::metronome::Metronome;
 use crate::program::Program;
+pub use crate::soundfont_error::SoundFontError;
 pub use crate::synth_state::SynthState;
-pub(crate) use crate::types::{AudioBuffer, SharedMidiEventQueue, SharedSynth};
+pub use crate::tuning_error::TuningError;
+pub(crate) use crate::types::{
+    AudioBuffer, MidiEventQueueReceiver, MidiEventQueueSender, SharedMetronome, SharedSynth,
+};
 pub use crate::types::{AudioMessage, CommandsMessage, SharedExportState, SharedPlayState};
 use player::Player;
@@ -0,0 +1,21 @@
+use crate::{SoundFontError, TuningError};
+use std::path::Path;
+
+/// An error that occurred while loading a file that a track needs.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum LoadError {
+    /// A SoundFont couldn't be loaded.
+    SoundFont(SoundFontError),
+    /// A tuning couldn't be loaded.
+    Tuning(TuningError),
+}
+
+impl LoadError {
+    /// Returns the path to the file that couldn't be loaded.
+    pub fn get_path(&self) -> &Path {
+        match self {
+            Self::SoundFont(error) => error.get_path(),
+            Self::Tuning(error) => error.get_path(),
+        }
+    }
+}
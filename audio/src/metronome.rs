@@ -0,0 +1,276 @@
+use common::{Index, Time, MAX_VOLUME, PPQ_F};
+use std::f32::consts::TAU;
+
+/// The frequency of a click in Hz.
+const CLICK_FREQUENCY: f32 = 1000.0;
+/// The frequency of an accented click in Hz.
+const ACCENT_FREQUENCY: f32 = 1500.0;
+/// The duration of a click in seconds.
+const CLICK_DURATION: f32 = 0.03;
+/// The amplitude of a click at max gain.
+const CLICK_AMPLITUDE: f32 = 0.5;
+
+/// Clicks that repeat forever after the last scheduled click. The tempo and time signature are constant.
+#[derive(Clone, Copy)]
+struct Repeat {
+    /// The time of the first repeated click in number of samples.
+    start: u64,
+    /// The duration of a beat in number of samples.
+    beat_samples: f64,
+    /// The index of the first repeated click in its bar.
+    beat: u64,
+    /// The number of beats per bar.
+    numerator: u64,
+    /// If true, the first beat of each bar is accented.
+    accent: bool,
+}
+
+/// A schedule of clicks.
+#[derive(Clone, Default)]
+struct Clicks {
+    /// The time of each click in number of samples, and whether the click is accented.
+    times: Vec<(u64, bool)>,
+    /// If not None, clicks continue after `times` without an end.
+    repeat: Option<Repeat>,
+    /// The index of the next click.
+    next: usize,
+    /// The current click, if any.
+    click: Option<(u64, bool)>,
+}
+
+impl Clicks {
+    fn new(times: Vec<(u64, bool)>, repeat: Option<Repeat>) -> Self {
+        Self {
+            times,
+            repeat,
+            next: 0,
+            click: None,
+        }
+    }
+
+    /// Returns the time of a click in number of samples and whether the click is accented.
+    fn get(&self, index: usize) -> Option<(u64, bool)> {
+        match self.times.get(index) {
+            Some(click) => Some(*click),
+            None => self.repeat.map(|repeat| {
+                let i = (index - self.times.len()) as u64;
+                (
+                    repeat.start + (i as f64 * repeat.beat_samples) as u64,
+                    repeat.accent && (repeat.beat + i) % repeat.numerator == 0,
+                )
+            }),
+        }
+    }
+
+    /// Start from the first click again.
+    fn rearm(&mut self) {
+        self.next = 0;
+        self.click = None;
+    }
+
+    /// Returns the number of samples since the start of the current click and whether the click is accented.
+    ///
+    /// - `t` The current time in number of samples.
+    fn update(&mut self, t: u64) -> Option<(u64, bool)> {
+        // Start the next click.
+        while let Some(click) = self.get(self.next).filter(|c| c.0 <= t) {
+            self.click = Some(click);
+            self.next += 1;
+        }
+        self.click
+            .map(|(t0, accent)| (t.saturating_sub(t0), accent))
+    }
+}
+
+/// A metronome that clicks on every beat while music is playing.
+/// The clicks are generated rather than synthesized, so they don't need a SoundFont or a channel.
+///
+/// The metronome can also count in before music starts to play. The count-in always clicks, even if the metronome isn't enabled.
+#[derive(Clone)]
+pub struct Metronome {
+    /// If true, the metronome will click during playback.
+    pub enabled: bool,
+    /// If true, the first beat of each bar is accented.
+    pub accent: bool,
+    /// If true, the metronome will be included in exported audio.
+    pub export: bool,
+    /// The gain.
+    pub gain: Index<u8>,
+    /// The clicks scheduled during playback.
+    clicks: Clicks,
+    /// The count-in clicks.
+    count_in: Clicks,
+    /// The duration of the count-in in number of samples.
+    count_in_length: u64,
+    /// The elapsed time of the count-in in number of samples.
+    count_in_time: u64,
+    /// The framerate that the clicks were scheduled at.
+    framerate: f32,
+}
+
+impl Default for Metronome {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accent: true,
+            export: false,
+            gain: Index::new(MAX_VOLUME / 2, MAX_VOLUME + 1),
+            clicks: Clicks::default(),
+            count_in: Clicks::default(),
+            count_in_length: 0,
+            count_in_time: 0,
+            framerate: 0.0,
+        }
+    }
+}
+
+impl Metronome {
+    /// Schedule a click on every beat.
+    ///
+    /// - `time` The time state. This is used for the tempo map and the time signatures.
+    /// - `t0` The start time in PPQ.
+    /// - `t1` The end time in PPQ. If None, the metronome clicks until playback stops.
+    /// - `framerate` The framerate.
+    pub(crate) fn schedule(&mut self, time: &Time, t0: u64, t1: Option<u64>, framerate: f32) {
+        // After the last tempo change and the last time signature change, the beats are evenly spaced.
+        let end = t1.unwrap_or_else(|| {
+            time.tempos
+                .iter()
+                .map(|t| t.time)
+                .chain(time.time_signatures.iter().map(|t| t.time))
+                .fold(t0, u64::max)
+        });
+        let repeat = match t1 {
+            Some(_) => None,
+            None => {
+                let time_signature = time.get_time_signature(end);
+                let beat_ppq = time_signature.get_beat_ppq();
+                let numerator = time_signature.numerator as u64;
+                // The index of the first beat after `end`.
+                let beat = (end - time_signature.time) / beat_ppq + 1;
+                Some(Repeat {
+                    start: time.ppq_to_samples(time_signature.time + beat * beat_ppq, framerate),
+                    beat_samples: (beat_ppq as f64 / PPQ_F as f64) * 60.0
+                        / time.get_bpm(end).get_f() as f64
+                        * framerate as f64,
+                    beat: beat % numerator,
+                    numerator,
+                    accent: self.accent,
+                })
+            }
+        };
+        self.clicks = Clicks::new(
+            time.get_beats(t0, end)
+                .iter()
+                .map(|(t, bar)| (time.ppq_to_samples(*t, framerate), self.accent && *bar))
+                .collect(),
+            repeat,
+        );
+        self.framerate = framerate;
+    }
+
+    /// Schedule a count-in that ends at `t`. The count-in uses the tempo and time signature at `t`.
+    ///
+    /// - `time` The time state.
+    /// - `t` The time at which music will start to play in PPQ.
+    /// - `count_in` The number of beats or bars.
+    /// - `bars` If true, `count_in` is a number of bars.
+    /// - `framerate` The framerate.
+    pub(crate) fn schedule_count_in(
+        &mut self,
+        time: &Time,
+        t: u64,
+        count_in: u64,
+        bars: bool,
+        framerate: f32,
+    ) {
+        let time_signature = time.get_time_signature(t);
+        let num_beats = if bars {
+            count_in * time_signature.numerator as u64
+        } else {
+            count_in
+        };
+        let beat_samples = ((time_signature.get_beat_ppq() as f32 / PPQ_F) * 60.0
+            / time.get_bpm(t).get_f()
+            * framerate) as u64;
+        self.count_in = Clicks::new(
+            (0..num_beats)
+                .map(|i| {
+                    (
+                        i * beat_samples,
+                        self.accent && i % time_signature.numerator as u64 == 0,
+                    )
+                })
+                .collect(),
+            None,
+        );
+        self.count_in_length = num_beats * beat_samples;
+        self.count_in_time = 0;
+        self.framerate = framerate;
+    }
+
+    /// Returns true if the metronome is counting in.
+    pub fn is_counting_in(&self) -> bool {
+        self.count_in_time < self.count_in_length
+    }
+
+    /// Returns the remaining duration of the count-in in number of samples.
+    pub(crate) fn get_count_in_remaining(&self) -> u64 {
+        self.count_in_length.saturating_sub(self.count_in_time)
+    }
+
+    /// Start clicking from the first scheduled click again.
+    pub(crate) fn rearm(&mut self) {
+        self.clicks.rearm();
+    }
+
+    /// Clear all scheduled clicks.
+    pub(crate) fn clear(&mut self) {
+        self.clicks = Clicks::default();
+        self.count_in = Clicks::default();
+        self.count_in_length = 0;
+        self.count_in_time = 0;
+    }
+
+    /// Returns the next sample.
+    ///
+    /// - `t` The current time in number of samples.
+    pub(crate) fn next_sample(&mut self, t: u64) -> f32 {
+        match self.clicks.update(t) {
+            Some((dt, accent)) if self.enabled => self.get_sample(dt, accent),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the next count-in sample and advances the count-in.
+    pub(crate) fn next_count_in_sample(&mut self) -> f32 {
+        let sample = match self.count_in.update(self.count_in_time) {
+            Some((dt, accent)) => self.get_sample(dt, accent),
+            None => 0.0,
+        };
+        self.count_in_time += 1;
+        sample
+    }
+
+    /// Returns a click sample.
+    ///
+    /// - `dt` The number of samples since the start of the click.
+    /// - `accent` If true, the click is accented.
+    fn get_sample(&self, dt: u64, accent: bool) -> f32 {
+        let dt = dt as f32 / self.framerate;
+        if dt < CLICK_DURATION {
+            let frequency = if accent {
+                ACCENT_FREQUENCY
+            } else {
+                CLICK_FREQUENCY
+            };
+            // A sine wave with a linear decay.
+            (self.gain.get() as f32 / MAX_VOLUME as f32)
+                * CLICK_AMPLITUDE
+                * (1.0 - dt / CLICK_DURATION)
+                * (TAU * frequency * dt).sin()
+        } else {
+            0.0
+        }
+    }
+}
@@ -2,10 +2,23 @@ use super::timed_midi_event::TimedMidiEvent;
 use oxisynth::MidiEvent;
 
 /// A queue of timed MIDI events.
+///
+/// The events are stored in a sorted list and never removed during playback. Instead, a cursor points to the next event.
+/// This way, dequeuing and looping are O(1) and don't allocate memory, which matters in the audio callback.
 #[derive(Default)]
 pub(crate) struct MidiEventQueue {
     /// The events. Assume that this is sorted.
     events: Vec<TimedMidiEvent>,
+    /// The index of the next event.
+    cursor: usize,
+    /// If playback is looping, this is the start and end time of the loop in number of samples.
+    loop_range: Option<[u64; 2]>,
+    /// The index of the first event in the loop. The cursor goes back to this index at the start of each pass.
+    loop_cursor: usize,
+    /// The channels that might have held notes at the end of the loop.
+    loop_channels: Vec<u8>,
+    /// If true, notes are being recorded in real time, so playback continues after the last event until the user stops it.
+    record: bool,
 }
 
 impl MidiEventQueue {
@@ -18,30 +31,58 @@ impl MidiEventQueue {
         self.events.push(TimedMidiEvent { time, event });
     }
 
+    /// Returns the time of the next event in number of samples, or None if there are no more events.
     pub(crate) fn get_next_time(&self) -> Option<u64> {
-        if self.events.is_empty() {
-            None
-        } else {
-            Some(self.events[0].time)
-        }
+        self.events.get(self.cursor).map(|e| e.time)
     }
 
-    /// Sort the list of events by start time.
+    /// Sort the list of events by start time and go back to the first event.
     pub(crate) fn sort(&mut self) {
-        self.events.sort()
+        self.events.sort();
+        self.cursor = 0;
     }
 
-    /// Dequeue any events that start at `time`.
-    pub(crate) fn dequeue(&mut self, time: u64) -> Vec<MidiEvent> {
-        let mut midi_events = vec![];
-        while !self.events.is_empty() && self.events[0].time == time {
-            midi_events.push(self.events.remove(0).event);
+    /// Dequeue any events that start at or before `time`.
+    pub(crate) fn dequeue(&mut self, time: u64) -> &[TimedMidiEvent] {
+        let start = self.cursor;
+        while self.cursor < self.events.len() && self.events[self.cursor].time <= time {
+            self.cursor += 1;
         }
-        midi_events
+        &self.events[start..self.cursor]
+    }
+
+    /// Start to loop. Remove any events after the loop and remember where the loop's events start so that they can be played again.
+    /// Events before the loop (i.e. pre-roll events) are only played once.
+    /// Call this after `sort()`.
+    ///
+    /// - `start` The start time of the loop in number of samples.
+    /// - `end` The end time of the loop in number of samples.
+    /// - `channels` The channels that might have held notes at the end of the loop.
+    pub(crate) fn set_loop(&mut self, start: u64, end: u64, channels: Vec<u8>) {
+        self.events.retain(|e| e.time < end);
+        self.loop_cursor = self.events.partition_point(|e| e.time < start);
+        self.loop_channels = channels;
+        self.loop_range = Some([start, end]);
+    }
+
+    /// Returns the start and end time of the loop in number of samples, or None if we're not looping.
+    pub(crate) fn get_loop_range(&self) -> Option<[u64; 2]> {
+        self.loop_range
+    }
+
+    /// Keep playing after the last event so that notes can be recorded until the user stops playback.
+    pub(crate) fn set_record(&mut self) {
+        self.record = true;
+    }
+
+    /// Returns true if playback continues after the last event.
+    pub(crate) fn is_recording(&self) -> bool {
+        self.record
     }
 
-    /// Clear the queue.
-    pub(crate) fn clear(&mut self) {
-        self.events.clear()
+    /// Go back to the first event of the loop. Returns the channels that might have held notes.
+    pub(crate) fn rearm(&mut self) -> &[u8] {
+        self.cursor = self.loop_cursor;
+        &self.loop_channels
     }
 }
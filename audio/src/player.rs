@@ -1,91 +1,179 @@
+use crate::audio_devices::{get_device, get_host, get_stream_config};
 use crate::decayer::Decayer;
+use crate::midi_event_queue::MidiEventQueue;
 use crate::play_state::PlayState;
+use crate::thread_output::ThreadOutput;
 use crate::types::SharedSample;
-use crate::{SharedMidiEventQueue, SharedPlayState, SharedSynth};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::{MidiEventQueueReceiver, SharedMetronome, SharedPlayState, SharedSynth};
+use common::{AudioBackend, AudioDeviceSettings, DEFAULT_FRAMERATE};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use cpal::*;
-use oxisynth::Synth;
+use oxisynth::{MidiEvent, Synth};
 
 const ERROR_MESSAGE: &str = "Failed to create an audio output stream: ";
+/// The number of channels when there is no audio device.
+const NUM_CHANNELS: usize = 2;
+/// The buffer size in frames when there is no audio device.
+const DEFAULT_BUFFER_SIZE: u32 = 512;
 
 /// Try to start an audio stream and play audio.
 /// Source: https://github.com/PolyMeilex/OxiSynth/blob/master/examples/real-time/src/main.rs
 pub(crate) struct Player {
-    /// The audio host. We don't want to drop it.
-    _host: Host,
-    /// The audio stream. We don't want to drop it.
-    _stream: Option<Stream>,
+    /// The audio output. We don't want to drop it.
+    output: Output,
     /// The machine's audio framerate.
     pub framerate: u32,
 }
 
+/// Where the `Player` sends audio.
+enum Output {
+    /// An audio device.
+    Device {
+        /// The audio host. We don't want to drop it.
+        _host: Host,
+        /// The audio stream. We don't want to drop it.
+        _stream: Option<Stream>,
+    },
+    /// A thread that plays audio in real time without an audio device.
+    Thread(ThreadOutput),
+}
+
 impl Player {
     pub(crate) fn new(
-        midi_event_queue: SharedMidiEventQueue,
+        audio_device: &AudioDeviceSettings,
+        midi_event_queue: MidiEventQueueReceiver,
         synth: SharedSynth,
         sample: SharedSample,
         play_state: SharedPlayState,
-    ) -> Option<Self> {
-        // Get the host.
-        let host = default_host();
-        // Try to get an output device.
-        match host.default_output_device() {
-            None => {
-                println!("{} Failed to get output device", ERROR_MESSAGE);
-                None
-            }
-            // Try to get config info.
-            Some(device) => match device.default_output_config() {
-                Err(err) => {
-                    println!("{} {}", ERROR_MESSAGE, err);
-                    None
-                }
+        metronome: SharedMetronome,
+    ) -> Self {
+        let path = match &audio_device.backend {
+            AudioBackend::Device => match Self::get_output(audio_device) {
                 // We have a device and a config!
-                Ok(config) => {
-                    let framerate = config.sample_rate().0;
-                    let stream_config: StreamConfig = config.into();
+                Some((host, device, stream_config)) => {
+                    let framerate = stream_config.sample_rate.0;
                     let channels = stream_config.channels as usize;
 
                     // Try to get a stream.
-                    let stream = Player::run(
+                    let data_callback = Self::get_data_callback(
                         channels,
-                        device,
-                        stream_config,
                         midi_event_queue,
                         synth,
                         sample,
                         play_state,
+                        metronome,
                     );
-                    Some(Self {
-                        _host: host,
-                        _stream: stream,
+                    let stream = Player::run(device, stream_config, data_callback);
+                    return Self {
+                        output: Output::Device {
+                            _host: host,
+                            _stream: stream,
+                        },
                         framerate,
-                    })
+                    };
+                }
+                // Keep the play clock running without a device.
+                None => {
+                    println!("Playing audio without an output device.");
+                    None
                 }
             },
+            AudioBackend::Null => None,
+            AudioBackend::File(path) => Some(path.as_path()),
+        };
+        let framerate = audio_device.framerate.unwrap_or(DEFAULT_FRAMERATE as u32);
+        let buffer_size = audio_device.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        let data_callback = Self::get_data_callback(
+            NUM_CHANNELS,
+            midi_event_queue,
+            synth,
+            sample,
+            play_state,
+            metronome,
+        );
+        Self {
+            output: Output::Thread(ThreadOutput::new(
+                framerate,
+                buffer_size,
+                data_callback,
+                path,
+            )),
+            framerate,
+        }
+    }
+
+    /// Returns true if this player is sending audio to an audio device.
+    pub(crate) fn has_device(&self) -> bool {
+        matches!(self.output, Output::Device { .. })
+    }
+
+    /// Try to get an audio host, an output device, and a stream config.
+    fn get_output(audio_device: &AudioDeviceSettings) -> Option<(Host, Device, StreamConfig)> {
+        // Get the host.
+        let host = get_host(audio_device);
+        // Try to get an output device.
+        match get_device(&host, audio_device) {
+            None => {
+                println!("{} Failed to get output device", ERROR_MESSAGE);
+                None
+            }
+            // Try to get config info.
+            Some(device) => match get_stream_config(&device, audio_device) {
+                Err(err) => {
+                    println!("{} {}", ERROR_MESSAGE, err);
+                    None
+                }
+                Ok(stream_config) => Some((host, device, stream_config)),
+            },
         }
     }
 
     /// Start running the stream.
-    fn run(
+    fn run<F>(device: Device, stream_config: StreamConfig, mut data_callback: F) -> Option<Stream>
+    where
+        F: FnMut(&mut [f32]) + Send + 'static,
+    {
+        // Define the error callback.
+        let err_callback = |err| println!("Stream error: {}", err);
+
+        // Build the cpal output stream from the stream config info and the callbacks.
+        match device.build_output_stream(
+            &stream_config,
+            move |output: &mut [f32], _: &OutputCallbackInfo| data_callback(output),
+            err_callback,
+        ) {
+            // We have a stream!
+            Ok(stream) => match stream.play() {
+                Ok(_) => Some(stream),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// Returns a callback that writes samples to an output buffer of interleaved frames.
+    ///
+    /// The callback owns the queue of MIDI events. The `Conn` sends a new queue whenever music starts to play.
+    /// Between events, the synthesizer writes whole sub-blocks of frames at a time.
+    pub(crate) fn get_data_callback(
         channels: usize,
-        device: Device,
-        stream_config: StreamConfig,
-        midi_event_queue: SharedMidiEventQueue,
+        midi_event_queue: MidiEventQueueReceiver,
         synth: SharedSynth,
         sample: SharedSample,
         play_state: SharedPlayState,
-    ) -> Option<Stream> {
-        // Define the error callback.
-        let err_callback = |err| println!("Stream error: {}", err);
-
+        metronome: SharedMetronome,
+    ) -> impl FnMut(&mut [f32]) + Send + 'static {
         let two_channels = channels == 2;
         let mut buffer = vec![0.0; 2];
-        let mut sample_buffer = [0.0; 2];
+        let mut queue = MidiEventQueue::default();
         let mut decayer = Decayer::default();
 
-        // Define the data callback used by cpal. Move `stream_send` into the closure.
-        let data_callback = move |output: &mut [f32], _: &OutputCallbackInfo| {
+        // Define the data callback. Move `stream_send` into the closure.
+        move |output: &mut [f32]| {
+            // Get the most recent queue of events.
+            while let Ok(q) = midi_event_queue.try_recv() {
+                queue = q;
+            }
             let ps = *play_state.lock();
             match ps {
                 // Assume that there is no audio and do nothing.
@@ -121,60 +209,101 @@ impl Player {
                 }
                 // Playing music.
                 PlayState::Playing(time) => {
-                    let len = output.len();
-                    // Resize the buffers.
-                    if len > buffer.len() {
-                        buffer.resize(len, 0.0);
+                    let num_frames = output.len() / channels;
+                    // Resize the buffer.
+                    if num_frames * 2 > buffer.len() {
+                        buffer.resize(num_frames * 2, 0.0);
                     }
-                    // Get the next sample.
                     let mut synth = synth.lock();
-                    let mut midi_event_queue = midi_event_queue.lock();
-                    // Iterate through the output buffer's frames.
+                    let mut metronome = metronome.lock();
                     let mut begin_decay = false;
-                    let buffer_len = len / channels;
                     let mut t = time;
-                    for frame in output.chunks_mut(channels) {
-                        match midi_event_queue.get_next_time() {
-                            Some(next_time) => {
-                                // There are events on this frame.
-                                if t == next_time {
-                                    // Dequeue events.
-                                    let events = midi_event_queue.dequeue(t);
-                                    // Send the MIDI events to the synth.
-                                    if !events.is_empty() {
-                                        for event in events {
-                                            if synth.send_event(event).is_ok() {}
-                                        }
+                    let loop_range = queue.get_loop_range();
+                    // Iterate through the output buffer in sub-blocks of frames.
+                    let mut frame = 0;
+                    while frame < num_frames {
+                        let remaining = (num_frames - frame) as u64;
+                        // Count in before playing music. Time doesn't advance during the count-in.
+                        let counting_in = metronome.is_counting_in();
+                        let block = if counting_in {
+                            remaining.min(metronome.get_count_in_remaining())
+                        } else {
+                            // Go back to the start of the loop.
+                            if let Some([loop_start, loop_end]) = loop_range {
+                                if t >= loop_end {
+                                    // Release any held notes and go back to the loop's first event.
+                                    for channel in queue.rearm() {
+                                        let _ = synth.send_event(MidiEvent::AllNotesOff {
+                                            channel: *channel,
+                                        });
                                     }
+                                    metronome.rearm();
+                                    t = loop_start;
                                 }
-                                // Add the sample.
-                                // This is almost certainly more performant than the code in the `else` block.
-                                if two_channels {
-                                    // Get the sample.
-                                    synth.write(frame);
-                                }
-                                // Add for more than one channel. This is slower.
-                                else {
-                                    synth.write(sample_buffer.as_mut_slice());
-                                    for (id, sample) in frame.iter_mut().enumerate() {
-                                        *sample = sample_buffer[id % 2];
+                            }
+                            // Send the MIDI events on this frame to the synth.
+                            for event in queue.dequeue(t) {
+                                let _ = synth.send_event(event.event);
+                            }
+                            // Render until the next event or the end of the loop.
+                            let block = match queue.get_next_time() {
+                                Some(next_time) => remaining.min(next_time - t),
+                                // There are no more events. If we're looping or recording, keep playing.
+                                None => {
+                                    if loop_range.is_none() && !queue.is_recording() {
+                                        begin_decay = true;
+                                        break;
                                     }
+                                    remaining
+                                }
+                            };
+                            match loop_range {
+                                Some([_, loop_end]) => block.min(loop_end.saturating_sub(t)),
+                                None => block,
+                            }
+                        };
+                        // Always advance by at least one frame.
+                        let block = block.max(1) as usize;
+                        let frames = &mut output[frame * channels..(frame + block) * channels];
+                        // Add the samples.
+                        // This is almost certainly more performant than the code in the `else` block.
+                        if two_channels {
+                            synth.write(&mut *frames);
+                        }
+                        // Add for more than one channel. This is slower.
+                        else {
+                            let samples = &mut buffer[0..block * 2];
+                            synth.write(&mut *samples);
+                            for (out_frame, in_frame) in
+                                frames.chunks_mut(channels).zip(samples.chunks(2))
+                            {
+                                for (id, sample) in out_frame.iter_mut().enumerate() {
+                                    *sample = in_frame[id % 2];
                                 }
-                                // Advance time.
-                                t += 1;
                             }
-                            // There are no more events.
-                            None => {
-                                begin_decay = true;
-                                break;
+                        }
+                        // Add the metronome clicks.
+                        for (i, out_frame) in frames.chunks_mut(channels).enumerate() {
+                            let click = if counting_in {
+                                metronome.next_count_in_sample()
+                            } else {
+                                metronome.next_sample(t + i as u64)
+                            };
+                            if click != 0.0 {
+                                out_frame.iter_mut().for_each(|sample| *sample += click);
                             }
                         }
+                        // Advance time.
+                        if !counting_in {
+                            t += block as u64;
+                        }
+                        frame += block;
                     }
                     if begin_decay {
-                        *play_state.lock() = PlayState::Decaying;
+                        let remaining = num_frames - frame;
                         Self::begin_decay(
-                            buffer[0..buffer_len].as_mut(),
-                            output,
+                            buffer[0..remaining * 2].as_mut(),
+                            output[frame * channels..].as_mut(),
                             channels,
                             two_channels,
                             &play_state,
@@ -189,16 +318,6 @@ impl Player {
             let mut sample = sample.lock();
             sample.0 = output[0];
             sample.1 = output[1]
-        };
-
-        // Build the cpal output stream from the stream config info and the callbacks.
-        match device.build_output_stream(&stream_config, data_callback, err_callback) {
-            // We have a stream!
-            Ok(stream) => match stream.play() {
-                Ok(_) => Some(stream),
-                Err(_) => None,
-            },
-            Err(_) => None,
         }
     }
 
@@ -224,3 +343,81 @@ impl Player {
         *play_state.lock() = PlayState::Decaying;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Player;
+    use crate::midi_event_queue::MidiEventQueue;
+    use crate::play_state::PlayState;
+    use crate::Metronome;
+    use common::{Time, DEFAULT_FRAMERATE, PPQ_U};
+    use oxisynth::{MidiEvent, Synth};
+    use parking_lot::Mutex;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    #[test]
+    fn record_past_last_note() {
+        let framerate = DEFAULT_FRAMERATE as f32;
+        let mut synth = Synth::default();
+        synth.set_sample_rate(framerate);
+        // A single short note.
+        let mut queue = MidiEventQueue::default();
+        queue.enqueue(
+            0,
+            MidiEvent::NoteOn {
+                channel: 0,
+                key: 60,
+                vel: 100,
+            },
+        );
+        queue.enqueue(
+            100,
+            MidiEvent::NoteOff {
+                channel: 0,
+                key: 60,
+            },
+        );
+        queue.sort();
+        queue.set_record();
+        let (sender, receiver) = channel();
+        let _ = sender.send(queue);
+        // Click until playback stops.
+        let mut metronome = Metronome {
+            enabled: true,
+            ..Default::default()
+        };
+        let time = Time::default();
+        metronome.schedule(&time, 0, None, framerate);
+        let play_state = Arc::new(Mutex::new(PlayState::Playing(0)));
+        let mut callback = Player::get_data_callback(
+            2,
+            receiver,
+            Arc::new(Mutex::new(synth)),
+            Arc::new(Mutex::new((0.0, 0.0))),
+            Arc::clone(&play_state),
+            Arc::new(Mutex::new(metronome)),
+        );
+        // Play for a few beats after the last note.
+        let beat = time.ppq_to_samples(PPQ_U, framerate);
+        let mut output = vec![0.0; 1024];
+        let mut clicked = false;
+        for _ in 0..(beat * 3) / 512 {
+            callback(&mut output);
+            match *play_state.lock() {
+                PlayState::Playing(t) => {
+                    // The metronome keeps clicking after the last note.
+                    if t > beat * 2 && output.iter().any(|s| *s != 0.0) {
+                        clicked = true;
+                    }
+                }
+                play_state => panic!("{:?}", play_state),
+            }
+        }
+        assert!(clicked);
+        match *play_state.lock() {
+            PlayState::Playing(t) => assert!(t >= beat * 3 - 512, "{}", t),
+            play_state => panic!("{:?}", play_state),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+/// An error that occurred while loading a SoundFont.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum SoundFontError {
+    /// The file doesn't exist.
+    Missing { channel: u8, path: PathBuf },
+    /// The file exists but couldn't be opened.
+    Open {
+        channel: u8,
+        path: PathBuf,
+        error: String,
+    },
+    /// The file isn't a valid SoundFont.
+    Invalid {
+        channel: u8,
+        path: PathBuf,
+        error: String,
+    },
+}
+
+impl SoundFontError {
+    /// Returns the channel that the SoundFont was supposed to be loaded into.
+    pub fn get_channel(&self) -> u8 {
+        match self {
+            Self::Missing { channel, .. }
+            | Self::Open { channel, .. }
+            | Self::Invalid { channel, .. } => *channel,
+        }
+    }
+
+    /// Returns the path to the SoundFont.
+    pub fn get_path(&self) -> &Path {
+        match self {
+            Self::Missing { path, .. } | Self::Open { path, .. } | Self::Invalid { path, .. } => {
+                path
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::Program;
-use common::MAX_VOLUME;
+use common::{Tuning, MAX_VOLUME};
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// The state of the synthesizer.
 #[derive(Serialize, Deserialize)]
@@ -10,6 +11,16 @@ pub struct SynthState {
     pub programs: HashMap<u8, Program>,
     /// The current gain.
     pub gain: u8,
+    /// The paths of SoundFonts that couldn't be loaded, per channel.
+    /// These channels don't have programs until they are given a new SoundFont.
+    #[serde(default)]
+    pub missing_programs: HashMap<u8, PathBuf>,
+    /// The content hashes of SoundFonts that were loaded. If a SoundFont goes missing, this is used to find it again.
+    #[serde(default)]
+    pub hashes: HashMap<PathBuf, u64>,
+    /// The microtonal tuning per channel. Channels that aren't in this map are 12-tone equal temperament.
+    #[serde(default)]
+    pub tunings: HashMap<u8, Tuning>,
 }
 
 impl Default for SynthState {
@@ -17,6 +28,9 @@ impl Default for SynthState {
         Self {
             programs: HashMap::new(),
             gain: MAX_VOLUME,
+            missing_programs: HashMap::new(),
+            hashes: HashMap::new(),
+            tunings: HashMap::new(),
         }
     }
 }
@@ -26,6 +40,9 @@ impl Clone for SynthState {
         Self {
             programs: self.programs.clone(),
             gain: self.gain,
+            missing_programs: self.missing_programs.clone(),
+            hashes: self.hashes.clone(),
+            tunings: self.tunings.clone(),
         }
     }
 }
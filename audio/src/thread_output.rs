@@ -0,0 +1,107 @@
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The number of channels.
+const NUM_CHANNELS: u16 = 2;
+
+/// Play audio on a thread in real time without an audio device.
+/// Optionally, write everything that would've been heard to a .wav file.
+pub(crate) struct ThreadOutput {
+    /// If true, the thread stops.
+    done: Arc<AtomicBool>,
+    /// The thread. We join it when this is dropped so that the .wav file is finalized.
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ThreadOutput {
+    /// - `framerate` The audio framerate.
+    /// - `buffer_size` The number of frames written per call to `data_callback`.
+    /// - `data_callback` Writes samples to an output buffer of interleaved stereo frames.
+    /// - `path` If not None, write the audio to this .wav file.
+    pub(crate) fn new<F>(
+        framerate: u32,
+        buffer_size: u32,
+        mut data_callback: F,
+        path: Option<&Path>,
+    ) -> Self
+    where
+        F: FnMut(&mut [f32]) + Send + 'static,
+    {
+        // Try to create the .wav file.
+        let mut writer = path.and_then(|path| {
+            let spec = WavSpec {
+                channels: NUM_CHANNELS,
+                sample_rate: framerate,
+                bits_per_sample: 32,
+                sample_format: SampleFormat::Float,
+            };
+            match WavWriter::create(path, spec) {
+                Ok(writer) => Some(writer),
+                Err(error) => {
+                    println!("Failed to create audio file {:?}: {}", path, error);
+                    None
+                }
+            }
+        });
+        let done = Arc::new(AtomicBool::new(false));
+        let thread_done = Arc::clone(&done);
+        let handle = spawn(move || {
+            let mut buffer = vec![0.0; buffer_size as usize * NUM_CHANNELS as usize];
+            let block = Duration::from_secs_f64(buffer_size as f64 / framerate as f64);
+            let mut next = Instant::now();
+            while !thread_done.load(Ordering::Relaxed) {
+                // The data callback doesn't write anything if there is no audio.
+                buffer.iter_mut().for_each(|s| *s = 0.0);
+                data_callback(&mut buffer);
+                // Stop writing if there was an error.
+                if writer
+                    .as_mut()
+                    .is_some_and(|writer| !Self::write(writer, &buffer))
+                {
+                    writer = None;
+                }
+                // Wait until the buffer would've been played.
+                next += block;
+                let now = Instant::now();
+                if next > now {
+                    sleep(next - now);
+                }
+            }
+            if let Some(writer) = writer {
+                if let Err(error) = writer.finalize() {
+                    println!("Failed to finalize audio file: {}", error);
+                }
+            }
+        });
+        Self {
+            done,
+            handle: Some(handle),
+        }
+    }
+
+    /// Write a buffer of samples to the .wav file. Returns false if there was an error.
+    fn write(writer: &mut WavWriter<BufWriter<File>>, buffer: &[f32]) -> bool {
+        for sample in buffer.iter() {
+            if let Err(error) = writer.write_sample(*sample) {
+                println!("Failed to write to audio file: {}", error);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Drop for ThreadOutput {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -16,6 +16,68 @@ impl Ord for TimedMidiEvent {
             Ordering::Less => Ordering::Less,
             Ordering::Greater => Ordering::Greater,
             Ordering::Equal => match (&self.event, &other.event) {
+                // Two control change, channel pressure, or pitch bend events are equal.
+                (
+                    MidiEvent::ControlChange {
+                        channel: _,
+                        ctrl: _,
+                        value: _,
+                    }
+                    | MidiEvent::PitchBend {
+                        channel: _,
+                        value: _,
+                    }
+                    | MidiEvent::ChannelPressure {
+                        channel: _,
+                        value: _,
+                    },
+                    MidiEvent::ControlChange {
+                        channel: _,
+                        ctrl: _,
+                        value: _,
+                    }
+                    | MidiEvent::PitchBend {
+                        channel: _,
+                        value: _,
+                    }
+                    | MidiEvent::ChannelPressure {
+                        channel: _,
+                        value: _,
+                    },
+                ) => Ordering::Equal,
+                // Control change, channel pressure, and pitch bend events are always before all other events so that they apply to notes at the same time.
+                (
+                    MidiEvent::ControlChange {
+                        channel: _,
+                        ctrl: _,
+                        value: _,
+                    }
+                    | MidiEvent::PitchBend {
+                        channel: _,
+                        value: _,
+                    }
+                    | MidiEvent::ChannelPressure {
+                        channel: _,
+                        value: _,
+                    },
+                    _,
+                ) => Ordering::Less,
+                (
+                    _,
+                    MidiEvent::ControlChange {
+                        channel: _,
+                        ctrl: _,
+                        value: _,
+                    }
+                    | MidiEvent::PitchBend {
+                        channel: _,
+                        value: _,
+                    }
+                    | MidiEvent::ChannelPressure {
+                        channel: _,
+                        value: _,
+                    },
+                ) => Ordering::Greater,
                 // Two note-on events are equal.
                 (
                     MidiEvent::NoteOn {
@@ -0,0 +1,34 @@
+use std::path::{Path, PathBuf};
+
+/// An error that occurred while loading a tuning.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum TuningError {
+    /// The .scl file couldn't be read.
+    Scale {
+        channel: u8,
+        path: PathBuf,
+        error: String,
+    },
+    /// The .kbm file couldn't be read.
+    KeyboardMapping {
+        channel: u8,
+        path: PathBuf,
+        error: String,
+    },
+}
+
+impl TuningError {
+    /// Returns the channel that the tuning was supposed to be applied to.
+    pub fn get_channel(&self) -> u8 {
+        match self {
+            Self::Scale { channel, .. } | Self::KeyboardMapping { channel, .. } => *channel,
+        }
+    }
+
+    /// Returns the path to the .scl or .kbm file.
+    pub fn get_path(&self) -> &Path {
+        match self {
+            Self::Scale { path, .. } | Self::KeyboardMapping { path, .. } => path,
+        }
+    }
+}
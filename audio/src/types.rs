@@ -1,9 +1,11 @@
 use crate::export::ExportState;
+use crate::metronome::Metronome;
 use crate::midi_event_queue::MidiEventQueue;
 use crate::play_state::PlayState;
 use crate::Command;
 use oxisynth::Synth;
 use parking_lot::Mutex;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
 
 /// Type alias for an audio messages.
@@ -14,6 +16,9 @@ pub type CommandsMessage = Vec<Command>;
 pub(crate) type AudioBuffer = [Vec<f32>; 2];
 pub(crate) type SharedSynth = Arc<Mutex<Synth>>;
 pub type SharedExportState = Arc<Mutex<ExportState>>;
-pub(crate) type SharedMidiEventQueue = Arc<Mutex<MidiEventQueue>>;
 pub type SharedPlayState = Arc<Mutex<PlayState>>;
 pub(crate) type SharedSample = Arc<Mutex<AudioMessage>>;
+pub(crate) type SharedMetronome = Arc<Mutex<Metronome>>;
+/// The `Conn` sends each new queue of MIDI events to the audio callback, which owns it.
+pub(crate) type MidiEventQueueSender = Sender<MidiEventQueue>;
+pub(crate) type MidiEventQueueReceiver = Receiver<MidiEventQueue>;
@@ -0,0 +1,144 @@
+//! Compare the latency and memory usage of the undo stack when snapshots share notes vs. when each snapshot copies every note.
+//!
+//! `cargo bench -p common`
+//!
+//! Each edit clones the music, nudges a few notes in one track, and pushes the music before and after the edit to the undo stack, like an arrow-key nudge in the piano roll.
+//!
+//! Then, compare idle frames with selected notes: Getting mutable notes on a frame without an edit copies the selected track, whereas getting read-only notes doesn't.
+
+use common::{MidiTrack, Music, Note, Notes, SelectMode, PPQ_U};
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+/// The number of tracks in the song.
+const NUM_TRACKS: u8 = 20;
+/// The number of notes per track.
+const NUM_NOTES: u64 = 2000;
+/// The number of edits, i.e. the size of the undo stack.
+const NUM_EDITS: usize = 100;
+/// The number of selected notes that are nudged per edit.
+const NUM_SELECTED: usize = 8;
+/// The number of idle frames.
+const NUM_FRAMES: usize = 100;
+
+fn main() {
+    println!(
+        "{} tracks, {} notes per track, {} edits",
+        NUM_TRACKS, NUM_NOTES, NUM_EDITS
+    );
+    bench("Shared notes", |music| music.clone());
+    bench("Copied notes", deep_clone);
+    println!(
+        "{} idle frames with {} selected notes",
+        NUM_FRAMES, NUM_SELECTED
+    );
+    bench_idle("Read-only notes", |select_mode, music| {
+        select_mode.get_notes(music);
+    });
+    bench_idle("Mutable notes", |select_mode, music| {
+        // Like `Edit`, keep a copy of the state before the edit.
+        let _s0 = music.clone();
+        select_mode.get_notes_mut(music);
+    });
+}
+
+/// Edit a song, push each edit to an undo stack, and print the mean and max time per edit and the size of the notes on the undo stack.
+///
+/// - `label` The name of the benchmark.
+/// - `clone` The function used to clone the music.
+fn bench(label: &str, clone: fn(&Music) -> Music) {
+    let mut music = get_music();
+    let mut undo = vec![];
+    let mut total = Duration::ZERO;
+    let mut max = Duration::ZERO;
+    for i in 0..NUM_EDITS {
+        let now = Instant::now();
+        let m0 = clone(&music);
+        let track = &mut music.midi_tracks[i % NUM_TRACKS as usize];
+        for note in track.notes.iter_mut().take(NUM_SELECTED) {
+            note.start += 1;
+            note.end += 1;
+        }
+        undo.push((m0, clone(&music)));
+        let elapsed = now.elapsed();
+        total += elapsed;
+        max = max.max(elapsed);
+    }
+    // Count each distinct copy of the notes once.
+    let mut copies = HashSet::new();
+    let bytes: usize = undo
+        .iter()
+        .flat_map(|(m0, m1)| m0.midi_tracks.iter().chain(m1.midi_tracks.iter()))
+        .filter(|track| copies.insert(track.notes.as_ptr()))
+        .map(|track| track.notes.len() * size_of::<Note>())
+        .sum();
+    println!(
+        "\t{}: mean {:?}, max {:?} per edit. {:.1} MB of notes on the undo stack",
+        label,
+        total / NUM_EDITS as u32,
+        max,
+        bytes as f64 / 1000000.0
+    );
+}
+
+/// Run idle frames with a selection after an edit, and print the mean and max time per frame and whether the selected track's notes are still shared with the undo stack.
+///
+/// - `label` The name of the benchmark.
+/// - `frame` The function that runs on each frame.
+fn bench_idle(label: &str, frame: fn(&mut SelectMode, &mut Music)) {
+    let mut music = get_music();
+    let mut select_mode = SelectMode::Many(Some((0..NUM_SELECTED).collect()));
+    // The undo stack has the music after the most recent edit.
+    let undo = music.clone();
+    let mut total = Duration::ZERO;
+    let mut max = Duration::ZERO;
+    for _ in 0..NUM_FRAMES {
+        let now = Instant::now();
+        frame(&mut select_mode, &mut music);
+        let elapsed = now.elapsed();
+        total += elapsed;
+        max = max.max(elapsed);
+    }
+    println!(
+        "\t{}: mean {:?}, max {:?} per frame. Shared with the undo stack: {}",
+        label,
+        total / NUM_FRAMES as u32,
+        max,
+        undo.midi_tracks[0]
+            .notes
+            .ptr_eq(&music.midi_tracks[0].notes)
+    );
+}
+
+/// Returns a song with `NUM_TRACKS` tracks, each of which has `NUM_NOTES` notes.
+fn get_music() -> Music {
+    let midi_tracks = (0..NUM_TRACKS)
+        .map(|channel| {
+            let mut track = MidiTrack::new(channel);
+            track.notes = (0..NUM_NOTES)
+                .map(|i| Note {
+                    note: 60 + (i % 12) as u8,
+                    velocity: 127,
+                    start: i * PPQ_U,
+                    end: (i + 1) * PPQ_U,
+                })
+                .collect();
+            track
+        })
+        .collect();
+    Music {
+        midi_tracks,
+        selected: Some(0),
+        ..Default::default()
+    }
+}
+
+/// Clone the music and give each track its own copy of its notes. This is how the undo stack behaved before `Notes`.
+fn deep_clone(music: &Music) -> Music {
+    let mut music = music.clone();
+    for track in music.midi_tracks.iter_mut() {
+        track.notes = Notes::from(track.notes.to_vec());
+    }
+    music
+}
@@ -1,12 +1,12 @@
-use crate::get_default_data_folder;
-use clap::Parser;
+use crate::{get_default_data_folder, AudioBackend};
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 /// Command-line arguments.
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct Args {
-    /// Open the project from disk.
+    /// Open the project from disk. This can be a save file or a bundle directory.
     #[arg(value_name = "FILE")]
     pub file: Option<PathBuf>,
     /// Directory where Cacophony data files reside.
@@ -26,4 +26,62 @@ pub struct Args {
     /// This is meant to be used for debugging.
     #[arg(short, long)]
     pub events: Option<PathBuf>,
+    /// Don't open an audio device. Music still plays in real time, but silently.
+    ///
+    /// This is meant to be used on machines without audio devices.
+    #[arg(long, env = "CACOPHONY_NULL_AUDIO", conflicts_with = "audio_file")]
+    pub null_audio: bool,
+    /// Don't open an audio device. Write everything that would've been heard to a .wav file instead.
+    ///
+    /// This is meant to be used on machines without audio devices.
+    #[arg(long, value_name = "FILE", env = "CACOPHONY_AUDIO_FILE")]
+    pub audio_file: Option<PathBuf>,
+    /// Run a command instead of opening the window.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    /// Returns where audio should be played.
+    pub fn get_audio_backend(&self) -> AudioBackend {
+        match &self.audio_file {
+            Some(path) => AudioBackend::File(path.clone()),
+            None if self.null_audio => AudioBackend::Null,
+            None => AudioBackend::Device,
+        }
+    }
+}
+
+/// Commands that run without opening a window.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Export a save file without opening a window or an audio device.
+    ///
+    /// Uses the export settings in the save file unless they are overridden.
+    Export(ExportArgs),
+}
+
+/// Command-line arguments for exporting a save file.
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    /// The save file or bundle directory.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+    /// The export format: wav, mid, mp3, ogg, or flac.
+    #[arg(long, value_name = "FORMAT")]
+    pub format: Option<String>,
+    /// The output directory. The exported file is named after the save file.
+    ///
+    /// Uses the save file's export directory if not set.
+    #[arg(short, long, value_name = "DIR")]
+    pub out: Option<PathBuf>,
+    /// The framerate: 22050, 44100, or 48000.
+    #[arg(long)]
+    pub framerate: Option<u64>,
+    /// Export each track to a separate file.
+    #[arg(long, conflicts_with = "single_file")]
+    pub multi_file: bool,
+    /// Export all tracks to a single file.
+    #[arg(long)]
+    pub single_file: bool,
 }
@@ -0,0 +1,104 @@
+use crate::config::{get_section, parse_or, set_user_config};
+use ini::{Ini, Properties};
+use std::path::PathBuf;
+
+/// The config.ini section of the audio device settings.
+const SECTION: &str = "AUDIO";
+
+/// Where audio is played.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub enum AudioBackend {
+    /// An audio output device.
+    #[default]
+    Device,
+    /// Nothing. Music still plays in real time.
+    Null,
+    /// A .wav file. Music still plays in real time, and everything that would've been heard is written to the file.
+    File(PathBuf),
+}
+
+/// User-defined audio output settings. These are stored in config.ini.
+/// If a value is None, the default value is used.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct AudioDeviceSettings {
+    /// The name of the audio host.
+    pub host: Option<String>,
+    /// The name of the output device.
+    pub device: Option<String>,
+    /// The output framerate.
+    pub framerate: Option<u32>,
+    /// The output buffer size in samples.
+    pub buffer_size: Option<u32>,
+    /// Where audio is played. This is set via command line arguments, not config.ini.
+    pub backend: AudioBackend,
+}
+
+impl AudioDeviceSettings {
+    pub fn new(config: &Ini) -> Self {
+        let section = get_section(config, SECTION);
+        Self {
+            host: Self::get_name(&section, "host"),
+            device: Self::get_name(&section, "device"),
+            framerate: Self::get_frames(&section, "framerate"),
+            buffer_size: Self::get_frames(&section, "buffer_size"),
+            backend: AudioBackend::default(),
+        }
+    }
+
+    /// Write the settings to the user config file.
+    pub fn write(&self) {
+        set_user_config(
+            SECTION,
+            &[
+                ("host", self.host.clone().unwrap_or_default()),
+                ("device", self.device.clone().unwrap_or_default()),
+                ("framerate", self.framerate.unwrap_or_default().to_string()),
+                (
+                    "buffer_size",
+                    self.buffer_size.unwrap_or_default().to_string(),
+                ),
+            ],
+        );
+    }
+
+    /// Returns a name. An empty string means that there is no name.
+    fn get_name(properties: &Properties, key: &str) -> Option<String> {
+        properties
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string())
+    }
+
+    /// Returns a framerate or a number of samples. 0 means that there is no value.
+    fn get_frames(properties: &Properties, key: &str) -> Option<u32> {
+        Some(parse_or(properties, key, 0)).filter(|value| *value > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioDeviceSettings;
+    use ini::Ini;
+
+    #[test]
+    fn audio_device_settings_defaults() {
+        // There is no section.
+        let ini = Ini::load_from_str("[FONTS]\n").unwrap();
+        assert_eq!(
+            AudioDeviceSettings::new(&ini),
+            AudioDeviceSettings::default()
+        );
+        // The section is missing some keys.
+        let ini = Ini::load_from_str("[AUDIO]\nframerate = 48000\n").unwrap();
+        let settings = AudioDeviceSettings::new(&ini);
+        assert_eq!(settings.framerate, Some(48000));
+        assert_eq!(settings.buffer_size, None);
+        // Invalid values are the same as no value.
+        let ini = Ini::load_from_str("[AUDIO]\nframerate = fast\nbuffer_size = 0\n").unwrap();
+        assert_eq!(
+            AudioDeviceSettings::new(&ini),
+            AudioDeviceSettings::default()
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{Index, CENTER_PAN, PAN_CONTROLLER};
+use serde::{Deserialize, Serialize};
+
+/// The MIDI controller number for the modulation wheel.
+pub const MODULATION_CONTROLLER: u8 = 1;
+/// The MIDI controller number for channel volume.
+pub const VOLUME_CONTROLLER: u8 = 7;
+/// The MIDI controller number for expression.
+pub const EXPRESSION_CONTROLLER: u8 = 11;
+/// The MIDI controller number for the sustain pedal.
+pub const SUSTAIN_CONTROLLER: u8 = 64;
+/// The maximum value of a MIDI controller.
+pub const MAX_CONTROLLER_VALUE: u16 = 127;
+/// The maximum pitch bend value.
+pub const MAX_PITCH_BEND: u16 = 16383;
+/// The pitch bend value that doesn't bend the pitch.
+pub const CENTER_PITCH_BEND: u16 = 8192;
+/// The types of automation that can be edited in the piano roll, in the order that they are cycled through.
+pub const AUTOMATION_TYPES: [AutomationType; 5] = [
+    AutomationType::Controller(MODULATION_CONTROLLER),
+    AutomationType::Controller(EXPRESSION_CONTROLLER),
+    AutomationType::Controller(SUSTAIN_CONTROLLER),
+    AutomationType::ChannelPressure,
+    AutomationType::PitchBend,
+];
+
+/// The type of value that an automation lane sets.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Deserialize, Serialize)]
+pub enum AutomationType {
+    /// A MIDI controller. The value is the controller number.
+    Controller(u8),
+    /// Channel pressure (aftertouch).
+    ChannelPressure,
+    /// Pitch bend.
+    PitchBend,
+}
+
+impl Default for AutomationType {
+    fn default() -> Self {
+        Self::Controller(MODULATION_CONTROLLER)
+    }
+}
+
+impl AutomationType {
+    /// Returns the maximum value.
+    pub fn get_max(&self) -> u16 {
+        match self {
+            Self::Controller(_) | Self::ChannelPressure => MAX_CONTROLLER_VALUE,
+            Self::PitchBend => MAX_PITCH_BEND,
+        }
+    }
+
+    /// Returns the value that the synthesizer uses when there is no automation.
+    pub fn get_default(&self) -> u16 {
+        match self {
+            Self::Controller(VOLUME_CONTROLLER) => 100,
+            Self::Controller(PAN_CONTROLLER) => CENTER_PAN as u16,
+            Self::Controller(EXPRESSION_CONTROLLER) => MAX_CONTROLLER_VALUE,
+            Self::Controller(_) | Self::ChannelPressure => 0,
+            Self::PitchBend => CENTER_PITCH_BEND,
+        }
+    }
+
+    /// Returns the smallest editable change in value.
+    /// Pitch bend has a much finer resolution than a controller, so it is edited in bigger steps.
+    pub fn get_step(&self) -> u16 {
+        match self {
+            Self::Controller(_) | Self::ChannelPressure => 1,
+            Self::PitchBend => (MAX_PITCH_BEND + 1) / (MAX_CONTROLLER_VALUE + 1),
+        }
+    }
+
+    /// Parses a raw MIDI message. Returns the automation type and value if this is a controller, channel pressure, or pitch bend message.
+    pub fn from_midi_message(message: &[u8; 3]) -> Option<(Self, u16)> {
+        match message[0] & 0xF0 {
+            0xB0 => Some((Self::Controller(message[1]), message[2] as u16)),
+            0xD0 => Some((Self::ChannelPressure, message[1] as u16)),
+            // The first data byte is the least significant byte.
+            0xE0 => Some((
+                Self::PitchBend,
+                ((message[2] as u16) << 7) | message[1] as u16,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the next or previous editable automation type.
+    pub fn cycle(&self, up: bool) -> Self {
+        let index = AUTOMATION_TYPES
+            .iter()
+            .position(|t| t == self)
+            .unwrap_or_default();
+        let mut index = Index::new(index, AUTOMATION_TYPES.len());
+        index.increment(up);
+        AUTOMATION_TYPES[index.get()]
+    }
+}
+
+/// A timed automation value.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize, Serialize)]
+pub struct AutomationPoint {
+    /// The time in PPQ.
+    pub time: u64,
+    /// The value. This is either a controller value (0-127) or a pitch bend value (0-16383).
+    pub value: u16,
+}
+
+/// A lane of timed values for a controller or for pitch bend.
+/// Each value holds until the next point.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Automation {
+    /// The type of automation.
+    pub automation_type: AutomationType,
+    /// The points, sorted by time. There is at most one point at any given time.
+    pub points: Vec<AutomationPoint>,
+}
+
+impl Automation {
+    pub fn new(automation_type: AutomationType) -> Self {
+        Self {
+            automation_type,
+            points: vec![],
+        }
+    }
+
+    /// Returns the value at `time`.
+    /// This is the value of the most recent point, or the default value if there are no points at or before `time`.
+    pub fn get_value(&self, time: u64) -> u16 {
+        match self.points.iter().rev().find(|p| p.time <= time) {
+            Some(point) => point.value,
+            None => self.automation_type.get_default(),
+        }
+    }
+
+    /// Set the value at `time`. If there isn't a point at `time`, this adds one.
+    pub fn set(&mut self, time: u64, value: u16) {
+        let value = u16::min(value, self.automation_type.get_max());
+        match self.points.binary_search_by(|p| p.time.cmp(&time)) {
+            Ok(index) => self.points[index].value = value,
+            Err(index) => self.points.insert(index, AutomationPoint { time, value }),
+        }
+    }
+
+    /// Remove the point at `time`. Returns true if there was a point.
+    pub fn remove(&mut self, time: u64) -> bool {
+        match self.points.binary_search_by(|p| p.time.cmp(&time)) {
+            Ok(index) => {
+                self.points.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the time of the last point before `time`.
+    pub fn get_previous_time(&self, time: u64) -> Option<u64> {
+        self.points
+            .iter()
+            .rev()
+            .find(|p| p.time < time)
+            .map(|p| p.time)
+    }
+
+    /// Returns the time of the first point after `time`.
+    pub fn get_next_time(&self, time: u64) -> Option<u64> {
+        self.points.iter().find(|p| p.time > time).map(|p| p.time)
+    }
+
+    /// Returns all points that can be played from `start` onwards.
+    /// The first point always sets the value at `start`.
+    pub fn get_playback_points(&self, start: u64) -> Vec<AutomationPoint> {
+        let mut points = vec![AutomationPoint {
+            time: start,
+            value: self.get_value(start),
+        }];
+        points.extend(self.points.iter().filter(|p| p.time > start).copied());
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automation::*;
+
+    #[test]
+    fn automation() {
+        let mut automation = Automation::new(AutomationType::PitchBend);
+        assert_eq!(automation.get_value(0), CENTER_PITCH_BEND);
+        automation.set(192, 20000);
+        automation.set(96, 0);
+        assert_eq!(automation.points.len(), 2);
+        assert_eq!(automation.points[0].time, 96);
+        assert_eq!(automation.points[1].value, MAX_PITCH_BEND);
+        assert_eq!(automation.get_value(0), CENTER_PITCH_BEND);
+        assert_eq!(automation.get_value(100), 0);
+        assert_eq!(automation.get_value(1000), MAX_PITCH_BEND);
+        automation.set(96, 100);
+        assert_eq!(automation.points.len(), 2);
+        assert_eq!(automation.get_value(96), 100);
+        assert_eq!(automation.get_previous_time(192), Some(96));
+        assert_eq!(automation.get_next_time(96), Some(192));
+        assert_eq!(automation.get_next_time(192), None);
+        let points = automation.get_playback_points(120);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].time, 120);
+        assert_eq!(points[0].value, 100);
+        assert!(automation.remove(96));
+        assert!(!automation.remove(96));
+        assert_eq!(automation.get_value(120), CENTER_PITCH_BEND);
+    }
+
+    #[test]
+    fn automation_type() {
+        let t = AutomationType::default();
+        assert_eq!(t, AUTOMATION_TYPES[0]);
+        assert_eq!(t.cycle(false), AutomationType::PitchBend);
+        assert_eq!(t.cycle(true).cycle(true), AUTOMATION_TYPES[2]);
+        assert_eq!(AutomationType::PitchBend.get_step(), 128);
+        assert_eq!(
+            AutomationType::Controller(PAN_CONTROLLER).cycle(true),
+            AUTOMATION_TYPES[1]
+        );
+    }
+
+    #[test]
+    fn automation_from_midi_message() {
+        assert_eq!(
+            AutomationType::from_midi_message(&[0xB3, SUSTAIN_CONTROLLER, 127]),
+            Some((AutomationType::Controller(SUSTAIN_CONTROLLER), 127))
+        );
+        assert_eq!(
+            AutomationType::from_midi_message(&[0xD0, 100, 0]),
+            Some((AutomationType::ChannelPressure, 100))
+        );
+        assert_eq!(
+            AutomationType::from_midi_message(&[0xE0, 0, 64]),
+            Some((AutomationType::PitchBend, CENTER_PITCH_BEND))
+        );
+        assert_eq!(
+            AutomationType::from_midi_message(&[0xE0, 127, 127]),
+            Some((AutomationType::PitchBend, MAX_PITCH_BEND))
+        );
+        assert_eq!(AutomationType::from_midi_message(&[0x90, 60, 127]), None);
+    }
+}
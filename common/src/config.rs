@@ -1,8 +1,11 @@
 use crate::fraction::*;
 use crate::Paths;
 use ini::{Ini, Properties};
+use serde::de::DeserializeOwned;
 use serde_json::from_str;
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::fs::{read_to_string, write};
 use std::str::FromStr;
 
 /// Load the config file.
@@ -19,6 +22,84 @@ pub fn load() -> Ini {
     }
 }
 
+/// Set key-value pairs in a section of the user config file. If there isn't a user config file, this creates one.
+///
+/// This edits the text of the file rather than re-serializing it so that the comments aren't lost.
+pub fn set_user_config(section: &str, values: &[(&str, String)]) {
+    let paths = Paths::get();
+    if !paths.user_ini_path.exists() {
+        paths.create_user_config();
+    }
+    match read_to_string(&paths.user_ini_path) {
+        Ok(text) => {
+            if let Err(error) = write(&paths.user_ini_path, set_values(&text, section, values)) {
+                panic!(
+                    "Error writing config.ini to {:?}: {}",
+                    paths.user_ini_path, error
+                )
+            }
+        }
+        Err(error) => panic!(
+            "Error loading config.ini from {:?}: {}",
+            paths.user_ini_path, error
+        ),
+    }
+}
+
+/// Set key-value pairs in a section of the text of a config file and return the new text.
+/// Existing keys are replaced in place. Missing keys are added to the end of the section. A missing section is added to the end of the file.
+fn set_values(text: &str, section: &str, values: &[(&str, String)]) -> String {
+    let header = format!("[{}]", section);
+    let mut lines: Vec<String> = text.lines().map(|line| line.to_string()).collect();
+    // Find the section or add it.
+    let start = match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => start,
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.len() - 1
+        }
+    };
+    // The section ends at the next section header.
+    let mut end = lines
+        .iter()
+        .skip(start + 1)
+        .position(|line| line.trim_start().starts_with('['))
+        .map(|index| start + 1 + index)
+        .unwrap_or(lines.len());
+    for (key, value) in values.iter() {
+        let line = format!("{} = {}", key, value);
+        match lines[start + 1..end].iter().position(|line| {
+            !line.trim_start().starts_with('#')
+                && line.split('=').next().map(|k| k.trim()) == Some(*key)
+        }) {
+            Some(index) => lines[start + 1 + index] = line,
+            None => {
+                // Add the key after the last non-empty line in the section.
+                let mut index = end;
+                while index > start + 1 && lines[index - 1].trim().is_empty() {
+                    index -= 1;
+                }
+                lines.insert(index, line);
+                end += 1;
+            }
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// Returns a section of the config file. If the section is missing, e.g. because the user config file was created before the section existed, this returns an empty section. See `parse_or`.
+pub fn get_section<'a>(config: &'a Ini, section: &str) -> Cow<'a, Properties> {
+    match config.section(Some(section)) {
+        Some(properties) => Cow::Borrowed(properties),
+        None => Cow::Owned(Properties::default()),
+    }
+}
+
 /// Parse a string `value` and returns an enum of type `T`.
 fn string_to_value<T>(value: &str) -> T
 where
@@ -46,6 +127,29 @@ where
     }
 }
 
+/// Parse a config key-value string pair into a value of type T.
+///
+/// Use this for keys that were added after the first release of Cacophony.
+/// If the key is missing, e.g. because the user config file was created before the key existed, this returns `default`.
+/// If the value is invalid, this prints a warning and returns `default`.
+///
+/// - `properties` The `Ini` properties.
+/// - `key` the key portion of the key-value pair.
+/// - `default` The value if the key is missing or invalid.
+pub fn parse_or<T>(properties: &Properties, key: &str, default: T) -> T
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    match properties.get(key) {
+        Some(value) => match value.trim().parse::<T>() {
+            Ok(value) => value,
+            Err(error) => invalid_value(key, value, error, default),
+        },
+        None => default,
+    }
+}
+
 /// Parse a 1 or 0 as a boolean.
 pub fn parse_bool(properties: &Properties, key: &str) -> bool {
     match properties.get(key) {
@@ -58,6 +162,41 @@ pub fn parse_bool(properties: &Properties, key: &str) -> bool {
     }
 }
 
+/// Parse a 1 or 0 as a boolean. See `parse_or`.
+pub fn parse_bool_or(properties: &Properties, key: &str, default: bool) -> bool {
+    match properties.get(key) {
+        Some(value) => match value.trim() {
+            "1" => true,
+            "0" => false,
+            _ => invalid_value(key, value, "Expected 1 or 0", default),
+        },
+        None => default,
+    }
+}
+
+/// Parse a list, e.g. `[1, 2, 3]`. See `parse_or`.
+pub fn parse_list_or<T>(properties: &Properties, key: &str, default: Vec<T>) -> Vec<T>
+where
+    T: DeserializeOwned,
+{
+    match properties.get(key) {
+        Some(value) => match from_str::<Vec<T>>(value) {
+            Ok(value) => value,
+            Err(error) => invalid_value(key, value, error, default),
+        },
+        None => default,
+    }
+}
+
+/// Print a warning about an invalid config value and return `default`.
+fn invalid_value<T, E: Display>(key: &str, value: &str, error: E, default: T) -> T {
+    println!(
+        "WARNING! Invalid value {} for key {}: {}. Using the default value.",
+        value, key, error
+    );
+    default
+}
+
 /// Parse a list of fraction strings to PPQ values.
 pub fn parse_fractions(properties: &Properties, key: &str) -> Vec<f32> {
     match properties.get(key) {
@@ -143,3 +282,53 @@ fn parse_fraction_kv(key: &str, value: &str) -> Fraction {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{get_section, parse_bool_or, parse_list_or, parse_or, set_values};
+    use ini::Ini;
+
+    #[test]
+    fn config_set_values() {
+        let text = "[A]\n# A comment.\nx = 0\n\n[B]\ny = 1\n";
+        // Replace a key.
+        assert_eq!(
+            set_values(text, "A", &[("x", "2".to_string())]),
+            "[A]\n# A comment.\nx = 2\n\n[B]\ny = 1\n"
+        );
+        // Add a key.
+        assert_eq!(
+            set_values(text, "A", &[("z", String::new())]),
+            "[A]\n# A comment.\nx = 0\nz = \n\n[B]\ny = 1\n"
+        );
+        // Add a section.
+        assert_eq!(
+            set_values(text, "C", &[("w", "3".to_string())]),
+            "[A]\n# A comment.\nx = 0\n\n[B]\ny = 1\n\n[C]\nw = 3\n"
+        );
+    }
+
+    #[test]
+    fn config_parse_or() {
+        let ini = Ini::load_from_str("[A]\nx = 2\ny = 1\n").unwrap();
+        let section = ini.section(Some("A")).unwrap();
+        assert_eq!(parse_or::<u64>(section, "x", 0), 2);
+        assert!(parse_bool_or(section, "y", false));
+        // Missing keys.
+        assert_eq!(parse_or::<u64>(section, "z", 3), 3);
+        assert!(parse_bool_or(section, "z", true));
+        // Invalid values.
+        let ini = Ini::load_from_str("[A]\nx = -2\ny = yes\n").unwrap();
+        let section = ini.section(Some("A")).unwrap();
+        assert_eq!(parse_or::<u64>(section, "x", 4), 4);
+        assert!(parse_bool_or(section, "y", true));
+        // Lists.
+        let ini = Ini::load_from_str("[A]\nx = [1, 2]\ny = [1, 2\n").unwrap();
+        let section = ini.section(Some("A")).unwrap();
+        assert_eq!(parse_list_or::<u8>(section, "x", vec![]), vec![1, 2]);
+        assert_eq!(parse_list_or::<u8>(section, "y", vec![3]), vec![3]);
+        assert_eq!(parse_list_or::<u8>(section, "z", vec![4]), vec![4]);
+        // Missing sections.
+        assert!(get_section(&ini, "B").is_empty());
+    }
+}
@@ -0,0 +1,27 @@
+use crate::config::{parse_bool_or, parse_or};
+use ini::Ini;
+
+/// Count-in and pre-roll settings for real-time recording.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CountIn {
+    /// Before recording, the metronome clicks for this many beats or bars.
+    pub count_in: u64,
+    /// If true, `count_in` is a number of bars. If false, it is a number of beats.
+    pub count_in_bars: bool,
+    /// Before recording, the music starts to play this many beats before the playback time.
+    pub pre_roll: u64,
+}
+
+impl CountIn {
+    pub fn new(config: &Ini) -> Self {
+        let section = config.section(Some("PIANO_ROLL")).unwrap();
+        let count_in = parse_or(section, "count_in", 0);
+        let count_in_bars = parse_bool_or(section, "count_in_bars", false);
+        let pre_roll = parse_or(section, "pre_roll", 0);
+        Self {
+            count_in,
+            count_in_bars,
+            pre_roll,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::{Index, U64orF32, MAX_VOLUME, PPQ_U};
 use serde::{Deserialize, Serialize};
 
 /// Booleans and numerical values describing the input state.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct InputState {
     /// If true, we will accept musical input.
     pub armed: bool,
@@ -14,6 +14,12 @@ pub struct InputState {
     pub use_volume: bool,
     /// The input beat in PPQ.
     pub beat: U64orF32,
+    /// If true and the track is armed, notes will be recorded in real time while music is playing.
+    #[serde(default)]
+    pub record: bool,
+    /// If true, new notes from qwerty and MIDI input are snapped to the key of the music.
+    #[serde(default)]
+    pub snap_to_key: bool,
     /// If true, music is playing or exporting.
     #[serde(skip)]
     pub is_playing: bool,
@@ -27,6 +33,8 @@ impl Default for InputState {
             volume: Index::new(MAX_VOLUME, MAX_VOLUME + 1),
             use_volume: true,
             beat: U64orF32::from(PPQ_U),
+            record: false,
+            snap_to_key: false,
             is_playing: false,
         }
     }
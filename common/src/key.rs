@@ -0,0 +1,218 @@
+use crate::{MAX_NOTE, MIN_NOTE};
+use serde::{Deserialize, Serialize};
+
+/// The name of each pitch class, starting from C.
+pub const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Every scale type, in the order that the user cycles through them.
+pub const SCALE_TYPES: [ScaleType; 13] = [
+    ScaleType::Chromatic,
+    ScaleType::Major,
+    ScaleType::Minor,
+    ScaleType::HarmonicMinor,
+    ScaleType::MelodicMinor,
+    ScaleType::Dorian,
+    ScaleType::Phrygian,
+    ScaleType::Lydian,
+    ScaleType::Mixolydian,
+    ScaleType::Locrian,
+    ScaleType::MajorPentatonic,
+    ScaleType::MinorPentatonic,
+    ScaleType::Custom,
+];
+
+/// The type of scale of a key.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone, Hash, Deserialize, Serialize)]
+pub enum ScaleType {
+    /// Every note is in the key. This is the same as not having a key.
+    #[default]
+    Chromatic,
+    Major,
+    Minor,
+    HarmonicMinor,
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+    /// A user-defined set of pitch classes.
+    Custom,
+}
+
+impl ScaleType {
+    /// Returns the pitch classes of the scale relative to the root, or None if this is a custom scale.
+    fn get_intervals(&self) -> Option<&'static [usize]> {
+        match self {
+            Self::Chromatic => Some(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+            Self::Major => Some(&[0, 2, 4, 5, 7, 9, 11]),
+            Self::Minor => Some(&[0, 2, 3, 5, 7, 8, 10]),
+            Self::HarmonicMinor => Some(&[0, 2, 3, 5, 7, 8, 11]),
+            Self::MelodicMinor => Some(&[0, 2, 3, 5, 7, 9, 11]),
+            Self::Dorian => Some(&[0, 2, 3, 5, 7, 9, 10]),
+            Self::Phrygian => Some(&[0, 1, 3, 5, 7, 8, 10]),
+            Self::Lydian => Some(&[0, 2, 4, 6, 7, 9, 11]),
+            Self::Mixolydian => Some(&[0, 2, 4, 5, 7, 9, 10]),
+            Self::Locrian => Some(&[0, 1, 3, 5, 6, 8, 10]),
+            Self::MajorPentatonic => Some(&[0, 2, 4, 7, 9]),
+            Self::MinorPentatonic => Some(&[0, 3, 5, 7, 10]),
+            Self::Custom => None,
+        }
+    }
+}
+
+/// The key of the music: A root note and a scale.
+/// Notes can be moved by scale degree, input can be snapped to the key, and the piano roll shades rows that are out of the key.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize, Serialize)]
+pub struct Key {
+    /// The pitch class of the root note. 0 is C, 1 is C#, etc.
+    pub root: u8,
+    /// The type of scale.
+    pub scale_type: ScaleType,
+    /// If `scale_type` is `Custom`, these are the pitch classes of the scale relative to the root.
+    pub custom: [bool; 12],
+}
+
+impl Default for Key {
+    fn default() -> Self {
+        Self {
+            root: 0,
+            scale_type: ScaleType::default(),
+            custom: [true; 12],
+        }
+    }
+}
+
+impl Key {
+    /// Returns true if every note is in the key.
+    pub fn is_chromatic(&self) -> bool {
+        self.get_pitch_classes().iter().all(|p| *p)
+    }
+
+    /// Returns true if a MIDI note is in the key.
+    pub fn contains(&self, note: u8) -> bool {
+        self.get_pitch_classes()[(note as usize + 12 - self.root as usize % 12) % 12]
+    }
+
+    /// Move a note up or down by a number of scale degrees. A note that isn't in the key moves to the nearest note in the key in that direction.
+    ///
+    /// Returns None if the note would go out of range.
+    pub fn transpose(&self, note: u8, degrees: u8, up: bool) -> Option<u8> {
+        let mut note = note;
+        for _ in 0..degrees {
+            loop {
+                note = if up {
+                    note.checked_add(1).filter(|n| *n <= MAX_NOTE)?
+                } else {
+                    note.checked_sub(1).filter(|n| *n >= MIN_NOTE)?
+                };
+                if self.contains(note) {
+                    break;
+                }
+            }
+        }
+        Some(note)
+    }
+
+    /// Returns the nearest note in the key. If two notes are equally near, this returns the lower note.
+    pub fn snap(&self, note: u8) -> u8 {
+        (0..12)
+            .find_map(|d| {
+                [note.checked_sub(d), note.checked_add(d)]
+                    .into_iter()
+                    .flatten()
+                    .find(|n| *n <= MAX_NOTE && self.contains(*n))
+            })
+            .unwrap_or(note)
+    }
+
+    /// Returns the name of the root note.
+    pub fn get_root_name(&self) -> &str {
+        PITCH_CLASS_NAMES[self.root as usize % 12]
+    }
+
+    /// Returns the pitch classes of the scale relative to the root.
+    fn get_pitch_classes(&self) -> [bool; 12] {
+        match self.scale_type.get_intervals() {
+            Some(intervals) => {
+                let mut pitch_classes = [false; 12];
+                intervals.iter().for_each(|i| pitch_classes[*i] = true);
+                pitch_classes
+            }
+            None => self.custom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::note::MIDDLE_C;
+    use crate::{Key, ScaleType};
+
+    #[test]
+    fn key_contains() {
+        let mut key = Key::default();
+        assert!(key.is_chromatic());
+        assert!((0..128).all(|n| key.contains(n)));
+        // D major.
+        key.root = 2;
+        key.scale_type = ScaleType::Major;
+        assert!(!key.is_chromatic());
+        assert!(key.contains(MIDDLE_C + 1));
+        assert!(!key.contains(MIDDLE_C));
+        assert!(key.contains(MIDDLE_C + 6));
+        assert_eq!(key.get_root_name(), "D");
+        // A custom scale.
+        key.scale_type = ScaleType::Custom;
+        key.custom = [false; 12];
+        key.custom[0] = true;
+        key.custom[7] = true;
+        assert!(key.contains(MIDDLE_C + 2));
+        assert!(key.contains(MIDDLE_C + 9));
+        assert!(!key.contains(MIDDLE_C + 4));
+    }
+
+    #[test]
+    fn key_transpose() {
+        let key = Key {
+            root: 0,
+            scale_type: ScaleType::Major,
+            ..Default::default()
+        };
+        // C -> D -> E -> F.
+        assert_eq!(key.transpose(MIDDLE_C, 1, true), Some(MIDDLE_C + 2));
+        assert_eq!(key.transpose(MIDDLE_C, 3, true), Some(MIDDLE_C + 5));
+        // C -> B.
+        assert_eq!(key.transpose(MIDDLE_C, 1, false), Some(MIDDLE_C - 1));
+        // C# isn't in the key, so it moves to the next note in the key.
+        assert_eq!(key.transpose(MIDDLE_C + 1, 1, true), Some(MIDDLE_C + 2));
+        assert_eq!(key.transpose(MIDDLE_C + 1, 1, false), Some(MIDDLE_C));
+        // Out of range.
+        assert_eq!(key.transpose(127, 1, true), None);
+        // A chromatic key moves by half-steps.
+        assert_eq!(
+            Key::default().transpose(MIDDLE_C, 1, true),
+            Some(MIDDLE_C + 1)
+        );
+    }
+
+    #[test]
+    fn key_snap() {
+        let key = Key {
+            root: 0,
+            scale_type: ScaleType::MajorPentatonic,
+            ..Default::default()
+        };
+        assert_eq!(key.snap(MIDDLE_C), MIDDLE_C);
+        // C# is equally near C and D, so it snaps down.
+        assert_eq!(key.snap(MIDDLE_C + 1), MIDDLE_C);
+        // F is nearer to E than to G.
+        assert_eq!(key.snap(MIDDLE_C + 5), MIDDLE_C + 4);
+        // A# is nearer to A than to C.
+        assert_eq!(key.snap(MIDDLE_C + 10), MIDDLE_C + 9);
+    }
+}
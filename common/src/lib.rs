@@ -2,7 +2,7 @@
 //!
 //! There are two app-state-level structs defined in this crate:
 //!
-//! 1. `State` is *most* of the app state. It contains any data that can be placed on the undo/redo stacks. Because the undo/redo stacks contain entire `State` structs, the struct needs to be as small as possible.
+//! 1. `State` is *most* of the app state. It contains any data that can be placed on the undo/redo stacks. Because the undo/redo stacks contain entire `State` structs, the struct needs to be as small as possible. Notes are shared between clones of `State` until they're modified (see `Notes`).
 //! 2. `PathsState` The state of directories, files, etc. defined by the user navigating through open-file dialogues. This isn't part of `State` because nothing here should go on the undo/redo stacks.
 //!
 //! There are two other state objects that aren't defined in this crate:
@@ -13,28 +13,53 @@
 //! `common` is designed such that any Cacophony crate can use it, but itself does not depend on any Cacophony crates.
 
 pub mod args;
+mod audio_device_settings;
+mod automation;
 pub mod config;
+mod count_in;
 mod index;
 mod input_state;
+mod key;
 mod midi_track;
 mod music;
 mod note;
+mod notes;
 mod panel_type;
 pub mod paths;
 mod paths_state;
+mod relocation;
 mod state;
+mod tempo;
 pub mod time;
+mod time_signature;
+mod tuning;
+mod undo_history;
 pub mod view;
+pub use audio_device_settings::{AudioBackend, AudioDeviceSettings};
+pub use automation::*;
+pub use count_in::CountIn;
 pub use index::Index;
 mod indexed_values;
 pub use indexed_values::IndexedValues;
 pub use input_state::InputState;
-pub use midi_track::MidiTrack;
+pub use key::{Key, ScaleType, PITCH_CLASS_NAMES, SCALE_TYPES};
+pub use midi_track::{
+    MidiTrack, CENTER_PAN, MAX_PAN, PAN_CONTROLLER, PERCUSSION_BANK, PERCUSSION_CHANNEL,
+    PERCUSSION_NOTE_DURATION,
+};
 pub use music::*;
-pub use note::{Note, MAX_NOTE, MIN_NOTE, NOTE_NAMES};
+pub use note::{get_drum_name, Note, MAX_DRUM, MAX_NOTE, MIN_DRUM, MIN_NOTE, NOTE_NAMES};
+pub use notes::Notes;
 pub use panel_type::PanelType;
 pub use paths::Paths;
+pub use relocation::{
+    find_soundfont, get_content_hash, get_relative_path, get_soundfont_search_paths, Relocation,
+};
 pub use state::State;
+pub use tempo::Tempo;
+pub use time_signature::*;
+pub use tuning::{KeyboardMapping, Scale, Tuning};
+pub use undo_history::UndoHistory;
 use view::View;
 mod edit_mode;
 pub mod music_panel_field;
@@ -1,6 +1,19 @@
-use crate::{Note, MAX_VOLUME};
+use crate::{Automation, AutomationType, Note, Notes, MAX_VOLUME, PPQ_U};
 use serde::{Deserialize, Serialize};
 
+/// The maximum pan value (hard right).
+pub const MAX_PAN: u8 = 127;
+/// The centered pan value.
+pub const CENTER_PAN: u8 = 64;
+/// The MIDI controller number for pan.
+pub const PAN_CONTROLLER: u8 = 10;
+/// The duration in PPQ of new notes in a percussion track. Percussion notes are one-shots, so their duration doesn't depend on the input beat.
+pub const PERCUSSION_NOTE_DURATION: u64 = PPQ_U / 4;
+/// The General MIDI percussion channel (channel 10, zero-indexed).
+pub const PERCUSSION_CHANNEL: u8 = 9;
+/// The SoundFont bank that has the percussion presets.
+pub const PERCUSSION_BANK: u32 = 128;
+
 /// A MIDI track has some notes.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MidiTrack {
@@ -8,12 +21,21 @@ pub struct MidiTrack {
     pub channel: u8,
     /// A gain value (0-127) for this track.
     pub gain: u8,
-    /// The notes in the track.
-    pub notes: Vec<Note>,
+    /// A stereo pan value (0-127) for this track. 0 is hard left, 64 is centered, and 127 is hard right.
+    #[serde(default = "default_pan")]
+    pub pan: u8,
+    /// The notes in the track. Clones of the track share the notes until they're modified.
+    pub notes: Notes,
+    /// Controller and pitch bend automation lanes. There is at most one lane per automation type.
+    #[serde(default)]
+    pub automation: Vec<Automation>,
     /// True if the track is muted.
     pub mute: bool,
     /// True if the track is soloed.
     pub solo: bool,
+    /// True if this is a percussion track. Percussion tracks use the SoundFont's percussion bank and are exported to the percussion channel.
+    #[serde(default)]
+    pub percussion: bool,
 }
 
 impl MidiTrack {
@@ -21,9 +43,19 @@ impl MidiTrack {
         Self {
             channel,
             gain: MAX_VOLUME,
-            notes: vec![],
+            pan: CENTER_PAN,
+            notes: Notes::default(),
+            automation: vec![],
             mute: false,
             solo: false,
+            percussion: false,
+        }
+    }
+
+    pub fn new_percussion(channel: u8) -> Self {
+        Self {
+            percussion: true,
+            ..Self::new(channel)
         }
     }
 
@@ -32,6 +64,17 @@ impl MidiTrack {
         self.notes.iter().map(|n| n.end).max()
     }
 
+    /// Returns the duration in PPQ of a new note.
+    ///
+    /// - `beat` The input beat in PPQ. This is ignored if this is a percussion track.
+    pub fn get_new_note_duration(&self, beat: u64) -> u64 {
+        if self.percussion {
+            PERCUSSION_NOTE_DURATION
+        } else {
+            beat
+        }
+    }
+
     /// Returns the track gain as a float between 0 and 1.
     pub fn get_gain_f(&self) -> f32 {
         self.gain as f32 / MAX_VOLUME as f32
@@ -49,6 +92,28 @@ impl MidiTrack {
         notes.sort();
         notes
     }
+
+    /// Returns the automation lane of type `automation_type`, if any.
+    pub fn get_automation(&self, automation_type: &AutomationType) -> Option<&Automation> {
+        self.automation
+            .iter()
+            .find(|a| a.automation_type == *automation_type)
+    }
+
+    /// Returns the automation lane of type `automation_type`. If there isn't one, this adds one.
+    pub fn get_automation_mut(&mut self, automation_type: &AutomationType) -> &mut Automation {
+        match self
+            .automation
+            .iter()
+            .position(|a| a.automation_type == *automation_type)
+        {
+            Some(index) => &mut self.automation[index],
+            None => {
+                self.automation.push(Automation::new(*automation_type));
+                self.automation.last_mut().unwrap()
+            }
+        }
+    }
 }
 
 impl Clone for MidiTrack {
@@ -56,9 +121,16 @@ impl Clone for MidiTrack {
         Self {
             channel: self.channel,
             gain: self.gain,
+            pan: self.pan,
             notes: self.notes.clone(),
+            automation: self.automation.clone(),
             mute: self.mute,
             solo: self.solo,
+            percussion: self.percussion,
         }
     }
 }
+
+fn default_pan() -> u8 {
+    CENTER_PAN
+}
@@ -1,4 +1,5 @@
 use super::midi_track::MidiTrack;
+use crate::Key;
 use serde::{Deserialize, Serialize};
 
 /// Tracks, notes, and metadata.
@@ -8,6 +9,9 @@ pub struct Music {
     pub midi_tracks: Vec<MidiTrack>,
     /// The index of the selected track.
     pub selected: Option<usize>,
+    /// The key of the music.
+    #[serde(default)]
+    pub key: Key,
 }
 
 impl Music {
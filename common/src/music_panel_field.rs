@@ -7,4 +7,5 @@ pub enum MusicPanelField {
     Name,
     BPM,
     Gain,
+    Key,
 }
@@ -23,6 +23,28 @@ pub const NOTE_NAMES: [&str; 115] = [
     "C#1", "C1", "B1", "A#1", "A1", "G#1", "G0", "F#0", "F0", "E0", "D#0", "D0", "C#0",
 ];
 
+/// The MIDI value of the lowest General MIDI percussion note.
+pub const MIN_DRUM: u8 = 35;
+/// The MIDI value of the highest General MIDI percussion note.
+pub const MAX_DRUM: u8 = 81;
+/// Abbreviated General MIDI percussion names, in order starting from `MIN_DRUM`.
+/// These are short enough to fit in the piano roll's note name column.
+pub const DRUM_NAMES: [&str; 47] = [
+    "ABD", "BD1", "SSt", "ASn", "Clp", "ESn", "LFT", "CHH", "HFT", "PHH", "LT", "OHH", "LMT",
+    "HMT", "CC1", "HT", "RC1", "ChC", "RB", "Tmb", "SpC", "Cow", "CC2", "Vib", "RC2", "HBo", "LBo",
+    "MHC", "OHC", "LC", "HTi", "LTi", "HAg", "LAg", "Cab", "Mar", "SWh", "LWh", "SGu", "LGu",
+    "Clv", "HWB", "LWB", "MCu", "OCu", "MTr", "OTr",
+];
+
+/// Returns the abbreviated General MIDI percussion name of a MIDI note, or the note name if it isn't a percussion note.
+pub fn get_drum_name(note: u8) -> &'static str {
+    if (MIN_DRUM..=MAX_DRUM).contains(&note) {
+        DRUM_NAMES[(note - MIN_DRUM) as usize]
+    } else {
+        NOTE_NAMES[127 - note as usize]
+    }
+}
+
 /// A MIDI note with a start bar time and a duration bar time.
 #[derive(Copy, Clone, PartialEq, Eq, Deserialize)]
 pub struct Note {
@@ -97,7 +119,7 @@ impl Serialize for Note {
 
 #[cfg(test)]
 mod tests {
-    use crate::note::MIDDLE_C;
+    use crate::note::{get_drum_name, MAX_DRUM, MIDDLE_C, MIN_DRUM};
     use crate::{Note, MAX_VOLUME, PPQ_U};
     use serde_json::{from_str, to_string};
 
@@ -123,6 +145,16 @@ mod tests {
         assert_eq!(note.end, PPQ_U, "{:?}", note);
     }
 
+    #[test]
+    fn note_drum_name() {
+        assert_eq!(get_drum_name(MIN_DRUM), "ABD");
+        assert_eq!(get_drum_name(38), "ASn");
+        assert_eq!(get_drum_name(42), "CHH");
+        assert_eq!(get_drum_name(MAX_DRUM), "OTr");
+        // Fall back to note names.
+        assert_eq!(get_drum_name(MIDDLE_C + 24), "C6");
+    }
+
     fn get_note() -> Note {
         Note {
             note: MIDDLE_C,
@@ -0,0 +1,108 @@
+use crate::Note;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// The notes in a track.
+///
+/// Clones share the same notes until one of them is modified, at which point that clone gets its own copy (copy-on-write).
+/// This way, cloning a `State` for the undo stack doesn't copy every note in the music, and an edit only copies the notes of the tracks that it modifies.
+///
+/// `Notes` dereferences to `Vec<Note>`, so it can be read and modified like a vector.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Notes(Arc<Vec<Note>>);
+
+impl Notes {
+    /// Returns true if `self` and `other` share the same notes.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns a pointer to the shared notes. This can be used to count how many distinct copies of notes exist.
+    pub fn as_ptr(&self) -> *const Vec<Note> {
+        Arc::as_ptr(&self.0)
+    }
+}
+
+impl Deref for Notes {
+    type Target = Vec<Note>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Notes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl From<Vec<Note>> for Notes {
+    fn from(value: Vec<Note>) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl FromIterator<Note> for Notes {
+    fn from_iter<T: IntoIterator<Item = Note>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<Note>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a Notes {
+    type Item = &'a Note;
+    type IntoIter = std::slice::Iter<'a, Note>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Serialize for Notes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Notes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<Note>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Note, Notes};
+
+    #[test]
+    fn notes_copy_on_write() {
+        let note = Note {
+            note: 60,
+            velocity: 127,
+            start: 0,
+            end: 192,
+        };
+        let mut n0 = Notes::from(vec![note]);
+        let n1 = n0.clone();
+        assert!(n0.ptr_eq(&n1));
+        // Reading doesn't copy.
+        assert_eq!(n0.len(), 1);
+        assert!(n0.ptr_eq(&n1));
+        // Writing copies.
+        n0.push(note);
+        assert!(!n0.ptr_eq(&n1));
+        assert_eq!(n0.len(), 2);
+        assert_eq!(n1.len(), 1);
+        // Writing to an unshared copy doesn't copy.
+        let ptr = n0.as_ptr();
+        n0[1].note = 62;
+        assert_eq!(n0.as_ptr(), ptr);
+    }
+}
@@ -10,6 +10,8 @@ pub enum Extension {
     MP3,
     Ogg,
     Flac,
+    Scl,
+    Kbm,
 }
 
 impl Extension {
@@ -67,6 +69,20 @@ impl Extension {
                     "flac"
                 }
             }
+            Self::Scl => {
+                if period {
+                    ".scl"
+                } else {
+                    "scl"
+                }
+            }
+            Self::Kbm => {
+                if period {
+                    ".kbm"
+                } else {
+                    "kbm"
+                }
+            }
         }
     }
 }
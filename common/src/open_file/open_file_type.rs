@@ -8,8 +8,14 @@ pub enum OpenFileType {
     SoundFont,
     /// Write a save file.
     WriteSave,
+    /// Write a save file and copies of its SoundFonts to a directory.
+    WriteBundle,
     /// Set the export path.
     Export,
     /// Import a MIDI file.
     ImportMidi,
+    /// Read a Scala scale file.
+    Scale,
+    /// Read a Scala keyboard mapping file.
+    KeyboardMapping,
 }
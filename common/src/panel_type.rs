@@ -12,4 +12,9 @@ pub enum PanelType {
     ExportSettings,
     Quit,
     Links,
+    AudioDevice,
+    Error,
+    Relocate,
+    Recover,
+    UndoHistory,
 }
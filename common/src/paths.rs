@@ -5,6 +5,7 @@ use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 const CONFIG_FILENAME: &str = "config.ini";
+const RECOVERY_FILENAME: &str = "recovery.cac";
 
 /// Global reference to paths.
 static PATHS: OnceLock<Paths> = OnceLock::new();
@@ -32,6 +33,8 @@ pub struct Paths {
     pub default_soundfont_path: PathBuf,
     /// The path to the data/ directory itself.
     pub data_directory: PathBuf,
+    /// The path to the autosaved recovery file. If this exists when the app launches, the app didn't quit cleanly.
+    pub recovery_path: PathBuf,
 }
 
 impl Paths {
@@ -71,6 +74,7 @@ impl Paths {
         let export_directory = get_directory("exports", &user_directory);
         let splash_path = data_directory.join("splash.png");
         let default_soundfont_path = data_directory.join("CT1MBGMRSV1.06.sf2");
+        let recovery_path = user_directory.join(RECOVERY_FILENAME);
         Self {
             default_ini_path,
             user_directory,
@@ -82,6 +86,7 @@ impl Paths {
             splash_path,
             default_soundfont_path,
             data_directory: data_directory.to_path_buf(),
+            recovery_path,
         }
     }
 
@@ -18,6 +18,12 @@ pub struct PathsState {
     pub exports: FileAndDirectory,
     /// When the user wants to import a MIDI file, this is the path.
     pub midis: FileAndDirectory,
+    /// When the user wants to load a Scala scale or keyboard mapping, it will default to this directory.
+    #[serde(default = "default_tunings")]
+    pub tunings: FileAndDirectory,
+    /// If true, SoundFont paths are written to the save file relative to the save file's directory.
+    #[serde(default)]
+    pub relative_soundfont_paths: bool,
     /// The child paths within the current working directory.
     #[serde(skip_serializing, skip_deserializing)]
     pub children: ChildPaths,
@@ -32,11 +38,13 @@ impl PathsState {
         let saves = FileAndDirectory::new_directory(paths.saves_directory.clone());
         let exports = FileAndDirectory::new_directory(paths.export_directory.clone());
         let midis = FileAndDirectory::new_directory(paths.user_directory.clone());
+        let tunings = FileAndDirectory::new_directory(paths.user_directory.clone());
         Self {
             soundfonts,
             saves,
             exports,
             midis,
+            tunings,
             ..Default::default()
         }
     }
@@ -45,9 +53,12 @@ impl PathsState {
     pub fn get_directory(&self) -> &FileOrDirectory {
         match self.open_file_type {
             OpenFileType::Export => &self.exports.directory,
-            OpenFileType::ReadSave | OpenFileType::WriteSave => &self.saves.directory,
+            OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
+                &self.saves.directory
+            }
             OpenFileType::SoundFont => &self.soundfonts.directory,
             OpenFileType::ImportMidi => &self.midis.directory,
+            OpenFileType::Scale | OpenFileType::KeyboardMapping => &self.tunings.directory,
         }
     }
 
@@ -55,7 +66,7 @@ impl PathsState {
     pub fn get_filename(&self) -> Option<String> {
         match self.open_file_type {
             OpenFileType::Export => Some(self.exports.get_filename()),
-            OpenFileType::WriteSave => Some(self.saves.get_filename()),
+            OpenFileType::WriteSave | OpenFileType::WriteBundle => Some(self.saves.get_filename()),
             _ => None,
         }
     }
@@ -66,7 +77,7 @@ impl PathsState {
             OpenFileType::Export => {
                 Self::up_directory_type(&mut self.exports.directory, &mut self.children, extension)
             }
-            OpenFileType::ReadSave | OpenFileType::WriteSave => {
+            OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
                 Self::up_directory_type(&mut self.saves.directory, &mut self.children, extension)
             }
             OpenFileType::SoundFont => Self::up_directory_type(
@@ -77,6 +88,9 @@ impl PathsState {
             OpenFileType::ImportMidi => {
                 Self::up_directory_type(&mut self.midis.directory, &mut self.children, extension)
             }
+            OpenFileType::Scale | OpenFileType::KeyboardMapping => {
+                Self::up_directory_type(&mut self.tunings.directory, &mut self.children, extension)
+            }
         }
     }
 
@@ -92,11 +106,14 @@ impl PathsState {
                     } else {
                         let cwd0 = match &self.open_file_type {
                             OpenFileType::Export => self.exports.directory.path.to_path_buf(),
-                            OpenFileType::ReadSave | OpenFileType::WriteSave => {
-                                self.saves.directory.path.to_path_buf()
-                            }
+                            OpenFileType::ReadSave
+                            | OpenFileType::WriteSave
+                            | OpenFileType::WriteBundle => self.saves.directory.path.to_path_buf(),
                             OpenFileType::SoundFont => self.soundfonts.directory.path.to_path_buf(),
                             OpenFileType::ImportMidi => self.midis.directory.path.to_path_buf(),
+                            OpenFileType::Scale | OpenFileType::KeyboardMapping => {
+                                self.tunings.directory.path.to_path_buf()
+                            }
                         };
                         let cwd1 = self.children.children[*selected].path.clone();
                         // Set the children.
@@ -106,7 +123,9 @@ impl PathsState {
                             OpenFileType::Export => {
                                 self.exports.directory = FileOrDirectory::new(&cwd1)
                             }
-                            OpenFileType::ReadSave | OpenFileType::WriteSave => {
+                            OpenFileType::ReadSave
+                            | OpenFileType::WriteSave
+                            | OpenFileType::WriteBundle => {
                                 self.saves.directory = FileOrDirectory::new(&cwd1)
                             }
                             OpenFileType::SoundFont => {
@@ -115,6 +134,9 @@ impl PathsState {
                             OpenFileType::ImportMidi => {
                                 self.midis.directory = FileOrDirectory::new(&cwd1)
                             }
+                            OpenFileType::Scale | OpenFileType::KeyboardMapping => {
+                                self.tunings.directory = FileOrDirectory::new(&cwd1)
+                            }
                         }
                         true
                     }
@@ -150,9 +172,12 @@ impl PathsState {
         };
         match &self.open_file_type {
             OpenFileType::Export => self.exports.filename = f,
-            OpenFileType::ReadSave | OpenFileType::WriteSave => self.saves.filename = f,
+            OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
+                self.saves.filename = f
+            }
             OpenFileType::SoundFont => (),
             OpenFileType::ImportMidi => self.midis.filename = f,
+            OpenFileType::Scale | OpenFileType::KeyboardMapping => self.tunings.filename = f,
         }
     }
 
@@ -160,9 +185,12 @@ impl PathsState {
     pub fn get_path(&self) -> PathBuf {
         match &self.open_file_type {
             OpenFileType::Export => self.exports.get_path(),
-            OpenFileType::ReadSave | OpenFileType::WriteSave => self.saves.get_path(),
+            OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
+                self.saves.get_path()
+            }
             OpenFileType::SoundFont => self.soundfonts.get_path(),
             OpenFileType::ImportMidi => self.midis.get_path(),
+            OpenFileType::Scale | OpenFileType::KeyboardMapping => self.tunings.get_path(),
         }
     }
 
@@ -182,3 +210,8 @@ impl PathsState {
         }
     }
 }
+
+/// The default tunings directory, for save files that don't have one.
+fn default_tunings() -> FileAndDirectory {
+    FileAndDirectory::new_directory(Paths::get().user_directory.clone())
+}
@@ -7,4 +7,5 @@ pub enum PianoRollMode {
     View,
     Edit,
     Select,
+    Automation,
 }
@@ -0,0 +1,200 @@
+use crate::config::{get_section, parse_list_or};
+use ini::Ini;
+use std::fs::{read_dir, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+/// The config.ini section of the SoundFont settings.
+const SECTION: &str = "SOUNDFONTS";
+/// The number of bytes at the start and end of a file that are hashed.
+const HASH_LENGTH: u64 = 65536;
+/// The maximum depth of sub-directories that will be searched.
+const MAX_SEARCH_DEPTH: usize = 4;
+/// The FNV-1a offset basis and prime.
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// A SoundFont that was missing when a save file was loaded.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Relocation {
+    /// The track's channel.
+    pub channel: u8,
+    /// The path to the missing SoundFont.
+    pub path: PathBuf,
+    /// A file that might be the missing SoundFont.
+    pub candidate: Option<PathBuf>,
+    /// The index of the bank that the track was using.
+    pub bank_index: usize,
+    /// The index of the preset that the track was using.
+    pub preset_index: usize,
+}
+
+/// Returns the user-defined directories that will be searched for missing SoundFonts.
+pub fn get_soundfont_search_paths(config: &Ini) -> Vec<PathBuf> {
+    parse_list_or(&get_section(config, SECTION), "search_paths", vec![])
+}
+
+/// Returns a hash of a file's contents, or None if the file can't be read.
+///
+/// To keep this fast for large SoundFonts, only the length of the file and the bytes at its start and end are hashed.
+pub fn get_content_hash(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let length = file.metadata().ok()?.len();
+    let mut hash = hash_bytes(FNV_OFFSET, &length.to_le_bytes());
+    let mut buffer = vec![0; length.min(HASH_LENGTH) as usize];
+    file.read_exact(&mut buffer).ok()?;
+    hash = hash_bytes(hash, &buffer);
+    if length > HASH_LENGTH {
+        let start = (length - HASH_LENGTH).max(HASH_LENGTH);
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut buffer = vec![0; (length - start) as usize];
+        file.read_exact(&mut buffer).ok()?;
+        hash = hash_bytes(hash, &buffer);
+    }
+    Some(hash)
+}
+
+/// Returns `path` relative to the directory `base`, or None if there is no relative path (e.g. they are on different drives).
+pub fn get_relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(base_components.iter())
+        .take_while(|(p, b)| p == b)
+        .count();
+    // The paths don't have the same root.
+    if common == 0 {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in path_components.iter().skip(common) {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+/// Search `directories` and their sub-directories for a file that might be `path`.
+/// A file with the same name is preferred. Otherwise, if `hash` isn't None, this looks for a file with the same extension and the same content hash.
+pub fn find_soundfont(path: &Path, hash: Option<u64>, directories: &[PathBuf]) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?.to_lowercase();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let mut files = vec![];
+    for directory in directories.iter() {
+        get_files(directory, &extension, 0, &mut files);
+    }
+    // Try to find a file with the same name.
+    match files.iter().find(|f| {
+        f.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.to_lowercase() == file_name)
+    }) {
+        Some(file) => Some(file.clone()),
+        // Try to find a file with the same contents.
+        None => {
+            let hash = hash?;
+            files
+                .into_iter()
+                .find(|f| get_content_hash(f) == Some(hash))
+        }
+    }
+}
+
+/// Recursively add files in `directory` that have an `extension` to `files`.
+fn get_files(directory: &Path, extension: &Option<String>, depth: usize, files: &mut Vec<PathBuf>) {
+    if depth > MAX_SEARCH_DEPTH {
+        return;
+    }
+    if let Ok(entries) = read_dir(directory) {
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                get_files(&path, extension, depth + 1, files);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                == *extension
+                && !files.contains(&path)
+            {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// Hash bytes with the FNV-1a algorithm. This is stable across platforms and versions of Rust, so the hash can be saved.
+fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOUNDFONT: &str = "CT1MBGMRSV1.06.sf2";
+
+    #[test]
+    fn relocation_relative_path() {
+        let base = PathBuf::from("/home/user/saves");
+        assert_eq!(
+            get_relative_path(&PathBuf::from("/home/user/saves/a.sf2"), &base),
+            Some(PathBuf::from("a.sf2"))
+        );
+        assert_eq!(
+            get_relative_path(&PathBuf::from("/home/user/soundfonts/a.sf2"), &base),
+            Some(PathBuf::from("../soundfonts/a.sf2"))
+        );
+        assert_eq!(
+            get_relative_path(&PathBuf::from("/a.sf2"), &base),
+            Some(PathBuf::from("../../../a.sf2"))
+        );
+        assert_eq!(get_relative_path(&PathBuf::from("a.sf2"), &base), None);
+    }
+
+    #[test]
+    fn relocation_find() {
+        let directories = [PathBuf::from("../data")];
+        let path = PathBuf::from("../data").join(SOUNDFONT);
+        // Find by name.
+        assert_eq!(
+            find_soundfont(
+                &PathBuf::from("/missing").join(SOUNDFONT),
+                None,
+                &directories
+            ),
+            Some(path.clone())
+        );
+        // Find by hash.
+        let hash = get_content_hash(&path);
+        assert!(hash.is_some());
+        assert_eq!(
+            find_soundfont(&PathBuf::from("/missing/renamed.sf2"), hash, &directories),
+            Some(path)
+        );
+        assert_eq!(
+            find_soundfont(&PathBuf::from("/missing/renamed.sf2"), None, &directories),
+            None
+        );
+        assert_eq!(
+            find_soundfont(
+                &PathBuf::from("/missing/renamed.sf2"),
+                Some(0),
+                &directories
+            ),
+            None
+        );
+        assert!(get_content_hash(&PathBuf::from("/missing/renamed.sf2")).is_none());
+    }
+}
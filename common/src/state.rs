@@ -1,7 +1,7 @@
 use crate::music_panel_field::MusicPanelField;
 use crate::{
-    EditMode, Index, IndexedEditModes, IndexedValues, InputState, Music, PanelType, PianoRollMode,
-    SelectMode, Time, View,
+    AutomationType, EditMode, Index, IndexedEditModes, IndexedValues, InputState, Music, PanelType,
+    PianoRollMode, Relocation, SelectMode, Time, View,
 };
 use ini::Ini;
 use serde::{Deserialize, Serialize};
@@ -23,16 +23,28 @@ pub struct State {
     /// The index of the focused panel.
     pub focus: Index<usize>,
     /// The currently-selected music panel field.
-    pub music_panel_field: IndexedValues<MusicPanelField, 3>,
+    pub music_panel_field: IndexedValues<MusicPanelField, 4>,
     /// The piano roll panel's current mode.
     pub piano_roll_mode: PianoRollMode,
     /// The index of the current piano roll edit mode.
     pub edit_mode: IndexedEditModes,
     /// The current selection.
     pub select_mode: SelectMode,
+    /// The type of automation that is edited in the piano roll.
+    #[serde(default)]
+    pub automation_type: AutomationType,
+    /// The index of the current piano roll automation edit mode.
+    #[serde(default = "EditMode::indexed")]
+    pub automation_edit_mode: IndexedEditModes,
     /// If true, there are unsaved changes.
     #[serde(skip_serializing, skip_deserializing)]
     pub unsaved_changes: bool,
+    /// An error message that is shown in the error popup.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub error: Option<String>,
+    /// SoundFonts that were missing when a save file was loaded and that are shown in the relocate popup.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub relocations: Vec<Relocation>,
 }
 
 impl State {
@@ -49,11 +61,14 @@ impl State {
                 MusicPanelField::Name,
                 MusicPanelField::BPM,
                 MusicPanelField::Gain,
+                MusicPanelField::Key,
             ],
         );
         let piano_roll_mode = PianoRollMode::Time;
         let edit_mode = EditMode::indexed();
         let select_mode = SelectMode::Single(None);
+        let automation_type = AutomationType::default();
+        let automation_edit_mode = EditMode::indexed();
         Self {
             music,
             view,
@@ -65,7 +80,44 @@ impl State {
             piano_roll_mode,
             edit_mode,
             select_mode,
+            automation_type,
+            automation_edit_mode,
             unsaved_changes: false,
+            error: None,
+            relocations: vec![],
+        }
+    }
+
+    /// Returns the start and end time of the playback loop in PPQ, or None if playback won't loop.
+    ///
+    /// If notes are selected, the loop is the selection. Otherwise, the loop is between the playback time and the cursor.
+    pub fn get_loop_range(&self) -> Option<[u64; 2]> {
+        if !self.time.loop_playback {
+            return None;
+        }
+        let range = match self.select_mode.get_notes(&self.music) {
+            Some(notes) if !notes.is_empty() => [
+                notes.iter().map(|n| n.start).min().unwrap(),
+                notes.iter().map(|n| n.end).max().unwrap(),
+            ],
+            _ => [
+                u64::min(self.time.playback, self.time.cursor),
+                u64::max(self.time.playback, self.time.cursor),
+            ],
+        };
+        if range[0] < range[1] {
+            Some(range)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the time in PPQ at which music will start to play.
+    /// This is either the start of the playback loop or the playback time.
+    pub fn get_playback_start(&self) -> u64 {
+        match self.get_loop_range() {
+            Some(range) => range[0],
+            None => self.time.playback,
         }
     }
 }
@@ -0,0 +1,11 @@
+use crate::U64orF32;
+use serde::{Deserialize, Serialize};
+
+/// A tempo change. From `time` onwards, the music plays at `bpm` until the next tempo change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Tempo {
+    /// The time of the tempo change in PPQ.
+    pub time: u64,
+    /// The beats per minute.
+    pub bpm: U64orF32,
+}
@@ -1,5 +1,5 @@
 use crate::edit_mode::*;
-use crate::U64orF32;
+use crate::{Tempo, TimeSignature, U64orF32};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -15,14 +15,23 @@ pub const PPQ_F: f32 = PPQ_U as f32;
 pub const DEFAULT_FRAMERATE: u64 = 44100;
 
 /// The time state.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Time {
     /// The time defining the position of the cursor.
     pub cursor: u64,
     /// The time at which playback will start.
     pub playback: u64,
-    /// The beats per minute.
+    /// The beats per minute at the start of the music.
     pub bpm: U64orF32,
+    /// Tempo changes after the start of the music, sorted by time.
+    #[serde(default)]
+    pub tempos: Vec<Tempo>,
+    /// Time signature changes, sorted by time. If there isn't a time signature at the start of the music, it is 4/4.
+    #[serde(default)]
+    pub time_signatures: Vec<TimeSignature>,
+    /// If true, playback will loop. See: `State::get_loop_range()`.
+    #[serde(default)]
+    pub loop_playback: bool,
     /// The current edit mode.
     pub mode: IndexedEditModes,
 }
@@ -30,7 +39,16 @@ pub struct Time {
 impl Time {
     /// Converts pulses per quarter note into seconds.
     pub fn ppq_to_seconds(&self, ppq: u64) -> f32 {
-        ppq as f32 * (BPM_TO_SECONDS / (self.bpm.get_f() * PPQ_F))
+        let mut seconds = 0.0;
+        let mut t0 = 0;
+        let mut bpm = self.bpm;
+        // Add the duration of each tempo segment before `ppq`.
+        for tempo in self.tempos.iter().take_while(|t| t.time < ppq) {
+            seconds += Self::get_seconds(tempo.time - t0, &bpm);
+            t0 = tempo.time;
+            bpm = tempo.bpm;
+        }
+        seconds + Self::get_seconds(ppq - t0, &bpm)
     }
 
     /// Converts pulses per quarter note into a quantity of samples.
@@ -45,12 +63,197 @@ impl Time {
 
     /// Converts a quantity of samples into pulses per quarter note.
     pub fn samples_to_ppq(&self, samples: u64, framerate: f32) -> u64 {
-        ((self.bpm.get_f() * samples as f32) / (BPM_TO_SECONDS * framerate) * PPQ_F) as u64
+        let samples = samples as f32;
+        let mut t0 = 0;
+        let mut s0 = 0.0;
+        let mut bpm = self.bpm;
+        // Find the tempo segment that contains `samples`.
+        for tempo in self.tempos.iter() {
+            let s1 = s0 + Self::get_seconds(tempo.time - t0, &bpm) * framerate;
+            if s1 > samples {
+                break;
+            }
+            t0 = tempo.time;
+            s0 = s1;
+            bpm = tempo.bpm;
+        }
+        t0 + ((bpm.get_f() * (samples - s0)) / (BPM_TO_SECONDS * framerate) * PPQ_F) as u64
+    }
+
+    /// Returns the BPM at time `ppq`.
+    pub fn get_bpm(&self, ppq: u64) -> U64orF32 {
+        match self.tempos.iter().rev().find(|t| t.time <= ppq) {
+            Some(tempo) => tempo.bpm,
+            None => self.bpm,
+        }
+    }
+
+    /// Returns a mutable reference to the BPM at time `ppq`.
+    pub fn get_bpm_mut(&mut self, ppq: u64) -> &mut U64orF32 {
+        match self.tempos.iter_mut().rev().find(|t| t.time <= ppq) {
+            Some(tempo) => &mut tempo.bpm,
+            None => &mut self.bpm,
+        }
+    }
+
+    /// Add a tempo change at time `ppq`. The new tempo is the same as the current tempo at `ppq`.
+    ///
+    /// Returns false if there is already a tempo change at `ppq`.
+    pub fn add_tempo(&mut self, ppq: u64) -> bool {
+        if ppq == 0 || self.tempos.iter().any(|t| t.time == ppq) {
+            false
+        } else {
+            let bpm = self.get_bpm(ppq);
+            let index = self.tempos.iter().take_while(|t| t.time < ppq).count();
+            self.tempos.insert(index, Tempo { time: ppq, bpm });
+            true
+        }
+    }
+
+    /// Remove the tempo change at time `ppq`.
+    ///
+    /// Returns false if there is no tempo change at `ppq`.
+    pub fn remove_tempo(&mut self, ppq: u64) -> bool {
+        match self.tempos.iter().position(|t| t.time == ppq) {
+            Some(index) => {
+                self.tempos.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the time signature at time `ppq`.
+    pub fn get_time_signature(&self, ppq: u64) -> TimeSignature {
+        match self.time_signatures.iter().rev().find(|t| t.time <= ppq) {
+            Some(time_signature) => *time_signature,
+            None => TimeSignature::default(),
+        }
+    }
+
+    /// Returns a mutable reference to the time signature at time `ppq`.
+    /// If there is no time signature at or before `ppq`, a 4/4 time signature is added to the start of the music.
+    pub fn get_time_signature_mut(&mut self, ppq: u64) -> &mut TimeSignature {
+        if !self.time_signatures.iter().any(|t| t.time <= ppq) {
+            self.time_signatures.insert(0, TimeSignature::default());
+        }
+        self.time_signatures
+            .iter_mut()
+            .rev()
+            .find(|t| t.time <= ppq)
+            .unwrap()
+    }
+
+    /// Add a time signature change at time `ppq`. The new time signature is the same as the current time signature at `ppq`.
+    ///
+    /// Returns false if there is already a time signature change at `ppq`.
+    pub fn add_time_signature(&mut self, ppq: u64) -> bool {
+        if ppq == 0 || self.time_signatures.iter().any(|t| t.time == ppq) {
+            false
+        } else {
+            let time_signature = TimeSignature {
+                time: ppq,
+                ..self.get_time_signature(ppq)
+            };
+            let index = self
+                .time_signatures
+                .iter()
+                .take_while(|t| t.time < ppq)
+                .count();
+            self.time_signatures.insert(index, time_signature);
+            true
+        }
+    }
+
+    /// Remove the time signature change at time `ppq`.
+    ///
+    /// Returns false if there is no time signature change at `ppq`.
+    pub fn remove_time_signature(&mut self, ppq: u64) -> bool {
+        match self.time_signatures.iter().position(|t| t.time == ppq) {
+            Some(index) => {
+                self.time_signatures.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns every time signature, including the implicit 4/4 time signature at the start of the music.
+    pub fn get_time_signatures(&self) -> Vec<TimeSignature> {
+        let mut time_signatures = vec![];
+        let first_is_start = match self.time_signatures.first() {
+            Some(time_signature) => time_signature.time == 0,
+            None => false,
+        };
+        if !first_is_start {
+            time_signatures.push(TimeSignature::default());
+        }
+        time_signatures.extend(self.time_signatures.iter().copied());
+        time_signatures
+    }
+
+    /// Converts `ppq` into a bar, a beat, and a tick. The bar and beat start at 1.
+    ///
+    /// Every time signature change starts a new bar.
+    pub fn get_bar_beat_tick(&self, ppq: u64) -> [u64; 3] {
+        let time_signatures = self.get_time_signatures();
+        let index = time_signatures
+            .iter()
+            .rposition(|t| t.time <= ppq)
+            .unwrap_or(0);
+        // Count the bars before the current time signature. A partial bar counts as a bar.
+        let mut bar = time_signatures[..=index]
+            .windows(2)
+            .map(|t| (t[1].time - t[0].time).div_ceil(t[0].get_bar_ppq()))
+            .sum::<u64>();
+        let time_signature = &time_signatures[index];
+        let dt = ppq - time_signature.time;
+        let bar_ppq = time_signature.get_bar_ppq();
+        let beat_ppq = time_signature.get_beat_ppq();
+        bar += dt / bar_ppq;
+        let dt = dt % bar_ppq;
+        [bar + 1, dt / beat_ppq + 1, dt % beat_ppq]
+    }
+
+    /// Returns the time of each beat between `t0` and `t1` inclusive, and whether the beat is the start of a bar.
+    pub fn get_beats(&self, t0: u64, t1: u64) -> Vec<(u64, bool)> {
+        let time_signatures = self.get_time_signatures();
+        let mut beats = vec![];
+        for (i, time_signature) in time_signatures.iter().enumerate() {
+            // Get the end time of this time signature.
+            let end = match time_signatures.get(i + 1) {
+                Some(next) => next.time,
+                None => u64::MAX,
+            };
+            if end <= t0 || time_signature.time > t1 {
+                continue;
+            }
+            let beat_ppq = time_signature.get_beat_ppq();
+            let numerator = time_signature.numerator as u64;
+            // Get the index of the first beat in the range.
+            let mut beat = t0.saturating_sub(time_signature.time).div_ceil(beat_ppq);
+            loop {
+                let t = time_signature.time + beat * beat_ppq;
+                if t >= end || t > t1 {
+                    break;
+                }
+                beats.push((t, beat % numerator == 0));
+                beat += 1;
+            }
+        }
+        beats
     }
 
     pub fn reset(&mut self) {
         self.cursor = 0;
         self.playback = 0;
+        self.tempos.clear();
+        self.time_signatures.clear();
+    }
+
+    /// Converts pulses per quarter note into seconds at a constant tempo.
+    fn get_seconds(ppq: u64, bpm: &U64orF32) -> f32 {
+        ppq as f32 * (BPM_TO_SECONDS / (bpm.get_f() * PPQ_F))
     }
 }
 
@@ -60,6 +263,9 @@ impl Default for Time {
             cursor: 0,
             playback: 0,
             bpm: U64orF32::from(DEFAULT_BPM),
+            tempos: vec![],
+            time_signatures: vec![],
+            loop_playback: false,
             mode: EditMode::indexed(),
         }
     }
@@ -110,6 +316,101 @@ mod tests {
         samples_ppq(44100, PPQ_U * 2, framerate, &time);
     }
 
+    #[test]
+    fn tempo_map() {
+        let mut time = Time::default();
+        let framerate: f32 = 44100.0;
+
+        // Add tempo changes.
+        assert!(!time.add_tempo(0));
+        assert!(time.add_tempo(PPQ_U * 4));
+        assert!(!time.add_tempo(PPQ_U * 4));
+        assert!(time.add_tempo(PPQ_U * 2));
+        assert_eq!(time.tempos.len(), 2);
+        assert_eq!(time.tempos[0].time, PPQ_U * 2);
+        assert_eq!(time.tempos[1].time, PPQ_U * 4);
+
+        // Set the tempo.
+        *time.get_bpm_mut(PPQ_U * 3) = U64orF32::from(60);
+        assert_eq!(time.get_bpm(0).get_u(), DEFAULT_BPM);
+        assert_eq!(time.get_bpm(PPQ_U * 2).get_u(), 60);
+        assert_eq!(time.get_bpm(PPQ_U * 4).get_u(), DEFAULT_BPM);
+        *time.get_bpm_mut(PPQ_U * 4) = U64orF32::from(240);
+
+        // PPQ to seconds.
+        ppq_seconds(PPQ_U, 0.5, &time);
+        ppq_seconds(PPQ_U * 2, 1.0, &time);
+        ppq_seconds(PPQ_U * 3, 2.0, &time);
+        ppq_seconds(PPQ_U * 4, 3.0, &time);
+        ppq_seconds(PPQ_U * 6, 3.5, &time);
+
+        // PPQ to samples.
+        ppq_samples(PPQ_U * 3, 88200, framerate, &time);
+        ppq_samples(PPQ_U * 6, 154350, framerate, &time);
+
+        // Samples to PPQ.
+        samples_ppq(22050, PPQ_U, framerate, &time);
+        samples_ppq(88200, PPQ_U * 3, framerate, &time);
+        samples_ppq(154350, PPQ_U * 6, framerate, &time);
+
+        // Remove tempo changes.
+        assert!(!time.remove_tempo(PPQ_U));
+        assert!(time.remove_tempo(PPQ_U * 2));
+        assert_eq!(time.get_bpm(PPQ_U * 3).get_u(), DEFAULT_BPM);
+        time.reset();
+        assert!(time.tempos.is_empty());
+    }
+
+    #[test]
+    fn time_signatures() {
+        let mut time = Time::default();
+
+        // The default time signature is 4/4.
+        assert_eq!(
+            time.get_time_signature(PPQ_U * 100),
+            TimeSignature::default()
+        );
+        assert_eq!(time.get_bar_beat_tick(0), [1, 1, 0]);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U), [1, 2, 0]);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 4 + 12), [2, 1, 12]);
+
+        // 3/4 from the start, then 6/8 from bar 3.
+        let time_signature = time.get_time_signature_mut(0);
+        time_signature.numerator = 3;
+        assert_eq!(time.time_signatures.len(), 1);
+        assert!(!time.add_time_signature(0));
+        assert!(time.add_time_signature(PPQ_U * 6));
+        assert!(!time.add_time_signature(PPQ_U * 6));
+        time.get_time_signature_mut(PPQ_U * 6).numerator = 6;
+        time.get_time_signature_mut(PPQ_U * 7).denominator = 8;
+        assert_eq!(time.get_time_signature(PPQ_U * 5).numerator, 3);
+        assert_eq!(time.get_time_signature(PPQ_U * 6).denominator, 8);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 3), [2, 1, 0]);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 6), [3, 1, 0]);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 6 + 96), [3, 2, 0]);
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 9 + 100), [4, 2, 4]);
+
+        // Beats and bars.
+        let beats = time.get_beats(PPQ_U * 5, PPQ_U * 7);
+        assert_eq!(
+            beats,
+            vec![
+                (PPQ_U * 5, false),
+                (PPQ_U * 6, true),
+                (PPQ_U * 6 + 96, false),
+                (PPQ_U * 7, false),
+            ]
+        );
+
+        // A time signature change in the middle of a bar starts a new bar.
+        assert!(time.remove_time_signature(PPQ_U * 6));
+        assert!(!time.remove_time_signature(PPQ_U * 6));
+        assert!(time.add_time_signature(PPQ_U * 4));
+        assert_eq!(time.get_bar_beat_tick(PPQ_U * 4), [3, 1, 0]);
+        time.reset();
+        assert!(time.time_signatures.is_empty());
+    }
+
     fn ppq_seconds(ppq: u64, f: f32, time: &Time) {
         let t = time.ppq_to_seconds(ppq);
         assert_eq!(t, f, "{} {}", t, f);
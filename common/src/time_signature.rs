@@ -0,0 +1,135 @@
+use crate::PPQ_U;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The allowed time signature denominators.
+pub const TIME_SIGNATURE_DENOMINATORS: [u8; 5] = [1, 2, 4, 8, 16];
+/// The maximum time signature numerator.
+pub const MAX_TIME_SIGNATURE_NUMERATOR: u8 = 32;
+
+/// A time signature change. From `time` onwards, bars are `numerator` beats long, and each beat is a 1/`denominator` note.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TimeSignature {
+    /// The time of the time signature change in PPQ.
+    pub time: u64,
+    /// The number of beats per bar.
+    #[serde(deserialize_with = "deserialize_numerator")]
+    pub numerator: u8,
+    /// The note value of a beat.
+    #[serde(deserialize_with = "deserialize_denominator")]
+    pub denominator: u8,
+}
+
+impl TimeSignature {
+    /// Returns the duration of a beat in PPQ.
+    pub fn get_beat_ppq(&self) -> u64 {
+        (PPQ_U * 4) / self.denominator as u64
+    }
+
+    /// Returns the duration of a bar in PPQ.
+    pub fn get_bar_ppq(&self) -> u64 {
+        self.get_beat_ppq() * self.numerator as u64
+    }
+
+    /// Returns the duration of a metronome click in PPQ.
+    /// Compound meters such as 6/8 or 12/16 click every three beats, e.g. on every dotted quarter note in 6/8.
+    pub fn get_click_ppq(&self) -> u64 {
+        if self.is_compound() {
+            self.get_beat_ppq() * 3
+        } else {
+            self.get_beat_ppq()
+        }
+    }
+
+    /// Returns true if this is a compound meter.
+    fn is_compound(&self) -> bool {
+        self.denominator >= 8 && self.numerator > 3 && self.numerator.is_multiple_of(3)
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            time: 0,
+            numerator: 4,
+            denominator: 4,
+        }
+    }
+}
+
+/// Deserialize a numerator. This returns an error if the numerator is out of range, e.g. because a save file was edited by hand.
+fn deserialize_numerator<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let numerator = u8::deserialize(deserializer)?;
+    if (1..=MAX_TIME_SIGNATURE_NUMERATOR).contains(&numerator) {
+        Ok(numerator)
+    } else {
+        Err(D::Error::custom(format!(
+            "Invalid time signature numerator: {}",
+            numerator
+        )))
+    }
+}
+
+/// Deserialize a denominator. This returns an error if the denominator isn't in `TIME_SIGNATURE_DENOMINATORS`.
+fn deserialize_denominator<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let denominator = u8::deserialize(deserializer)?;
+    if TIME_SIGNATURE_DENOMINATORS.contains(&denominator) {
+        Ok(denominator)
+    } else {
+        Err(D::Error::custom(format!(
+            "Invalid time signature denominator: {}",
+            denominator
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimeSignature;
+    use crate::PPQ_U;
+    use serde_json::from_str;
+
+    #[test]
+    fn time_signature_click() {
+        for (numerator, denominator, click_ppq) in [
+            (4, 4, PPQ_U),
+            (3, 4, PPQ_U),
+            (2, 2, PPQ_U * 2),
+            (3, 8, PPQ_U / 2),
+            (6, 8, PPQ_U * 3 / 2),
+            (12, 8, PPQ_U * 3 / 2),
+            (7, 8, PPQ_U / 2),
+            (6, 4, PPQ_U),
+            (9, 16, PPQ_U * 3 / 4),
+        ] {
+            let time_signature = TimeSignature {
+                time: 0,
+                numerator,
+                denominator,
+            };
+            assert_eq!(time_signature.get_click_ppq(), click_ppq);
+        }
+    }
+
+    #[test]
+    fn time_signature_deserialize() {
+        let time_signature: TimeSignature =
+            from_str(r#"{"time": 0, "numerator": 6, "denominator": 8}"#).unwrap();
+        assert_eq!(time_signature.numerator, 6);
+        assert_eq!(time_signature.denominator, 8);
+        for json in [
+            r#"{"time": 0, "numerator": 4, "denominator": 0}"#,
+            r#"{"time": 0, "numerator": 4, "denominator": 3}"#,
+            r#"{"time": 0, "numerator": 0, "denominator": 4}"#,
+            r#"{"time": 0, "numerator": 33, "denominator": 4}"#,
+        ] {
+            assert!(from_str::<TimeSignature>(json).is_err());
+        }
+    }
+}
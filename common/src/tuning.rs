@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// The frequency of MIDI note 0 in Hz.
+const NOTE_0_FREQUENCY: f64 = 8.175798915643707;
+/// The frequency of middle C in Hz.
+const MIDDLE_C_FREQUENCY: f64 = 261.6255653005986;
+/// The number of MIDI notes.
+const NUM_NOTES: usize = 128;
+/// Pitches that are this close in cents are considered to be the same.
+const CENTS_EPSILON: f64 = 0.01;
+
+/// A scale, loaded from a Scala .scl file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Scale {
+    /// The description of the scale.
+    pub description: String,
+    /// The pitch of each scale degree after the first degree in cents. The first degree is always 0 cents.
+    /// The last pitch is the period of the scale, which is usually an octave.
+    pub cents: Vec<f64>,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self {
+            description: String::from("12-TET"),
+            cents: (1..=12).map(|i| i as f64 * 100.0).collect(),
+        }
+    }
+}
+
+impl Scale {
+    /// Read a .scl file.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        match read_to_string(path) {
+            Ok(scl) => Self::from_scl(&scl),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    /// Parse the text of a .scl file.
+    pub fn from_scl(scl: &str) -> Result<Self, String> {
+        let mut lines = scl.lines().filter(|line| !line.starts_with('!'));
+        let description = match lines.next() {
+            Some(line) => line.trim().to_string(),
+            None => return Err(String::from("Missing description")),
+        };
+        let num_notes = match lines.next().and_then(|line| line.split_whitespace().next()) {
+            Some(token) => match token.parse::<usize>() {
+                Ok(num_notes) => num_notes,
+                Err(_) => return Err(format!("Invalid number of notes: {}", token)),
+            },
+            None => return Err(String::from("Missing number of notes")),
+        };
+        if num_notes == 0 {
+            return Err(String::from("The scale doesn't have any notes"));
+        }
+        let cents = lines
+            .take(num_notes)
+            .map(parse_pitch)
+            .collect::<Result<Vec<f64>, String>>()?;
+        if cents.len() != num_notes {
+            Err(format!(
+                "Expected {} notes but there are {}",
+                num_notes,
+                cents.len()
+            ))
+        } else {
+            Ok(Self { description, cents })
+        }
+    }
+
+    /// Returns the number of scale degrees per period.
+    pub fn get_num_degrees(&self) -> usize {
+        self.cents.len()
+    }
+
+    /// Returns the pitch in cents of a scale degree relative to the first degree.
+    /// The degree can be in another period, e.g. degree -1 is the last degree of the previous period.
+    fn get_cents(&self, degree: i64) -> f64 {
+        let num_degrees = self.cents.len() as i64;
+        let period = degree.div_euclid(num_degrees);
+        let degree = degree.rem_euclid(num_degrees) as usize;
+        let cents = if degree == 0 {
+            0.0
+        } else {
+            self.cents[degree - 1]
+        };
+        period as f64 * self.cents[self.cents.len() - 1] + cents
+    }
+}
+
+/// A mapping of MIDI notes to scale degrees, loaded from a Scala .kbm file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KeyboardMapping {
+    /// The first MIDI note to retune.
+    pub first_note: u8,
+    /// The last MIDI note to retune.
+    pub last_note: u8,
+    /// The MIDI note that the first scale degree is mapped to.
+    pub middle_note: u8,
+    /// The MIDI note that has the reference frequency.
+    pub reference_note: u8,
+    /// The frequency of the reference note in Hz.
+    pub reference_frequency: f64,
+    /// The scale degree that is the formal octave. If this is 0, the formal octave is the period of the scale.
+    pub octave_degree: usize,
+    /// The scale degree of each key in the repeating keyboard pattern, or None if a key isn't mapped.
+    /// If this is empty, keys are mapped to consecutive scale degrees.
+    pub mapping: Vec<Option<usize>>,
+}
+
+impl Default for KeyboardMapping {
+    fn default() -> Self {
+        Self {
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: 60,
+            reference_frequency: MIDDLE_C_FREQUENCY,
+            octave_degree: 0,
+            mapping: vec![],
+        }
+    }
+}
+
+impl KeyboardMapping {
+    /// Read a .kbm file.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        match read_to_string(path) {
+            Ok(kbm) => Self::from_kbm(&kbm),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    /// Parse the text of a .kbm file.
+    pub fn from_kbm(kbm: &str) -> Result<Self, String> {
+        let mut values = kbm
+            .lines()
+            .filter(|line| !line.starts_with('!'))
+            .filter_map(|line| line.split_whitespace().next());
+        let size = parse_value::<usize>(values.next(), "map size")?;
+        let first_note = parse_value::<u8>(values.next(), "first note")?;
+        let last_note = parse_value::<u8>(values.next(), "last note")?;
+        let middle_note = parse_value::<u8>(values.next(), "middle note")?;
+        let reference_note = parse_value::<u8>(values.next(), "reference note")?;
+        let reference_frequency = parse_value::<f64>(values.next(), "reference frequency")?;
+        let octave_degree = parse_value::<usize>(values.next(), "octave degree")?;
+        if reference_frequency <= 0.0 {
+            return Err(format!(
+                "Invalid reference frequency: {}",
+                reference_frequency
+            ));
+        }
+        // Missing keys aren't mapped.
+        let mut mapping: Vec<Option<usize>> = values
+            .take(size)
+            .map(|value| value.parse::<usize>().ok())
+            .collect();
+        mapping.resize(size, None);
+        Ok(Self {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            mapping,
+        })
+    }
+
+    /// Returns the scale degree of a MIDI note relative to the middle note, or None if the note isn't mapped.
+    ///
+    /// - `note` The MIDI note.
+    /// - `num_degrees` The number of degrees per period of the scale.
+    fn get_degree(&self, note: u8, num_degrees: usize) -> Option<i64> {
+        if note < self.first_note || note > self.last_note {
+            return None;
+        }
+        let dn = note as i64 - self.middle_note as i64;
+        if self.mapping.is_empty() {
+            Some(dn)
+        } else {
+            let size = self.mapping.len() as i64;
+            let octave_degree = if self.octave_degree == 0 {
+                num_degrees
+            } else {
+                self.octave_degree
+            } as i64;
+            self.mapping[dn.rem_euclid(size) as usize]
+                .map(|degree| dn.div_euclid(size) * octave_degree + degree as i64)
+        }
+    }
+}
+
+/// A microtonal tuning: a scale and a keyboard mapping.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Tuning {
+    /// The path to the .scl file, if any. If None, the scale is 12-tone equal temperament.
+    pub scale_path: Option<PathBuf>,
+    /// The path to the .kbm file, if any. If None, notes are mapped to consecutive scale degrees starting at middle C.
+    pub keyboard_mapping_path: Option<PathBuf>,
+    /// The scale.
+    pub scale: Scale,
+    /// The keyboard mapping.
+    pub keyboard_mapping: KeyboardMapping,
+}
+
+impl Tuning {
+    /// Read a tuning from a .scl file and a .kbm file.
+    ///
+    /// - `scale_path` The path to the .scl file. If None, use 12-tone equal temperament.
+    /// - `keyboard_mapping_path` The path to the .kbm file. If None, use the default mapping.
+    ///
+    /// Returns an error with the path of the file that couldn't be read.
+    pub fn new(
+        scale_path: Option<&Path>,
+        keyboard_mapping_path: Option<&Path>,
+    ) -> Result<Self, (PathBuf, String)> {
+        let scale = match scale_path {
+            Some(path) => Scale::read(path).map_err(|error| (path.to_path_buf(), error))?,
+            None => Scale::default(),
+        };
+        let keyboard_mapping = match keyboard_mapping_path {
+            Some(path) => {
+                KeyboardMapping::read(path).map_err(|error| (path.to_path_buf(), error))?
+            }
+            None => KeyboardMapping::default(),
+        };
+        Ok(Self {
+            scale_path: scale_path.map(|p| p.to_path_buf()),
+            keyboard_mapping_path: keyboard_mapping_path.map(|p| p.to_path_buf()),
+            scale,
+            keyboard_mapping,
+        })
+    }
+
+    /// Returns the pitch of each MIDI note in cents, where 0 cents is MIDI note 0 in 12-tone equal temperament.
+    /// Notes that aren't mapped keep their 12-tone equal temperament pitch.
+    pub fn get_pitches(&self) -> [f64; NUM_NOTES] {
+        let reference =
+            1200.0 * (self.keyboard_mapping.reference_frequency / NOTE_0_FREQUENCY).log2();
+        // Get the pitch of the middle note from the pitch of the reference note.
+        let middle = match self.get_absolute_degree(self.keyboard_mapping.reference_note) {
+            Some(degree) => reference - self.scale.get_cents(degree),
+            None => self.keyboard_mapping.middle_note as f64 * 100.0,
+        };
+        let mut pitches = [0.0; NUM_NOTES];
+        for (note, pitch) in pitches.iter_mut().enumerate() {
+            *pitch = match self.get_absolute_degree(note as u8) {
+                Some(degree) => middle + self.scale.get_cents(degree),
+                None => note as f64 * 100.0,
+            };
+        }
+        pitches
+    }
+
+    /// Returns true if every note has the same pitch as in 12-tone equal temperament.
+    pub fn is_12_tet(&self) -> bool {
+        self.get_pitches()
+            .iter()
+            .enumerate()
+            .all(|(note, pitch)| (pitch - note as f64 * 100.0).abs() < CENTS_EPSILON)
+    }
+
+    /// Returns the scale degree of a MIDI note (0 is the first degree) and its period relative to the middle note, or None if the note isn't mapped.
+    pub fn get_degree(&self, note: u8) -> Option<(usize, i64)> {
+        let num_degrees = self.scale.get_num_degrees() as i64;
+        self.get_absolute_degree(note).map(|degree| {
+            (
+                degree.rem_euclid(num_degrees) as usize,
+                degree.div_euclid(num_degrees),
+            )
+        })
+    }
+
+    /// Returns a short label of a MIDI note's scale degree, or "-" if the note isn't mapped.
+    pub fn get_label(&self, note: u8) -> String {
+        match self.get_degree(note) {
+            Some((degree, _)) => degree.to_string(),
+            None => String::from("-"),
+        }
+    }
+
+    /// Returns the scale degree of a MIDI note relative to the middle note, which can be in another period.
+    fn get_absolute_degree(&self, note: u8) -> Option<i64> {
+        self.keyboard_mapping
+            .get_degree(note, self.scale.get_num_degrees())
+    }
+}
+
+/// Parse a pitch in a .scl file. Values with a period are in cents. Other values are ratios.
+fn parse_pitch(line: &str) -> Result<f64, String> {
+    let token = match line.split_whitespace().next() {
+        Some(token) => token,
+        None => return Err(String::from("Missing pitch")),
+    };
+    let error = || format!("Invalid pitch: {}", token);
+    if token.contains('.') {
+        token.parse::<f64>().map_err(|_| error())
+    } else {
+        let mut ratio = token.split('/');
+        let numerator = ratio.next().and_then(|n| n.parse::<f64>().ok());
+        let denominator = match ratio.next() {
+            Some(d) => d.parse::<f64>().ok(),
+            None => Some(1.0),
+        };
+        match (numerator, denominator) {
+            (Some(n), Some(d)) if n > 0.0 && d > 0.0 => Ok(1200.0 * (n / d).log2()),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Parse a value in a .kbm file.
+fn parse_value<T: std::str::FromStr>(value: Option<&str>, name: &str) -> Result<T, String> {
+    match value {
+        Some(value) => value
+            .parse::<T>()
+            .map_err(|_| format!("Invalid {}: {}", name, value)),
+        None => Err(format!("Missing {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyboardMapping, Scale, Tuning};
+
+    const EDO_19: &str = "! 19-edo.scl
+!
+19 equal divisions of the octave
+ 19
+!
+ 63.15789
+ 126.31579
+ 189.47368
+ 252.63158
+ 315.78947
+ 378.94737
+ 442.10526
+ 505.26316
+ 568.42105
+ 631.57895
+ 694.73684
+ 757.89474
+ 821.05263
+ 884.21053
+ 947.36842
+ 1010.52632
+ 1073.68421
+ 1136.84211
+ 2/1
+";
+
+    const JUST: &str = "! just.scl
+Just intonation
+ 3
+ 5/4
+ 3/2
+ 2
+";
+
+    const WHITE_KEYS: &str = "! white.kbm
+12
+0
+127
+60
+69
+440.0
+3
+! Mapping.
+0
+x
+x
+x
+1
+x
+x
+2
+x
+x
+x
+x
+";
+
+    #[test]
+    fn tuning_scl() {
+        let scale = Scale::from_scl(EDO_19).unwrap();
+        assert_eq!(scale.description, "19 equal divisions of the octave");
+        assert_eq!(scale.get_num_degrees(), 19);
+        assert_eq!(scale.cents[18], 1200.0);
+        let scale = Scale::from_scl(JUST).unwrap();
+        assert_eq!(scale.get_num_degrees(), 3);
+        assert!(
+            (scale.cents[0] - 386.3137).abs() < 0.001,
+            "{}",
+            scale.cents[0]
+        );
+        assert!(
+            (scale.cents[1] - 701.955).abs() < 0.001,
+            "{}",
+            scale.cents[1]
+        );
+        assert_eq!(scale.cents[2], 1200.0);
+        assert!(Scale::from_scl("Bad\n 2\n 100.0\n").is_err());
+        assert!(Scale::from_scl("Bad\n 1\n abc\n").is_err());
+        assert!(Scale::from_scl("Bad\n 0\n").is_err());
+    }
+
+    #[test]
+    fn tuning_kbm() {
+        let keyboard_mapping = KeyboardMapping::from_kbm(WHITE_KEYS).unwrap();
+        assert_eq!(keyboard_mapping.middle_note, 60);
+        assert_eq!(keyboard_mapping.reference_note, 69);
+        assert_eq!(keyboard_mapping.reference_frequency, 440.0);
+        assert_eq!(keyboard_mapping.octave_degree, 3);
+        assert_eq!(keyboard_mapping.mapping.len(), 12);
+        assert_eq!(keyboard_mapping.mapping[0], Some(0));
+        assert_eq!(keyboard_mapping.mapping[1], None);
+        assert_eq!(keyboard_mapping.mapping[4], Some(1));
+        assert_eq!(keyboard_mapping.mapping[7], Some(2));
+        assert!(KeyboardMapping::from_kbm("12\n0\n127\n").is_err());
+    }
+
+    #[test]
+    fn tuning_pitches() {
+        // The default tuning is 12-TET.
+        let tuning = Tuning::default();
+        assert!(tuning.is_12_tet());
+        assert_eq!(tuning.get_degree(60), Some((0, 0)));
+        assert_eq!(tuning.get_degree(59), Some((11, -1)));
+        assert_eq!(tuning.get_label(64), "4");
+        // 19-EDO.
+        let tuning = Tuning {
+            scale: Scale::from_scl(EDO_19).unwrap(),
+            ..Default::default()
+        };
+        assert!(!tuning.is_12_tet());
+        let pitches = tuning.get_pitches();
+        assert!((pitches[60] - 6000.0).abs() < 0.001, "{}", pitches[60]);
+        assert!((pitches[61] - 6063.15789).abs() < 0.001, "{}", pitches[61]);
+        assert!((pitches[79] - 7200.0).abs() < 0.001, "{}", pitches[79]);
+        assert_eq!(tuning.get_degree(79), Some((0, 1)));
+        // Just intonation on the white keys. The reference note isn't mapped, so the middle note keeps its pitch.
+        let tuning = Tuning {
+            scale: Scale::from_scl(JUST).unwrap(),
+            keyboard_mapping: KeyboardMapping::from_kbm(WHITE_KEYS).unwrap(),
+            ..Default::default()
+        };
+        let pitches = tuning.get_pitches();
+        assert!((pitches[60] - 6000.0).abs() < 0.001, "{}", pitches[60]);
+        assert!((pitches[64] - 6386.3137).abs() < 0.001, "{}", pitches[64]);
+        assert!((pitches[72] - 7200.0).abs() < 0.001, "{}", pitches[72]);
+        // Unmapped notes keep their 12-TET pitch.
+        assert_eq!(pitches[61], 6100.0);
+        assert_eq!(tuning.get_label(61), "-");
+        assert_eq!(tuning.get_degree(67), Some((2, 0)));
+    }
+}
@@ -0,0 +1,18 @@
+/// The undo history that is shown in the undo history popup.
+/// This is stored by the `IO` rather than in the `State` so that it isn't cloned into every undo snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct UndoHistory {
+    /// The label of each action from oldest to newest, including actions that were undone and can be redone.
+    pub labels: Vec<String>,
+    /// The number of actions that are applied, i.e. the size of the undo stack. Actions at indices >= `position` can be redone.
+    pub position: usize,
+    /// The selected entry. Entry 0 is the music before the first action. Entry `i` is the music after action `i - 1`.
+    pub selected: usize,
+}
+
+impl UndoHistory {
+    /// Returns the number of entries: the initial music, and the music after each action.
+    pub fn get_num_entries(&self) -> usize {
+        self.labels.len() + 1
+    }
+}
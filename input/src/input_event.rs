@@ -16,14 +16,18 @@ pub enum InputEvent {
     StopTTS,
     // Enable links panel.
     EnableLinksPanel,
+    // Enable audio device panel.
+    EnableAudioDevicePanel,
     // Undo-redo.
     Undo,
     Redo,
+    EnableUndoHistoryPanel,
     // Files.
     OpenFile,
     NewFile,
     SaveFile,
     SaveFileAs,
+    SaveBundle,
     ExportFile,
     ImportMidi,
     EditConfig,
@@ -32,20 +36,30 @@ pub enum InputEvent {
     // Music panel.
     NextMusicPanelField,
     PreviousMusicPanelField,
+    PreviousKeyRoot,
+    NextKeyRoot,
+    PreviousScaleType,
+    NextScaleType,
     IncreaseMusicGain,
     DecreaseMusicGain,
     // Tracks panel.
     AddTrack,
+    AddPercussionTrack,
     RemoveTrack,
     NextTrack,
     PreviousTrack,
     EnableSoundFontPanel,
+    EnableScalePanel,
+    EnableKeyboardMappingPanel,
+    ResetTuning,
     PreviousPreset,
     NextPreset,
     PreviousBank,
     NextBank,
     IncreaseTrackGain,
     DecreaseTrackGain,
+    PanTrackLeft,
+    PanTrackRight,
     Mute,
     Solo,
     // Open file panel.
@@ -68,14 +82,23 @@ pub enum InputEvent {
     PianoRollSetView,
     PianoRollSetSelect,
     PianoRollSetEdit,
+    PianoRollSetAutomation,
     PianoRollToggleTracks,
     Arm,
+    ToggleRecord,
+    ToggleSnapToKey,
     InputBeatLeft,
     InputBeatRight,
     IncreaseInputVolume,
     DecreaseInputVolume,
     ToggleInputVolume,
     PlayStop,
+    ToggleLoop,
+    ToggleMetronome,
+    ToggleMetronomeAccent,
+    ToggleMetronomeExport,
+    IncreaseMetronomeGain,
+    DecreaseMetronomeGain,
     PianoRollPreviousTrack,
     PianoRollNextTrack,
     // Piano roll - view mode.
@@ -101,6 +124,14 @@ pub enum InputEvent {
     TimePlaybackCursor,
     TimeCursorBeat,
     TimePlaybackBeat,
+    TimeAddTempo,
+    TimeRemoveTempo,
+    TimeAddTimeSignature,
+    TimeRemoveTimeSignature,
+    TimeSignatureNumeratorUp,
+    TimeSignatureNumeratorDown,
+    TimeSignatureDenominatorUp,
+    TimeSignatureDenominatorDown,
     // Piano roll - edit mode.
     EditStartLeft,
     EditStartRight,
@@ -110,6 +141,8 @@ pub enum InputEvent {
     EditPitchDown,
     EditVolumeUp,
     EditVolumeDown,
+    EditQuantize,
+    EditHumanize,
     // Piano roll - select mode.
     SelectStartLeft,
     SelectStartRight,
@@ -117,6 +150,17 @@ pub enum InputEvent {
     SelectEndRight,
     SelectAll,
     SelectNone,
+    // Piano roll - automation mode.
+    AutomationCursorLeft,
+    AutomationCursorRight,
+    AutomationPreviousPoint,
+    AutomationNextPoint,
+    AutomationValueUp,
+    AutomationValueDown,
+    AutomationAddPoint,
+    AutomationRemovePoint,
+    AutomationPreviousType,
+    AutomationNextType,
     // Copy, cut, paste, delete.
     CopyNotes,
     CutNotes,
@@ -130,6 +174,27 @@ pub enum InputEvent {
     DiscordUrl,
     GitHubUrl,
     CloseLinksPanel,
+    // Audio device panel.
+    PreviousAudioDeviceSetting,
+    NextAudioDeviceSetting,
+    PreviousAudioDeviceSettingValue,
+    NextAudioDeviceSettingValue,
+    CloseAudioDevicePanel,
+    // Error panel.
+    CloseErrorPanel,
+    // Relocate panel.
+    RelocateSoundFont,
+    BrowseSoundFont,
+    SkipSoundFont,
+    ToggleRelativeSoundFontPaths,
+    // Recover panel.
+    RecoverPanelYes,
+    RecoverPanelNo,
+    // Undo history panel.
+    PreviousUndoHistoryEntry,
+    NextUndoHistoryEntry,
+    SelectUndoHistoryEntry,
+    CloseUndoHistoryPanel,
     // Qwerty note input.
     C,
     CSharp,
@@ -11,9 +11,10 @@ mod midi_binding;
 mod midi_conn;
 mod note_on;
 mod qwerty_binding;
+mod snapped_notes;
 
 use common::args::Args;
-use common::{State, MAX_NOTE, MIN_NOTE};
+use common::{AutomationType, State, MAX_NOTE, MIN_NOTE};
 use debug_input_event::DebugInputEvent;
 use hashbrown::HashMap;
 use ini::Ini;
@@ -26,6 +27,7 @@ use midi_conn::MidiConn;
 use note_on::NoteOn;
 pub use qwerty_binding::QwertyBinding;
 use serde_json::from_str;
+use snapped_notes::SnappedNotes;
 use std::fs::File;
 use std::io::Read;
 use std::str::FromStr;
@@ -78,8 +80,12 @@ pub struct Input {
     pub note_on_messages: Vec<[u8; 3]>,
     /// Note-off MIDI messages. These will be sent immediately to the synthesizer.
     pub note_off_keys: Vec<u8>,
+    /// Controller, channel pressure, and pitch bend MIDI messages that aren't bound to input events. These will be sent immediately to the synthesizer.
+    pub control_messages: Vec<[u8; 3]>,
     /// Note-on events that don't have corresponding off events.
     note_on_events: Vec<NoteOn>,
+    /// Notes that are held down, and the pitches that they were snapped to.
+    snapped_notes: SnappedNotes,
     /// Notes that were added after all note-off events are done.
     pub new_notes: Vec<[u8; 3]>,
     /// Input events generated by MIDI input.
@@ -165,12 +171,16 @@ impl Input {
     /// 1. Compare all polled MIDI events to MIDI bindings and register new events accordingly.
     /// 2. Add note messages to the list for playing notes.
     /// 3. Store new note-on events.
-    /// 4. If all note-ons have had a corresponding note-off, add them to the new notes lists.
+    /// 4. Store controller, channel pressure, and pitch bend messages that aren't bound to input events.
+    /// 5. If all note-ons have had a corresponding note-off, add them to the new notes lists.
+    ///
+    /// While music is playing, notes aren't added to the new notes list. If the track is armed and `state.input.record` is true, note-on messages are still added so that they can be recorded in real time.
     pub fn update(&mut self, state: &State) {
         // Clear the old new notes.
         self.new_notes.clear();
         self.note_on_messages.clear();
         self.note_off_keys.clear();
+        self.control_messages.clear();
 
         // QWERTY INPUT.
 
@@ -236,7 +246,12 @@ impl Input {
                     .iter()
                     .all(|k| is_key_released(*k))
         }) {
-            self.note_off_keys.push(self.get_pitch(*qwerty_note_off));
+            if let Some(pitch) = self
+                .snapped_notes
+                .note_off(self.get_pitch(*qwerty_note_off))
+            {
+                self.note_off_keys.push(pitch);
+            }
         }
 
         #[cfg(debug_assertions)]
@@ -268,11 +283,12 @@ impl Input {
             for midi in midi.iter() {
                 // Note-on.
                 if midi[0] >= 144 && midi[0] <= 159 && midi[1] > MIN_NOTE && midi[2] <= MAX_NOTE {
-                    // Set the volume.
+                    // Set the pitch and the volume.
+                    let pitch = self.snapped_notes.note_on(midi[1], state);
                     let midi = if state.input.use_volume {
-                        [midi[0], midi[1], volume]
+                        [midi[0], pitch, volume]
                     } else {
-                        *midi
+                        [midi[0], pitch, midi[2]]
                     };
                     // Remember the note-on for piano roll input.
                     if !state.input.is_playing {
@@ -282,21 +298,37 @@ impl Input {
                         // Copy this note to the immediate note-on array.
                         self.note_on_messages.push(midi);
                     }
+                    // Play the note so that it can be recorded in real time.
+                    else if Input::is_recording(state) {
+                        self.note_on_messages.push(midi);
+                    }
                 }
                 // Note-off.
                 if midi[0] >= 128 && midi[0] <= 143 {
-                    self.note_off_keys.push(midi[1]);
-                    if state.input.armed && !state.input.is_playing {
-                        // Find the corresponding note.
-                        for note_on in self.note_on_events.iter_mut() {
-                            // Same key. Note-off.
-                            if note_on.note[1] == midi[1] {
-                                note_on.off = true;
+                    if let Some(pitch) = self.snapped_notes.note_off(midi[1]) {
+                        self.note_off_keys.push(pitch);
+                        if state.input.armed && !state.input.is_playing {
+                            // Find the corresponding note.
+                            for note_on in self.note_on_events.iter_mut() {
+                                // Same key. Note-off.
+                                if note_on.note[1] == pitch {
+                                    note_on.off = true;
+                                }
                             }
                         }
                     }
                 }
             }
+            // Get controller, channel pressure, and pitch bend messages that aren't bound to input events.
+            for midi in midi.iter().filter(|m| {
+                AutomationType::from_midi_message(m).is_some()
+                    && !self
+                        .midi_events
+                        .values()
+                        .any(|b| b.bytes[0] == m[0] && b.bytes[1] == m[1])
+            }) {
+                self.control_messages.push(*midi);
+            }
             // If all note-ons are off, add them to the `notes` buffer as notes.
             if !self.note_on_events.is_empty() && self.note_on_events.iter().all(|n| n.off) {
                 for note_on in self.note_on_events.iter() {
@@ -423,13 +455,26 @@ impl Input {
 
     /// Push a new note from qwerty input.
     fn qwerty_note(&mut self, note: u8, state: &State) {
+        let note: [u8; 3] = [
+            144,
+            self.snapped_notes.note_on(self.get_pitch(note), state),
+            state.input.volume.get(),
+        ];
         if !state.input.is_playing {
-            let note: [u8; 3] = [144, self.get_pitch(note), state.input.volume.get()];
             if state.input.armed {
                 self.new_notes.push(note);
             }
             self.note_on_messages.push(note);
         }
+        // Play the note so that it can be recorded in real time.
+        else if Input::is_recording(state) {
+            self.note_on_messages.push(note);
+        }
+    }
+
+    /// Returns true if notes are being recorded in real time.
+    fn is_recording(state: &State) -> bool {
+        state.input.is_playing && state.input.armed && state.input.record
     }
 
     /// Converts the note index to a MIDI note value.
@@ -441,13 +486,19 @@ impl Input {
     fn clear_notes_on_qwerty_octave(&mut self) {
         // Qwerty note-off.
         for (_, qwerty_note_off) in QWERTY_NOTE_EVENTS.iter() {
-            self.note_off_keys.push(self.get_pitch(*qwerty_note_off));
+            if let Some(pitch) = self
+                .snapped_notes
+                .note_off(self.get_pitch(*qwerty_note_off))
+            {
+                self.note_off_keys.push(pitch);
+            }
         }
     }
 
     #[cfg(debug_assertions)]
     fn listen_for_note_offs(&mut self) {
         if self.happened(&InputEvent::NotesOff) {
+            self.snapped_notes.clear();
             self.note_off_keys
                 .append(&mut (MIN_NOTE..MAX_NOTE).collect());
         }
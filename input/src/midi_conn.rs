@@ -67,15 +67,17 @@ impl MidiConn {
     }
 
     /// The MIDI callback function. Send the message out of the thread.
+    ///
+    /// Channel messages are either 3 bytes or 2 bytes (program change and channel pressure). 2-byte messages are padded with a 0.
+    /// System messages, such as clock messages, are ignored.
     fn midi_callback(_: u64, message: &[u8], sender: &mut MidiBuffer) {
         const LEN: usize = 3;
 
-        // There are a few 2-byte MIDI messages that need to be ignored.
-        if message.len() != LEN {
+        if message.len() < 2 || message.len() > LEN || !(0x80..0xF0).contains(&message[0]) {
             return;
         }
         let mut m = [0u8; LEN];
-        m.copy_from_slice(message);
+        m[..message.len()].copy_from_slice(message);
         let mut buffer = sender.lock();
         buffer.push(m);
     }
@@ -87,28 +89,54 @@ mod tests {
 
     use super::MidiConn;
 
-    use midly::{live::LiveEvent, MidiMessage};
+    use midly::live::{LiveEvent, SystemCommon, SystemRealtime};
+    use midly::{MidiMessage, PitchBend};
     use parking_lot::Mutex;
 
     #[test]
     fn midi_test() {
         // These messages should be ready.
         for midi_message in [
-            MidiMessage::NoteOn {
-                key: 60.into(),
-                vel: 120.into(),
-            },
-            MidiMessage::NoteOff {
-                key: 60.into(),
-                vel: 120.into(),
-            },
-        ]
-        .iter()
-        .zip([144, 128])
-        {
+            (
+                MidiMessage::NoteOn {
+                    key: 60.into(),
+                    vel: 120.into(),
+                },
+                [144, 60, 120],
+            ),
+            (
+                MidiMessage::NoteOff {
+                    key: 60.into(),
+                    vel: 120.into(),
+                },
+                [128, 60, 120],
+            ),
+            (
+                MidiMessage::Controller {
+                    controller: 64.into(),
+                    value: 127.into(),
+                },
+                [176, 64, 127],
+            ),
+            (
+                MidiMessage::PitchBend {
+                    bend: PitchBend::mid_raw_value(),
+                },
+                [224, 0, 64],
+            ),
+            // 2-byte messages are padded.
+            (
+                MidiMessage::ChannelAftertouch { vel: 5.into() },
+                [208, 5, 0],
+            ),
+            (
+                MidiMessage::ProgramChange { program: 3.into() },
+                [192, 3, 0],
+            ),
+        ] {
             let message = LiveEvent::Midi {
                 channel: 0.into(),
-                message: midi_message.0.clone(),
+                message: midi_message.0,
             };
             let mut buffer_conn = Arc::new(Mutex::new(Vec::new()));
             let mut buffer_message = Vec::new();
@@ -117,20 +145,16 @@ mod tests {
             // The message was ready.
             let b = buffer_conn.lock();
             assert_eq!(b.len(), 1);
-            assert_eq!(b[0], [midi_message.1, 60, 120]);
+            assert_eq!(b[0], midi_message.1);
         }
         // These messages should be ignored.
         for ignore_message in [
-            MidiMessage::ChannelAftertouch { vel: 5.into() },
-            MidiMessage::ProgramChange { program: 0.into() },
+            LiveEvent::Realtime(SystemRealtime::TimingClock),
+            LiveEvent::Common(SystemCommon::SongSelect(1.into())),
         ] {
-            let message = LiveEvent::Midi {
-                channel: 0.into(),
-                message: ignore_message,
-            };
             let mut buffer_conn = Arc::new(Mutex::new(Vec::new()));
             let mut buffer_message = Vec::new();
-            message.write(&mut buffer_message).unwrap();
+            ignore_message.write(&mut buffer_message).unwrap();
             MidiConn::midi_callback(0, &buffer_message, &mut buffer_conn);
             // The message was ignored.
             assert_eq!(buffer_conn.lock().len(), 0);
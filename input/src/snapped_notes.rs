@@ -0,0 +1,81 @@
+use common::State;
+use hashbrown::HashMap;
+
+/// Notes that are held down and that might have been snapped to the key of the music.
+///
+/// Each note-off releases the pitch that its own note-on produced, even if the key or `state.input.snap_to_key` changed in the meantime.
+#[derive(Default)]
+pub(crate) struct SnappedNotes {
+    /// Key = The pitch of the note-on before it was snapped. Value = The pitch that was played.
+    held: HashMap<u8, u8>,
+}
+
+impl SnappedNotes {
+    /// Returns the pitch of a note-on. If `state.input.snap_to_key` is true, this is the nearest note in the key. Percussion tracks don't have a key.
+    pub(crate) fn note_on(&mut self, note: u8, state: &State) -> u8 {
+        let pitch = if state.input.snap_to_key
+            && !state
+                .music
+                .get_selected_track()
+                .is_some_and(|t| t.percussion)
+        {
+            state.music.key.snap(note)
+        } else {
+            note
+        };
+        self.held.insert(note, pitch);
+        pitch
+    }
+
+    /// Returns the pitch to release for a note-off.
+    /// Returns None if another held note was snapped to the same pitch, because that note is still playing.
+    pub(crate) fn note_off(&mut self, note: u8) -> Option<u8> {
+        let pitch = self.held.remove(&note).unwrap_or(note);
+        if self.held.values().any(|p| *p == pitch) {
+            None
+        } else {
+            Some(pitch)
+        }
+    }
+
+    /// Forget every held note.
+    #[cfg(debug_assertions)]
+    pub(crate) fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnappedNotes;
+    use common::{get_test_config, Key, MidiTrack, ScaleType, State};
+
+    const MIDDLE_C: u8 = 60;
+
+    #[test]
+    fn snapped_notes() {
+        let mut state = State::new(&get_test_config());
+        state.music.midi_tracks.push(MidiTrack::new(0));
+        state.music.selected = Some(0);
+        state.music.key = Key {
+            root: 0,
+            scale_type: ScaleType::Major,
+            ..Default::default()
+        };
+        let mut notes = SnappedNotes::default();
+        // Don't snap.
+        assert_eq!(notes.note_on(MIDDLE_C + 1, &state), MIDDLE_C + 1);
+        assert_eq!(notes.note_off(MIDDLE_C + 1), Some(MIDDLE_C + 1));
+        // C# and C both snap to C.
+        state.input.snap_to_key = true;
+        assert_eq!(notes.note_on(MIDDLE_C + 1, &state), MIDDLE_C);
+        assert_eq!(notes.note_on(MIDDLE_C, &state), MIDDLE_C);
+        // C is still held.
+        assert_eq!(notes.note_off(MIDDLE_C + 1), None);
+        // The key changed, but the note-off releases the pitch that was played.
+        state.music.key.root = 1;
+        assert_eq!(notes.note_off(MIDDLE_C), Some(MIDDLE_C));
+        // A note-off without a note-on.
+        assert_eq!(notes.note_off(MIDDLE_C + 2), Some(MIDDLE_C + 2));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::panel::*;
+use audio::audio_devices::AudioDeviceSetting;
+use common::PanelType;
+
+/// Set the audio output device.
+#[derive(Default)]
+pub(crate) struct AudioDevicePanel {
+    /// The popup.
+    popup: Popup,
+    /// The tooltips handler.
+    tooltips: Tooltips,
+}
+
+impl AudioDevicePanel {
+    pub fn enable(&mut self, state: &mut State, conn: &mut Conn) {
+        conn.audio_devices.refresh();
+        self.popup.enable(state, vec![PanelType::AudioDevice]);
+    }
+
+    /// Say the selected setting's value, and whether the audio device couldn't be opened.
+    fn enqueue_status_tts(conn: &Conn, tts: &mut TTS, text: &Text) {
+        let setting = conn.audio_devices.setting.get();
+        let value = conn
+            .audio_devices
+            .get_value(&setting)
+            .unwrap_or_else(|| text.get("AUDIO_DEVICE_PANEL_DEFAULT"));
+        let key = match setting {
+            AudioDeviceSetting::Host => "AUDIO_DEVICE_PANEL_STATUS_TTS_HOST",
+            AudioDeviceSetting::Device => "AUDIO_DEVICE_PANEL_STATUS_TTS_DEVICE",
+            AudioDeviceSetting::Framerate => "AUDIO_DEVICE_PANEL_STATUS_TTS_FRAMERATE",
+            AudioDeviceSetting::BufferSize => "AUDIO_DEVICE_PANEL_STATUS_TTS_BUFFER_SIZE",
+        };
+        tts.enqueue(TtsString::from(text.get_with_values(key, &[&value])));
+        if !conn.has_audio_device() {
+            tts.enqueue(TtsString::from(
+                text.get_ref("AUDIO_DEVICE_PANEL_STATUS_TTS_NO_DEVICE"),
+            ));
+        }
+    }
+}
+
+impl Panel for AudioDevicePanel {
+    fn update(
+        &mut self,
+        state: &mut State,
+        conn: &mut Conn,
+        input: &Input,
+        tts: &mut TTS,
+        text: &Text,
+        _: &mut PathsState,
+    ) -> Option<Snapshot> {
+        if input.happened(&InputEvent::StatusTTS) {
+            Self::enqueue_status_tts(conn, tts, text);
+        } else if input.happened(&InputEvent::InputTTS) {
+            tts.enqueue(self.tooltips.get_tooltip(
+                "AUDIO_DEVICE_PANEL_INPUT_TTS_0",
+                &[
+                    InputEvent::PreviousAudioDeviceSetting,
+                    InputEvent::NextAudioDeviceSetting,
+                ],
+                input,
+                text,
+            ));
+            tts.enqueue(self.tooltips.get_tooltip(
+                "AUDIO_DEVICE_PANEL_INPUT_TTS_1",
+                &[
+                    InputEvent::PreviousAudioDeviceSettingValue,
+                    InputEvent::NextAudioDeviceSettingValue,
+                ],
+                input,
+                text,
+            ));
+            tts.enqueue(self.tooltips.get_tooltip(
+                "AUDIO_DEVICE_PANEL_INPUT_TTS_2",
+                &[InputEvent::CloseAudioDevicePanel],
+                input,
+                text,
+            ));
+        }
+        // Select a setting.
+        else if input.happened(&InputEvent::PreviousAudioDeviceSetting) {
+            conn.audio_devices.setting.index.increment(false);
+        } else if input.happened(&InputEvent::NextAudioDeviceSetting) {
+            conn.audio_devices.setting.index.increment(true);
+        }
+        // Set a value, rebuild the audio stream, and remember the settings.
+        else if (input.happened(&InputEvent::PreviousAudioDeviceSettingValue)
+            && conn.audio_devices.cycle(false))
+            || (input.happened(&InputEvent::NextAudioDeviceSettingValue)
+                && conn.audio_devices.cycle(true))
+        {
+            conn.set_audio_device(&state.music);
+            conn.audio_devices.settings.write();
+            // Say the new value.
+            tts.stop();
+            Self::enqueue_status_tts(conn, tts, text);
+        }
+        // Close the panel.
+        else if input.happened(&InputEvent::CloseAudioDevicePanel) {
+            self.popup.disable(state);
+        }
+        None
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        false
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::Save;
+use audio::Conn;
+use common::config::{get_section, parse_or};
+use common::{PanelType, PathsState, State};
+use ini::Ini;
+use std::time::{Duration, Instant};
+
+/// The default number of seconds between autosaves.
+const DEFAULT_INTERVAL: u64 = 60;
+
+/// Periodically write the state to the recovery file.
+pub(crate) struct Autosave {
+    /// The time between autosaves. If None, autosave is disabled.
+    interval: Option<Duration>,
+    /// The time of the last autosave.
+    time: Instant,
+    /// If true, something changed since the last autosave.
+    changed: bool,
+}
+
+impl Autosave {
+    pub fn new(config: &Ini) -> Self {
+        let seconds = parse_or::<u64>(
+            &get_section(config, "SAVE"),
+            "autosave_interval",
+            DEFAULT_INTERVAL,
+        );
+        Self {
+            interval: if seconds == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(seconds))
+            },
+            time: Instant::now(),
+            changed: false,
+        }
+    }
+
+    /// Call this when the state changes. The next autosave will write the recovery file.
+    pub fn set_changed(&mut self) {
+        self.changed = true;
+    }
+
+    /// If enough time has elapsed and something changed since the last autosave, write the recovery file.
+    /// If there aren't any unsaved changes, there's nothing to recover, so the recovery file is deleted.
+    pub fn update(&mut self, state: &State, conn: &Conn, paths_state: &PathsState) {
+        if let Some(interval) = self.interval {
+            // Don't autosave while a popup is open because the popup's panels would be saved.
+            if self.time.elapsed() >= interval && state.panels.contains(&PanelType::Music) {
+                self.time = Instant::now();
+                if !state.unsaved_changes {
+                    Save::delete_recovery();
+                } else if self.changed {
+                    Save::write_recovery(state, conn, paths_state);
+                }
+                self.changed = false;
+            }
+        }
+    }
+}
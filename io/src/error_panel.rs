@@ -0,0 +1,157 @@
+use crate::panel::*;
+use crate::SaveError;
+use audio::{LoadError, SoundFontError, TuningError};
+use common::PanelType;
+use text::get_file_name;
+
+/// Show an error message.
+#[derive(Default)]
+pub(crate) struct ErrorPanel {
+    popup: Popup,
+    tooltips: Tooltips,
+}
+
+impl ErrorPanel {
+    /// Enable the panel and say the errors.
+    pub fn enable(&mut self, state: &mut State, tts: &mut TTS, text: &Text, errors: &[LoadError]) {
+        let error = errors
+            .iter()
+            .map(|error| match error {
+                LoadError::SoundFont(error) => Self::get_soundfont_error(error, text),
+                LoadError::Tuning(error) => Self::get_tuning_error(error, text),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        self.show(state, tts, text, error);
+    }
+
+    /// Enable the panel and say an error that occurred while reading a save file.
+    pub fn enable_save_error(
+        &mut self,
+        state: &mut State,
+        tts: &mut TTS,
+        text: &Text,
+        error: &SaveError,
+    ) {
+        let path = get_file_name(error.get_path());
+        let error = match error {
+            SaveError::Read { error, .. } => {
+                text.get_with_values("SAVE_ERROR_READ", &[path, error])
+            }
+            SaveError::Invalid { error, .. } => {
+                text.get_with_values("SAVE_ERROR_INVALID", &[path, error])
+            }
+            SaveError::Newer { version, .. } => {
+                text.get_with_values("SAVE_ERROR_NEWER", &[path, version])
+            }
+        };
+        self.show(state, tts, text, error);
+    }
+
+    /// Enable the panel and say an error that occurred while exporting.
+    pub fn enable_export_error(
+        &mut self,
+        state: &mut State,
+        tts: &mut TTS,
+        text: &Text,
+        error: &str,
+    ) {
+        let error = text.get_with_values("EXPORT_ERROR", &[error]);
+        self.show(state, tts, text, error);
+    }
+
+    /// Show the error text. If the panel is already enabled, the error is appended to the current error.
+    fn show(&mut self, state: &mut State, tts: &mut TTS, text: &Text, error: String) {
+        // Append to the current error.
+        if state.panels.contains(&PanelType::Error) {
+            if let Some(e) = &mut state.error {
+                e.push(' ');
+                e.push_str(&error);
+            }
+        } else {
+            self.popup.enable(state, vec![PanelType::Error]);
+            state.error = Some(error.clone());
+        }
+        tts.stop();
+        tts.enqueue(text.get_error(&error));
+    }
+
+    /// Returns the text of a SoundFont error.
+    fn get_soundfont_error(error: &SoundFontError, text: &Text) -> String {
+        let channel = error.get_channel().to_string();
+        let path = get_file_name(error.get_path());
+        match error {
+            SoundFontError::Missing { .. } => {
+                text.get_with_values("SOUNDFONT_ERROR_MISSING", &[&channel, path])
+            }
+            SoundFontError::Open { error, .. } => {
+                text.get_with_values("SOUNDFONT_ERROR_OPEN", &[&channel, path, error])
+            }
+            SoundFontError::Invalid { error, .. } => {
+                text.get_with_values("SOUNDFONT_ERROR_INVALID", &[&channel, path, error])
+            }
+        }
+    }
+
+    /// Returns the text of a tuning error.
+    fn get_tuning_error(error: &TuningError, text: &Text) -> String {
+        let channel = error.get_channel().to_string();
+        let path = get_file_name(error.get_path());
+        match error {
+            TuningError::Scale { error, .. } => {
+                text.get_with_values("TUNING_ERROR_SCALE", &[&channel, path, error])
+            }
+            TuningError::KeyboardMapping { error, .. } => {
+                text.get_with_values("TUNING_ERROR_KEYBOARD_MAPPING", &[&channel, path, error])
+            }
+        }
+    }
+}
+
+impl Panel for ErrorPanel {
+    fn update(
+        &mut self,
+        state: &mut State,
+        _: &mut Conn,
+        input: &Input,
+        tts: &mut TTS,
+        text: &Text,
+        _: &mut PathsState,
+    ) -> Option<Snapshot> {
+        if input.happened(&InputEvent::StatusTTS) {
+            if let Some(error) = &state.error {
+                tts.enqueue(text.get_error(error));
+            }
+        } else if input.happened(&InputEvent::InputTTS) {
+            tts.enqueue(self.tooltips.get_tooltip(
+                "ERROR_PANEL_INPUT_TTS",
+                &[InputEvent::CloseErrorPanel],
+                input,
+                text,
+            ));
+        } else if input.happened(&InputEvent::CloseErrorPanel) {
+            self.popup.disable(state);
+            state.error = None;
+        }
+        None
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        false
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::panel::*;
 use audio::export::ExportState;
 use common::PanelType;
+use std::thread::JoinHandle;
 
 /// Are we done yet?
 #[derive(Default)]
@@ -9,13 +10,22 @@ pub(crate) struct ExportPanel {
     panels: Vec<PanelType>,
     /// The previous focus.
     focus: usize,
+    /// The export thread.
+    handle: Option<JoinHandle<Result<(), String>>>,
 }
 
 impl ExportPanel {
     /// Enable this panel.
-    pub fn enable(&mut self, state: &mut State, panels: &[PanelType], focus: usize) {
+    pub fn enable(
+        &mut self,
+        state: &mut State,
+        panels: &[PanelType],
+        focus: usize,
+        handle: JoinHandle<Result<(), String>>,
+    ) {
         self.panels = panels.to_vec();
         self.focus = focus;
+        self.handle = Some(handle);
         state.panels = vec![PanelType::ExportState];
         state.focus.set(0);
     }
@@ -32,10 +42,16 @@ impl Panel for ExportPanel {
         _: &mut PathsState,
     ) -> Option<Snapshot> {
         // We're done.
-        let export_state = conn.export_state.lock();
-        if *export_state == ExportState::NotExporting {
+        let export_state = *conn.export_state.lock();
+        if export_state == ExportState::NotExporting {
             state.panels.clone_from(&self.panels);
             state.focus.set(self.focus);
+            // Show the error, if any.
+            if let Some(handle) = self.handle.take() {
+                if let Ok(Err(error)) = handle.join() {
+                    return Some(Snapshot::from_export_error(error));
+                }
+            }
         }
         None
     }
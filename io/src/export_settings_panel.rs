@@ -1,15 +1,12 @@
 use crate::abc123::{on_disable_exporter, update_exporter};
 use crate::panel::*;
 use audio::export::{ExportSetting, ExportType, MultiFileSuffix};
-use audio::exporter::{Exporter, MP3_BIT_RATES};
+use audio::exporter::{Exporter, FRAMERATES, MP3_BIT_RATES};
 use audio::Conn;
 use common::{IndexedValues, U64orF32};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-/// All possible audio framerates.
-const FRAMERATES: [u64; 3] = [22050, 44100, 48000];
-
 /// Set the values of export settings.
 #[derive(Default)]
 pub(crate) struct ExportSettingsPanel {
@@ -1,35 +1,59 @@
-use audio::{Command, Conn};
-use common::{MidiTrack, Music, Note, Paths, State, U64orF32};
+use audio::{Command, Conn, LoadError};
+use common::{
+    AutomationType, MidiTrack, Music, Note, Paths, State, U64orF32, MAX_TIME_SIGNATURE_NUMERATOR,
+    PAN_CONTROLLER, PERCUSSION_CHANNEL, PPQ_F, TIME_SIGNATURE_DENOMINATORS,
+};
 use midly::{MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
 use std::fs::read;
 use std::path::Path;
 use std::str::from_utf8;
 
-pub(crate) fn import(path: &Path, state: &mut State, conn: &mut Conn) {
+/// Import a MIDI file. Returns a list of SoundFonts that couldn't be loaded.
+pub(crate) fn import(path: &Path, state: &mut State, conn: &mut Conn) -> Vec<LoadError> {
     let bytes = read(path).unwrap();
     let smf = Smf::parse(&bytes).unwrap();
     let timing = match smf.header.timing {
         Timing::Metrical(v) => v.as_int() as f32,
         Timing::Timecode(fps, t) => fps.as_f32() / t as f32,
     };
+    // Converts MIDI ticks into PPQ.
+    let ticks_to_ppq = PPQ_F / timing;
     let mut music = Music::default();
+    // Remove any tempo and time signature changes. They will be replaced by the changes in the MIDI file.
+    state.time.tempos.clear();
+    state.time.time_signatures.clear();
     let paths = Paths::get();
+    let mut errors = vec![];
     for (i, track_events) in smf.tracks.iter().enumerate() {
         // Create a new track.
         let c = i as u8;
-        let mut track = MidiTrack::new(c);
+        // This is a percussion track if it uses the General MIDI percussion channel.
+        let percussion = track_events.iter().any(|e| match e.kind {
+            TrackEventKind::Midi {
+                channel,
+                message: _,
+            } => channel.as_int() == PERCUSSION_CHANNEL,
+            _ => false,
+        });
+        let mut track = if percussion {
+            MidiTrack::new_percussion(c)
+        } else {
+            MidiTrack::new(c)
+        };
         // Load the default SoundFont.
-        conn.do_commands(&[Command::LoadSoundFont {
+        errors.append(&mut conn.do_commands(&[Command::LoadSoundFont {
             channel: c,
             path: paths.default_soundfont_path.clone(),
-        }]);
-        let mut time = 0;
+            percussion,
+        }]));
+        let mut ticks = 0;
         // A list of note-on events that need corresponding note-off messages.
         let mut note_ons = vec![];
 
         // Iterate through this track's events.
         for track_event in track_events {
-            time += track_event.delta.as_int() as u64;
+            ticks += track_event.delta.as_int() as u64;
+            let time = (ticks as f32 * ticks_to_ppq) as u64;
             match track_event.kind {
                 TrackEventKind::Escape(_) | TrackEventKind::SysEx(_) => (),
                 TrackEventKind::Meta(message) => match message {
@@ -40,12 +64,20 @@ pub(crate) fn import(path: &Path, state: &mut State, conn: &mut Conn) {
                         }
                     }
                     MetaMessage::Tempo(data) => {
-                        state.time.bpm = U64orF32::from(60000000 / data.as_int() as u64);
+                        // Add a tempo change, or set the initial tempo if this is the start of the music.
+                        state.time.add_tempo(time);
+                        *state.time.get_bpm_mut(time) =
+                            U64orF32::from(60000000 / data.as_int() as u64);
                     }
-                    MetaMessage::TimeSignature(n, d, c, b) => {
-                        // This SHOULD always be correct. If not, there might be an error with how n is used.
-                        let q = (n as f32 / 2f32.powf(d as f32)) * (timing / (c * b) as f32);
-                        state.time.bpm = U64orF32::from(state.time.bpm.get_f() * q);
+                    MetaMessage::TimeSignature(n, d, _, _) => {
+                        // Add a time signature change, or set the initial time signature if this is the start of the music.
+                        state.time.add_time_signature(time);
+                        let time_signature = state.time.get_time_signature_mut(time);
+                        time_signature.numerator = n.clamp(1, MAX_TIME_SIGNATURE_NUMERATOR);
+                        // The denominator is a negative power of two.
+                        if let Some(denominator) = TIME_SIGNATURE_DENOMINATORS.get(d as usize) {
+                            time_signature.denominator = *denominator;
+                        }
                     }
                     MetaMessage::Text(data) => {
                         if let Ok(text) = from_utf8(data) {
@@ -78,19 +110,48 @@ pub(crate) fn import(path: &Path, state: &mut State, conn: &mut Conn) {
                             // Remove the note-on event.
                             note_ons.remove(index);
                         }
+                        // Set the pan if this is before the first note.
+                        MidiMessage::Controller { controller, value }
+                            if controller.as_int() == PAN_CONTROLLER
+                                && track.notes.is_empty()
+                                && note_ons.is_empty() =>
+                        {
+                            track.pan = value.as_int();
+                        }
+                        // Add an automation point.
+                        MidiMessage::Controller { controller, value } => {
+                            track
+                                .get_automation_mut(&AutomationType::Controller(
+                                    controller.as_int(),
+                                ))
+                                .set(time, value.as_int() as u16);
+                        }
+                        MidiMessage::ChannelAftertouch { vel } => {
+                            track
+                                .get_automation_mut(&AutomationType::ChannelPressure)
+                                .set(time, vel.as_int() as u16);
+                        }
+                        MidiMessage::PitchBend { bend } => {
+                            track
+                                .get_automation_mut(&AutomationType::PitchBend)
+                                .set(time, bend.0.as_int());
+                        }
                         // Set the preset.
                         MidiMessage::ProgramChange { program } => {
-                            conn.do_commands(&[Command::SetProgram {
-                                channel: track.channel,
-                                path: paths.default_soundfont_path.clone(),
-                                bank_index: conn
-                                    .state
-                                    .programs
-                                    .get(&track.channel)
-                                    .unwrap()
-                                    .bank_index,
-                                preset_index: program.as_int() as usize,
-                            }]);
+                            // The default SoundFont might not have been loaded.
+                            if let Some(bank_index) = conn
+                                .state
+                                .programs
+                                .get(&track.channel)
+                                .map(|p| p.bank_index)
+                            {
+                                conn.do_commands(&[Command::SetProgram {
+                                    channel: track.channel,
+                                    path: paths.default_soundfont_path.clone(),
+                                    bank_index,
+                                    preset_index: program.as_int() as usize,
+                                }]);
+                            }
                         }
                         _ => (),
                     }
@@ -106,4 +167,7 @@ pub(crate) fn import(path: &Path, state: &mut State, conn: &mut Conn) {
         music.selected = Some(0);
     }
     state.music = music;
+    // Every track tried to load the same SoundFont, so only report each SoundFont once.
+    errors.dedup_by(|a, b| a.get_path() == b.get_path());
+    errors
 }
@@ -11,6 +11,8 @@ pub(crate) enum IOCommand {
     CloseOpenFile,
     /// Quit the application.
     Quit,
+    /// Undo or redo until this many actions are applied.
+    SetUndoPosition(usize),
 }
 
 pub(crate) type IOCommands = Option<Vec<IOCommand>>;
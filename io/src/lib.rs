@@ -17,12 +17,13 @@
 
 use audio::export::ExportState;
 use audio::play_state::PlayState;
-use audio::Conn;
-use common::{InputState, Music, PanelType, Paths, PathsState, SelectMode, State};
+use audio::{Conn, LoadError, SoundFontError};
+use common::{InputState, Music, PanelType, Paths, PathsState, SelectMode, State, UndoHistory};
 use edit::edit_file;
 use hashbrown::HashMap;
 use ini::Ini;
 use input::{Input, InputEvent};
+use std::collections::VecDeque;
 use std::path::Path;
 use text::{Enqueable, Text, Tooltips, TtsString, TTS};
 mod export_panel;
@@ -44,19 +45,37 @@ use export_settings_panel::ExportSettingsPanel;
 use open_file_panel::OpenFilePanel;
 use panel::Panel;
 use piano_roll::PianoRollPanel;
-use save::Save;
+pub use save::Save;
 use snapshot::Snapshot;
 use tracks_panel::TracksPanel;
 mod abc123;
 mod export_settings_panel;
 mod quit_panel;
 use quit_panel::QuitPanel;
+mod recorder;
+use recorder::Recorder;
+mod audio_device_panel;
 mod links_panel;
+use audio_device_panel::AudioDevicePanel;
 mod popup;
 use links_panel::LinksPanel;
+mod error_panel;
+use error_panel::ErrorPanel;
+mod relocate_panel;
+use relocate_panel::RelocatePanel;
+mod autosave;
+use autosave::Autosave;
+mod recover_panel;
+use recover_panel::RecoverPanel;
+mod migrations;
+mod save_error;
+pub use save_error::SaveError;
+mod undo_history_panel;
+mod undo_label;
+use undo_history_panel::UndoHistoryPanel;
 
-/// The maximum size of the undo stack.
-const MAX_UNDOS: usize = 100;
+/// The maximum size of the undo stack. Snapshots share notes with each other (see `common::Notes`), so each snapshot only stores copies of the tracks that were modified.
+const MAX_UNDOS: usize = 1000;
 
 /// Parse user input and apply it to the application's various states as needed:
 ///
@@ -65,8 +84,8 @@ const MAX_UNDOS: usize = 100;
 /// - Modify the `PathsState`.
 /// - Modify the `Conn`.
 pub struct IO {
-    /// A stack of snapshots that can be popped to undo an action.
-    undo: Vec<Snapshot>,
+    /// A stack of snapshots that can be popped to undo an action. The oldest snapshot is at the front.
+    undo: VecDeque<Snapshot>,
     /// A stack of snapshots that can be popped to redo an action.
     redo: Vec<Snapshot>,
     /// Top-level text-to-speech lookups.
@@ -87,10 +106,24 @@ pub struct IO {
     quit_panel: QuitPanel,
     /// The links panel.
     links_panel: LinksPanel,
+    /// The audio device panel.
+    audio_device_panel: AudioDevicePanel,
+    /// The error panel.
+    error_panel: ErrorPanel,
+    /// The relocate panel.
+    relocate_panel: RelocatePanel,
+    /// The recover panel.
+    recover_panel: RecoverPanel,
+    /// The undo history panel.
+    undo_history_panel: UndoHistoryPanel,
     /// The active panels prior to exporting audio.
     pre_export_panels: Vec<PanelType>,
     /// The index of the focused panel prior to exporting audio.
     pre_export_focus: usize,
+    /// Records notes in real time while music is playing.
+    recorder: Recorder,
+    /// Periodically writes the recovery file.
+    autosave: Autosave,
 }
 
 impl IO {
@@ -137,6 +170,22 @@ impl IO {
             tooltips
                 .get_tooltip("APP_TTS_6", &[InputEvent::EnableLinksPanel], input, text)
                 .clone(),
+            tooltips
+                .get_tooltip(
+                    "APP_TTS_7",
+                    &[InputEvent::EnableAudioDevicePanel],
+                    input,
+                    text,
+                )
+                .clone(),
+            tooltips
+                .get_tooltip(
+                    "APP_TTS_8",
+                    &[InputEvent::EnableUndoHistoryPanel],
+                    input,
+                    text,
+                )
+                .clone(),
         ];
         tts.insert(InputEvent::AppTTS, app_tts);
         // File TTS.
@@ -164,9 +213,12 @@ impl IO {
             tooltips
                 .get_tooltip("FILE_TTS_5", &[InputEvent::EditConfig], input, text)
                 .clone(),
+            tooltips
+                .get_tooltip("FILE_TTS_6", &[InputEvent::SaveBundle], input, text)
+                .clone(),
         ];
         tts.insert(InputEvent::FileTTS, file_tts);
-        let music_panel = MusicPanel::default();
+        let music_panel = MusicPanel::new(config);
         let tracks_panel = TracksPanel::default();
         let open_file_panel = OpenFilePanel::default();
         let piano_roll_panel = PianoRollPanel::new(&input_state.beat.get_u(), config);
@@ -174,6 +226,11 @@ impl IO {
         let export_settings_panel = ExportSettingsPanel::default();
         let quit_panel = QuitPanel::default();
         let links_panel = LinksPanel::default();
+        let audio_device_panel = AudioDevicePanel::default();
+        let error_panel = ErrorPanel::default();
+        let relocate_panel = RelocatePanel::new(config);
+        let recover_panel = RecoverPanel::default();
+        let undo_history_panel = UndoHistoryPanel::default();
         Self {
             tts,
             music_panel,
@@ -184,10 +241,17 @@ impl IO {
             export_settings_panel,
             quit_panel,
             links_panel,
+            audio_device_panel,
+            error_panel,
+            relocate_panel,
+            recover_panel,
+            undo_history_panel,
             redo: vec![],
-            undo: vec![],
+            undo: VecDeque::new(),
             pre_export_panels: vec![],
             pre_export_focus: 0,
+            recorder: Recorder::default(),
+            autosave: Autosave::new(config),
         }
     }
 
@@ -226,6 +290,13 @@ impl IO {
             return false;
         }
 
+        // Write the recovery file.
+        self.autosave.update(state, conn, paths_state);
+
+        // Record notes in real time. If recording stopped, add the notes.
+        let snapshot = self.recorder.update(state, conn, input);
+        self.apply_snapshot(snapshot, state, conn, tts, text, paths_state);
+
         // Alphanumeric input.
         if state.input.alphanumeric_input {
             // Get the focused panel.
@@ -241,7 +312,7 @@ impl IO {
                     // There is always a snapshot (because we toggled off alphanumeric input).
                     let snapshot = Some(Snapshot::from_states(s0, state));
                     // Apply the snapshot.
-                    self.apply_snapshot(snapshot, state, conn, paths_state);
+                    self.apply_snapshot(snapshot, state, conn, tts, text, paths_state);
                     return false;
                 }
                 // Try to do alphanumeric input.
@@ -249,7 +320,7 @@ impl IO {
                     let (snapshot, updated) = panel.update_abc123(state, input, conn);
                     // We applied alphanumeric input.
                     if updated {
-                        self.apply_snapshot(snapshot, state, conn, paths_state);
+                        self.apply_snapshot(snapshot, state, conn, tts, text, paths_state);
                         return false;
                     }
                 }
@@ -266,7 +337,7 @@ impl IO {
                     true,
                     state,
                 ));
-                self.apply_snapshot(snapshot, state, conn, paths_state);
+                self.apply_snapshot(snapshot, state, conn, tts, text, paths_state);
                 return false;
             } else if let Some(track) = state.music.get_selected_track() {
                 // Play notes.
@@ -279,6 +350,13 @@ impl IO {
                 if !&input.note_off_keys.is_empty() {
                     conn.note_offs(state, &input.note_off_keys)
                 }
+                // Send controller, channel pressure, and pitch bend messages.
+                if !input.control_messages.is_empty()
+                    && panel.allow_play_music()
+                    && conn.state.programs.get(&track.channel).is_some()
+                {
+                    conn.control_messages(state, &input.control_messages);
+                }
             }
         }
         // New file.
@@ -320,6 +398,10 @@ impl IO {
         else if input.happened(&InputEvent::SaveFileAs) {
             self.open_file_panel.write_save(state, paths_state)
         }
+        // Save a bundle.
+        else if input.happened(&InputEvent::SaveBundle) {
+            self.open_file_panel.write_bundle(state, paths_state)
+        }
         // Export.
         else if input.happened(&InputEvent::ExportFile) {
             let export_state = *conn.export_state.lock();
@@ -344,36 +426,16 @@ impl IO {
         }
         // Undo.
         else if input.happened(&InputEvent::Undo) {
-            if let Some(undo) = self.undo.pop() {
-                // Get the redo state.
-                let redo = Snapshot::from_snapshot(&undo);
-                // Assign the undo state to the previous state.
-                if let Some(s1) = undo.from_state {
-                    *state = s1;
-                }
-                // Send the commands.
-                if let Some(commands) = undo.from_commands {
-                    conn.do_commands(&commands);
-                }
-                // Push to the redo stack.
-                self.redo.push(redo);
-                state.unsaved_changes = true;
+            let undo_history = state.panels.contains(&PanelType::UndoHistory);
+            // Undoing replaces the state, so refresh the undo history panel.
+            if self.undo(state, conn, tts, text) && undo_history {
+                self.enable_undo_history_panel(state, tts, text);
             }
         // Redo.
         } else if input.happened(&InputEvent::Redo) {
-            if let Some(redo) = self.redo.pop() {
-                let undo = Snapshot::from_snapshot(&redo);
-                // Assign the redo state to the current state.
-                if let Some(s1) = redo.from_state {
-                    *state = s1;
-                }
-                // Send the commands.
-                if let Some(commands) = redo.from_commands {
-                    conn.do_commands(&commands);
-                }
-                // Push to the undo stack.
-                self.undo.push(undo);
-                state.unsaved_changes = true;
+            let undo_history = state.panels.contains(&PanelType::UndoHistory);
+            if self.redo(state, conn, tts, text) && undo_history {
+                self.enable_undo_history_panel(state, tts, text);
             }
         }
         // Cycle panels.
@@ -381,12 +443,12 @@ impl IO {
             let s0 = state.clone();
             state.focus.increment(true);
             state.unsaved_changes = true;
-            self.undo.push(Snapshot::from_states(s0, state));
+            self.push_undo(Snapshot::from_states(s0, state), text);
         } else if input.happened(&InputEvent::PreviousPanel) {
             let s0 = state.clone();
             state.focus.increment(false);
             state.unsaved_changes = true;
-            self.undo.push(Snapshot::from_states(s0, state));
+            self.push_undo(Snapshot::from_states(s0, state), text);
         }
 
         // App-level TTS.
@@ -408,11 +470,26 @@ impl IO {
             self.links_panel.enable(state);
             return false;
         }
+        // Undo history.
+        if input.happened(&InputEvent::EnableUndoHistoryPanel)
+            && !state.panels.contains(&PanelType::UndoHistory)
+        {
+            self.enable_undo_history_panel(state, tts, text);
+            return false;
+        }
+        // Audio device.
+        if input.happened(&InputEvent::EnableAudioDevicePanel)
+            && !state.panels.contains(&PanelType::AudioDevice)
+        {
+            self.audio_device_panel.enable(state, conn);
+            return false;
+        }
         // Get the focused panel.
         let panel = self.get_panel(&state.panels[state.focus.get()]);
         // Update the focuses panel and potentially get a screenshot.
         let snapshot = panel.update(state, conn, input, tts, text, paths_state);
-        let (applied, need_to_quit) = self.apply_snapshot(snapshot, state, conn, paths_state);
+        let (applied, need_to_quit) =
+            self.apply_snapshot(snapshot, state, conn, tts, text, paths_state);
         // Quit while we're ahead.
         if need_to_quit {
             return true;
@@ -431,7 +508,7 @@ impl IO {
                 .music
                 .get_playable_tracks()
                 .iter()
-                .any(|t| !t.get_playback_notes(state.time.playback).is_empty())
+                .any(|t| !t.get_playback_notes(state.get_playback_start()).is_empty())
         {
             // Toggle whether music is playing.
             state.input.is_playing = matches!(
@@ -439,7 +516,7 @@ impl IO {
                 PlayState::NotPlaying | PlayState::Decaying
             );
             // Start to play music.
-            conn.set_music(state);
+            conn.set_music(state, &self.piano_roll_panel.count_in);
         }
         // No music is playing.
         if state.input.is_playing
@@ -456,15 +533,69 @@ impl IO {
 
     /// Open a save file from a path. This is called from main.rs
     pub fn load_save(
-        &self,
+        &mut self,
         save_path: &Path,
         state: &mut State,
         conn: &mut Conn,
+        tts: &mut TTS,
+        text: &Text,
         paths_state: &mut PathsState,
     ) {
-        Save::read(save_path, state, conn, paths_state);
-        // Set the saves directory.
-        paths_state.saves = FileAndDirectory::new_path(save_path.to_path_buf());
+        // Read the save file in a bundle.
+        let save_path = if save_path.is_dir() {
+            Save::get_bundle_save_path(save_path)
+        } else {
+            save_path.to_path_buf()
+        };
+        match Save::read(&save_path, state, conn, paths_state) {
+            Ok(errors) => {
+                // Set the saves directory.
+                paths_state.saves = FileAndDirectory::new_path(save_path);
+                self.show_errors(&errors, state, conn, tts, text, paths_state);
+            }
+            Err(error) => self.error_panel.enable_save_error(state, tts, text, &error),
+        }
+    }
+
+    /// Show SoundFont and tuning errors. Missing SoundFonts from a save file are shown in the relocate panel. Other errors are shown in the error panel.
+    fn show_errors(
+        &mut self,
+        errors: &[LoadError],
+        state: &mut State,
+        conn: &Conn,
+        tts: &mut TTS,
+        text: &Text,
+        paths_state: &PathsState,
+    ) {
+        if !state.relocations.is_empty() && !state.panels.contains(&PanelType::Relocate) {
+            self.relocate_panel
+                .enable(state, conn, tts, text, paths_state);
+        }
+        let errors: Vec<LoadError> = errors
+            .iter()
+            .filter(|error| match error {
+                LoadError::SoundFont(SoundFontError::Missing { channel, .. }) => {
+                    !state.relocations.iter().any(|r| r.channel == *channel)
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            self.error_panel.enable(state, tts, text, &errors);
+        }
+    }
+
+    /// Returns the undo history that is shown in the undo history panel. This is called from main.rs
+    pub fn get_undo_history(&self) -> &UndoHistory {
+        self.undo_history_panel.get_undo_history()
+    }
+
+    /// If the app didn't quit cleanly the last time it ran, ask the user whether to recover the unsaved music. This is called from main.rs
+    pub fn recover(&mut self, state: &mut State) {
+        if Paths::get().recovery_path.exists() {
+            self.recover_panel.enable(state);
+        }
     }
 
     fn get_panel(&mut self, panel_type: &PanelType) -> &mut dyn Panel {
@@ -480,6 +611,11 @@ impl IO {
             PanelType::Tracks => &mut self.tracks_panel,
             PanelType::Quit => &mut self.quit_panel,
             PanelType::Links => &mut self.links_panel,
+            PanelType::AudioDevice => &mut self.audio_device_panel,
+            PanelType::Error => &mut self.error_panel,
+            PanelType::Relocate => &mut self.relocate_panel,
+            PanelType::Recover => &mut self.recover_panel,
+            PanelType::UndoHistory => &mut self.undo_history_panel,
         }
     }
 
@@ -491,10 +627,13 @@ impl IO {
         snapshot: Option<Snapshot>,
         state: &mut State,
         conn: &mut Conn,
+        tts: &mut TTS,
+        text: &Text,
         paths_state: &mut PathsState,
     ) -> (bool, bool) {
         // Push an undo state generated by the focused panel.
         if let Some(snapshot) = snapshot {
+            self.autosave.set_changed();
             // Execute IO commands.
             if let Some(io_commands) = &snapshot.io_commands {
                 for command in io_commands {
@@ -511,30 +650,59 @@ impl IO {
                             OpenFileType::WriteSave => {
                                 self.open_file_panel.write_save(state, paths_state)
                             }
+                            OpenFileType::WriteBundle => {
+                                self.open_file_panel.write_bundle(state, paths_state)
+                            }
                             OpenFileType::ImportMidi => {
                                 self.open_file_panel.import_midi(state, paths_state)
                             }
+                            OpenFileType::Scale => self.open_file_panel.scale(state, paths_state),
+                            OpenFileType::KeyboardMapping => {
+                                self.open_file_panel.keyboard_mapping(state, paths_state)
+                            }
                         },
                         // Export.
                         IOCommand::Export => {
+                            let handle = conn.start_export(state, paths_state);
                             self.export_panel.enable(
                                 state,
                                 &self.pre_export_panels,
                                 self.pre_export_focus,
+                                handle,
                             );
-                            conn.start_export(state, paths_state);
                         }
                         // Close the open-file panel.
                         IOCommand::CloseOpenFile => self.open_file_panel.disable(state),
                         // Quit the application.
                         IOCommand::Quit => return (false, true),
+                        // Jump to a point in the undo history.
+                        IOCommand::SetUndoPosition(position) => {
+                            let position = *position;
+                            let mut applied = true;
+                            while applied && self.undo.len() > position {
+                                applied = self.undo(state, conn, tts, text);
+                            }
+                            while applied && self.undo.len() < position {
+                                applied = self.redo(state, conn, tts, text);
+                            }
+                            self.enable_undo_history_panel(state, tts, text);
+                        }
                     }
                 }
             }
+            // Show any errors.
+            self.show_errors(&snapshot.errors, state, conn, tts, text, paths_state);
+            if let Some(error) = &snapshot.save_error {
+                self.error_panel.enable_save_error(state, tts, text, error);
+            }
+            if let Some(error) = &snapshot.export_error {
+                self.error_panel
+                    .enable_export_error(state, tts, text, error);
+            }
             // Push to the undo stack.
             if snapshot.from_state.is_some() || snapshot.from_commands.is_some() {
                 state.unsaved_changes = true;
-                self.push_undo(snapshot);
+                self.push_undo(snapshot, text);
             }
             (true, false)
         } else {
@@ -542,13 +710,81 @@ impl IO {
         }
     }
 
-    /// Push this `UndoRedoState` to the undo stack and clear the redo stack.
-    fn push_undo(&mut self, snapshot: Snapshot) {
-        self.undo.push(snapshot);
+    /// Undo the most recent action. Returns true if there was an action to undo.
+    fn undo(&mut self, state: &mut State, conn: &mut Conn, tts: &mut TTS, text: &Text) -> bool {
+        match self.undo.pop_back() {
+            Some(undo) => {
+                // Get the redo state.
+                let redo = Snapshot::from_snapshot(&undo);
+                // Assign the undo state to the previous state.
+                if let Some(s1) = undo.from_state {
+                    *state = s1;
+                }
+                // Send the commands.
+                if let Some(commands) = undo.from_commands {
+                    let errors = conn.do_commands(&commands);
+                    if !errors.is_empty() {
+                        self.error_panel.enable(state, tts, text, &errors);
+                    }
+                }
+                // Push to the redo stack.
+                self.redo.push(redo);
+                state.unsaved_changes = true;
+                self.autosave.set_changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the most recently undone action. Returns true if there was an action to redo.
+    fn redo(&mut self, state: &mut State, conn: &mut Conn, tts: &mut TTS, text: &Text) -> bool {
+        match self.redo.pop() {
+            Some(redo) => {
+                let undo = Snapshot::from_snapshot(&redo);
+                // Assign the redo state to the current state.
+                if let Some(s1) = redo.from_state {
+                    *state = s1;
+                }
+                // Send the commands.
+                if let Some(commands) = redo.from_commands {
+                    let errors = conn.do_commands(&commands);
+                    if !errors.is_empty() {
+                        self.error_panel.enable(state, tts, text, &errors);
+                    }
+                }
+                // Push to the undo stack.
+                self.undo.push_back(undo);
+                state.unsaved_changes = true;
+                self.autosave.set_changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Enable the undo history panel, or refresh it if it's already enabled.
+    fn enable_undo_history_panel(&mut self, state: &mut State, tts: &mut TTS, text: &Text) {
+        // Undone actions are at the top of the redo stack.
+        let labels = self
+            .undo
+            .iter()
+            .chain(self.redo.iter().rev())
+            .map(|snapshot| snapshot.label.clone())
+            .collect();
+        self.undo_history_panel
+            .enable(state, labels, self.undo.len(), tts, text);
+    }
+
+    /// Label this `Snapshot`, push it to the undo stack, and clear the redo stack.
+    fn push_undo(&mut self, mut snapshot: Snapshot, text: &Text) {
+        snapshot.set_label(text);
+        self.autosave.set_changed();
+        self.undo.push_back(snapshot);
         self.redo.clear();
         // Remove an undo if there are too many.
         if self.undo.len() > MAX_UNDOS {
-            self.undo.remove(0);
+            self.undo.pop_front();
         }
     }
 }
@@ -0,0 +1,254 @@
+use crate::SaveError;
+use serde_json::Value;
+use std::path::Path;
+
+/// A migration converts a save file from one schema version to the next.
+type Migration = fn(Value) -> Value;
+
+/// The migrations, in order. The migration at index `i` converts a save file from schema version `i` to `i + 1`.
+const MIGRATIONS: [Migration; 2] = [add_flac, add_key_field];
+/// The current schema version. When the save file format changes, add a migration to `MIGRATIONS`.
+///
+/// 0. Pre-0.1.3. There is no version string and the exporter can't export .flac files.
+/// 1. 0.1.3 to 0.2.7.
+/// 2. The music panel has a key field.
+pub(crate) const SCHEMA_VERSION: u64 = MIGRATIONS.len() as u64;
+/// The key of the schema version in the save file.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+/// The key of the version string in the save file.
+const VERSION_KEY: &str = "version";
+
+/// Migrate deserialized save file data to the current schema version.
+///
+/// - `value` The deserialized save file data.
+/// - `path` The path to the save file. This is used for error messages.
+///
+/// Returns an error if the data isn't a JSON object or if the save file was written by a newer version of Cacophony.
+pub(crate) fn migrate(mut value: Value, path: &Path) -> Result<Value, SaveError> {
+    if !value.is_object() {
+        return Err(SaveError::Invalid {
+            path: path.to_path_buf(),
+            error: "Expected a JSON object.".to_string(),
+        });
+    }
+    let schema_version = get_schema_version(&value);
+    if schema_version > SCHEMA_VERSION {
+        return Err(SaveError::Newer {
+            path: path.to_path_buf(),
+            version: value[VERSION_KEY].as_str().unwrap_or("?").to_string(),
+        });
+    }
+    for migration in MIGRATIONS[schema_version as usize..].iter() {
+        value = migration(value);
+    }
+    value[SCHEMA_VERSION_KEY] = Value::from(SCHEMA_VERSION);
+    Ok(value)
+}
+
+/// Returns the schema version of deserialized save file data.
+/// Save files written before there were schema versions are identified by whether they have a version string.
+fn get_schema_version(value: &Value) -> u64 {
+    match value[SCHEMA_VERSION_KEY].as_u64() {
+        Some(schema_version) => schema_version,
+        None => {
+            if value[VERSION_KEY].is_string() {
+                1
+            } else {
+                0
+            }
+        }
+    }
+}
+
+/// 0 -> 1: Add .flac to the export types. Pre-0.1.3, the version isn't in the save file, so add it.
+fn add_flac(mut value: Value) -> Value {
+    if let Some(export_type) = value["exporter"]
+        .get_mut("export_type")
+        .and_then(|e| e.as_object_mut())
+    {
+        if let Some(values) = export_type.get_mut("values").and_then(|v| v.as_array_mut()) {
+            let flac = Value::from("Flac");
+            if !values.contains(&flac) {
+                values.push(flac);
+            }
+            let length = values.len();
+            if let Some(index) = export_type.get_mut("index") {
+                index["length"] = Value::from(length);
+            }
+        }
+    }
+    value[VERSION_KEY] = Value::from("0.1.2");
+    value
+}
+
+/// 1 -> 2: Add the key field to the music panel.
+fn add_key_field(mut value: Value) -> Value {
+    if let Some(music_panel_field) = value["state"]
+        .get_mut("music_panel_field")
+        .and_then(|m| m.as_object_mut())
+    {
+        if let Some(values) = music_panel_field
+            .get_mut("values")
+            .and_then(|v| v.as_array_mut())
+        {
+            let key = Value::from("Key");
+            if !values.contains(&key) {
+                values.push(key);
+            }
+            let length = values.len();
+            if let Some(index) = music_panel_field.get_mut("index") {
+                index["length"] = Value::from(length);
+            }
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Save;
+    use common::Paths;
+    use serde_json::{from_str, from_value, to_value};
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use std::sync::Once;
+
+    /// The save file fixtures:
+    ///
+    /// - `0.2.4.cac` was written by Cacophony 0.2.4. The save file format didn't change between 0.1.3 and 0.2.7, so this is the last format before schema versions.
+    /// - `0.1.2.cac` is the same music in the pre-0.1.3 format: There is no version string, the exporter can't export .flac files, and the selected export type is MP3.
+    /// - `newer.cac` has a schema version that doesn't exist yet.
+    const SAVES_DIRECTORY: &str = "../test_files/saves";
+    /// Fixtures in the save file formats of released versions of Cacophony.
+    const RELEASE_FIXTURES: [&str; 2] = ["0.1.2.cac", "0.2.4.cac"];
+    static INIT_PATHS: Once = Once::new();
+
+    #[test]
+    fn migrations_schema_version() {
+        assert_eq!(get_schema_version(&get_fixture("0.1.2.cac").1), 0);
+        assert_eq!(get_schema_version(&get_fixture("0.2.4.cac").1), 1);
+        assert_eq!(get_schema_version(&get_fixture("newer.cac").1), 999);
+    }
+
+    #[test]
+    fn migrations_add_flac() {
+        let (path, value) = get_fixture("0.1.2.cac");
+        let value = migrate(value, &path).unwrap();
+        assert_eq!(value[SCHEMA_VERSION_KEY], SCHEMA_VERSION);
+        assert_eq!(value[VERSION_KEY], "0.1.2");
+        let export_type = &value["exporter"]["export_type"];
+        assert_eq!(
+            export_type["values"],
+            to_value(["Wav", "Mid", "MP3", "Ogg", "Flac"]).unwrap()
+        );
+        // The selected export type didn't change.
+        assert_eq!(export_type["index"]["index"], 2);
+        assert_eq!(export_type["index"]["length"], 5);
+    }
+
+    #[test]
+    fn migrations_add_key_field() {
+        for filename in RELEASE_FIXTURES {
+            let (path, value) = get_fixture(filename);
+            let value = migrate(value, &path).unwrap();
+            let music_panel_field = &value["state"]["music_panel_field"];
+            assert_eq!(
+                music_panel_field["values"],
+                to_value(["Name", "BPM", "Gain", "Key"]).unwrap()
+            );
+            assert_eq!(music_panel_field["index"]["length"], 4);
+        }
+    }
+
+    #[test]
+    fn migrations_round_trip() {
+        for filename in RELEASE_FIXTURES {
+            let (path, value) = get_fixture(filename);
+            let migrated = migrate(value, &path).unwrap();
+            let save: Save = from_value(migrated.clone()).unwrap();
+            // Every value in the migrated save file survived deserialization.
+            let value = to_value(&save).unwrap();
+            assert_contains(&migrated, &value, filename);
+            assert_eq!(value[SCHEMA_VERSION_KEY], SCHEMA_VERSION);
+            // Migrate and deserialize again. Nothing should change.
+            let save: Save = from_value(migrate(value.clone(), &path).unwrap()).unwrap();
+            assert_eq!(to_value(&save).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn migrations_same_music() {
+        let saves = RELEASE_FIXTURES.map(|filename| {
+            let (path, value) = get_fixture(filename);
+            let save: Save = from_value(migrate(value, &path).unwrap()).unwrap();
+            to_value(&save).unwrap()
+        });
+        for key in ["state", "synth_state", "paths_state"] {
+            assert_eq!(saves[0][key], saves[1][key]);
+        }
+        assert_eq!(
+            saves[0]["exporter"]["export_type"]["values"],
+            saves[1]["exporter"]["export_type"]["values"]
+        );
+        assert_eq!(
+            saves[0]["exporter"]["metadata"],
+            saves[1]["exporter"]["metadata"]
+        );
+        assert_eq!(saves[0][VERSION_KEY], "0.1.2");
+        assert_eq!(saves[1][VERSION_KEY], "0.2.4");
+        let midi_track = &saves[0]["state"]["music"]["midi_tracks"][0];
+        assert_eq!(midi_track["channel"], 0);
+        assert_eq!(
+            midi_track["notes"][2],
+            to_value([64, 127, 384, 576]).unwrap()
+        );
+        assert_eq!(
+            saves[0]["synth_state"]["programs"]["0"]["preset_name"],
+            "Piano 1"
+        );
+        assert_eq!(saves[0]["paths_state"]["saves"]["filename"], "uh");
+    }
+
+    #[test]
+    fn migrations_newer() {
+        let (path, value) = get_fixture("newer.cac");
+        match migrate(value, &path) {
+            Err(SaveError::Newer { version, .. }) => assert_eq!(version, "9.9.9"),
+            _ => panic!("A save file from a newer version of Cacophony was migrated."),
+        }
+        assert!(matches!(
+            migrate(Value::from(1), &path),
+            Err(SaveError::Invalid { .. })
+        ));
+    }
+
+    /// Asserts that every value in `expected` is in `actual`. `actual` can have additional keys.
+    fn assert_contains(expected: &Value, actual: &Value, path: &str) {
+        match (expected, actual) {
+            (Value::Object(expected), Value::Object(actual)) => {
+                for (key, value) in expected.iter() {
+                    let path = format!("{}/{}", path, key);
+                    match actual.get(key) {
+                        Some(actual) => assert_contains(value, actual, &path),
+                        None => panic!("Missing {}", path),
+                    }
+                }
+            }
+            (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+                for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                    assert_contains(expected, actual, &format!("{}/{}", path, i));
+                }
+            }
+            _ => assert_eq!(expected, actual, "{}", path),
+        }
+    }
+
+    fn get_fixture(filename: &str) -> (PathBuf, Value) {
+        // Deserializing a save file without a tunings directory requires the paths.
+        INIT_PATHS.call_once(|| Paths::init(&PathBuf::from("../data")));
+        let path = PathBuf::from(SAVES_DIRECTORY).join(filename);
+        let value = from_str(&read_to_string(&path).unwrap()).unwrap();
+        (path, value)
+    }
+}
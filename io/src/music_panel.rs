@@ -1,15 +1,70 @@
 use crate::abc123::{on_disable_exporter, on_disable_state, update_exporter, update_state};
 use crate::panel::*;
+use common::config::parse_list_or;
 use common::music_panel_field::*;
-use common::{U64orF32, DEFAULT_BPM, MAX_VOLUME};
+use common::{ScaleType, U64orF32, DEFAULT_BPM, MAX_VOLUME, SCALE_TYPES};
+use ini::Ini;
+
+/// The default pitch classes of the custom scale: A major scale.
+const DEFAULT_CUSTOM_SCALE: [usize; 7] = [0, 2, 4, 5, 7, 9, 11];
 
 /// Set global music values.
-#[derive(Default)]
 pub(crate) struct MusicPanel {
     tooltips: Tooltips,
+    /// The pitch classes of the custom scale relative to the root, as defined in the config file.
+    custom_scale: [bool; 12],
 }
 
 impl MusicPanel {
+    pub fn new(config: &Ini) -> Self {
+        let section = config.section(Some("PIANO_ROLL")).unwrap();
+        let mut pitch_classes =
+            parse_list_or(section, "custom_scale", DEFAULT_CUSTOM_SCALE.to_vec());
+        // Pitch classes must be between 0 and 11.
+        if pitch_classes.is_empty() || pitch_classes.iter().any(|p| *p >= 12) {
+            println!(
+                "WARNING! Invalid custom_scale {:?}: Expected a list of pitch classes between 0 and 11. Using the default value.",
+                pitch_classes
+            );
+            pitch_classes = DEFAULT_CUSTOM_SCALE.to_vec();
+        }
+        let mut custom_scale = [false; 12];
+        pitch_classes.iter().for_each(|p| custom_scale[*p] = true);
+        Self {
+            tooltips: Tooltips::default(),
+            custom_scale,
+        }
+    }
+
+    /// Set the root note or the scale type of the key. Returns a new undo state.
+    fn set_key(&self, state: &mut State, input: &Input) -> Option<Snapshot> {
+        let mut key = state.music.key;
+        if input.happened(&InputEvent::PreviousKeyRoot) || input.happened(&InputEvent::NextKeyRoot)
+        {
+            let mut index = Index::new(key.root, 12);
+            index.increment(input.happened(&InputEvent::NextKeyRoot));
+            key.root = index.get();
+        } else if input.happened(&InputEvent::PreviousScaleType)
+            || input.happened(&InputEvent::NextScaleType)
+        {
+            let mut index = Index::new(
+                SCALE_TYPES
+                    .iter()
+                    .position(|s| *s == key.scale_type)
+                    .unwrap(),
+                SCALE_TYPES.len(),
+            );
+            index.increment(input.happened(&InputEvent::NextScaleType));
+            key.scale_type = SCALE_TYPES[index.get()];
+            if key.scale_type == ScaleType::Custom {
+                key.custom = self.custom_scale;
+            }
+        } else {
+            return None;
+        }
+        Some(Snapshot::from_state_value(|s| &mut s.music.key, key, state))
+    }
+
     /// Increment the current gain. Returns a new undo state.
     fn set_gain(conn: &mut Conn, up: bool) -> Option<Snapshot> {
         // Get undo commands.
@@ -54,8 +109,9 @@ impl Panel for MusicPanel {
                 "MUSIC_PANEL_STATUS_TTS",
                 &[
                     &conn.exporter.metadata.title,
-                    &state.time.bpm.to_string(),
+                    &state.time.get_bpm(state.time.cursor).to_string(),
                     &conn.state.gain.to_string(),
+                    &text.get_key(&state.music.key),
                 ],
             ));
             None
@@ -101,6 +157,22 @@ impl Panel for MusicPanel {
                         scroll,
                     ]
                 }
+                MusicPanelField::Key => {
+                    vec![
+                        self.tooltips.get_tooltip(
+                            "MUSIC_PANEL_INPUT_TTS_KEY",
+                            &[
+                                InputEvent::PreviousKeyRoot,
+                                InputEvent::NextKeyRoot,
+                                InputEvent::PreviousScaleType,
+                                InputEvent::NextScaleType,
+                            ],
+                            input,
+                            text,
+                        ),
+                        scroll,
+                    ]
+                }
                 MusicPanelField::Name => {
                     let key = if state.input.alphanumeric_input {
                         "MUSIC_PANEL_INPUT_TTS_NAME_ABC123"
@@ -136,6 +208,8 @@ impl Panel for MusicPanel {
                         None
                     }
                 }
+                // Set the key.
+                MusicPanelField::Key => self.set_key(state, input),
                 // Modify the name.
                 MusicPanelField::Name => None,
             }
@@ -150,11 +224,19 @@ impl Panel for MusicPanel {
     ) -> (Option<Snapshot>, bool) {
         match state.music_panel_field.get_ref() {
             MusicPanelField::BPM => {
-                let snapshot = update_state(|s| &mut s.time.bpm, state, input);
+                // Set the tempo at the cursor.
+                let snapshot = update_state(
+                    |s| {
+                        let cursor = s.time.cursor;
+                        s.time.get_bpm_mut(cursor)
+                    },
+                    state,
+                    input,
+                );
                 let updated = snapshot.is_some();
                 (snapshot, updated)
             }
-            MusicPanelField::Gain => (None, false),
+            MusicPanelField::Gain | MusicPanelField::Key => (None, false),
             MusicPanelField::Name => (
                 None,
                 update_exporter(|e| &mut e.metadata.title, input, &mut conn.exporter),
@@ -164,10 +246,15 @@ impl Panel for MusicPanel {
 
     fn on_disable_abc123(&mut self, state: &mut State, conn: &mut Conn) {
         match state.music_panel_field.get_ref() {
-            MusicPanelField::BPM => {
-                on_disable_state(|s| &mut s.time.bpm, state, U64orF32::from(DEFAULT_BPM))
-            }
-            MusicPanelField::Gain => (),
+            MusicPanelField::BPM => on_disable_state(
+                |s| {
+                    let cursor = s.time.cursor;
+                    s.time.get_bpm_mut(cursor)
+                },
+                state,
+                U64orF32::from(DEFAULT_BPM),
+            ),
+            MusicPanelField::Gain | MusicPanelField::Key => (),
             MusicPanelField::Name => on_disable_exporter(
                 |e| &mut e.metadata.title,
                 &mut conn.exporter,
@@ -181,6 +268,7 @@ impl Panel for MusicPanel {
             MusicPanelField::BPM => true,
             MusicPanelField::Gain => false,
             MusicPanelField::Name => true,
+            MusicPanelField::Key => false,
         }
     }
 
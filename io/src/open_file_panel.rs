@@ -55,6 +55,11 @@ impl OpenFilePanel {
         self.enable_as_save(OpenFileType::WriteSave, state, paths_state);
     }
 
+    /// Enable the panel for setting the directory that a bundle will be written to.
+    pub fn write_bundle(&mut self, state: &mut State, paths_state: &mut PathsState) {
+        self.enable_as_save(OpenFileType::WriteBundle, state, paths_state);
+    }
+
     /// Enable a panel for setting the export path.
     pub fn export(&mut self, state: &mut State, paths_state: &mut PathsState, conn: &Conn) {
         let extension = conn.exporter.export_type.get().into();
@@ -73,12 +78,32 @@ impl OpenFilePanel {
         self.enable(OpenFileType::ImportMidi, state, paths_state);
     }
 
+    /// Enable a panel for loading a Scala scale.
+    pub fn scale(&mut self, state: &mut State, paths_state: &mut PathsState) {
+        paths_state
+            .children
+            .set(&paths_state.tunings.directory.path, &Extension::Scl, None);
+        self.enable(OpenFileType::Scale, state, paths_state);
+    }
+
+    /// Enable a panel for loading a Scala keyboard mapping.
+    pub fn keyboard_mapping(&mut self, state: &mut State, paths_state: &mut PathsState) {
+        paths_state
+            .children
+            .set(&paths_state.tunings.directory.path, &Extension::Kbm, None);
+        self.enable(OpenFileType::KeyboardMapping, state, paths_state);
+    }
+
     fn get_extension(&self, paths_state: &PathsState, exporter: &Exporter) -> Extension {
         match paths_state.open_file_type {
             OpenFileType::Export => exporter.export_type.get().into(),
-            OpenFileType::ReadSave | OpenFileType::WriteSave => Extension::Cac,
+            OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
+                Extension::Cac
+            }
             OpenFileType::SoundFont => Extension::Sf2,
             OpenFileType::ImportMidi => Extension::Mid,
+            OpenFileType::Scale => Extension::Scl,
+            OpenFileType::KeyboardMapping => Extension::Kbm,
         }
     }
 
@@ -111,7 +136,10 @@ impl Panel for OpenFilePanel {
         paths_state: &mut PathsState,
     ) -> Option<Snapshot> {
         match &paths_state.open_file_type {
-            OpenFileType::SoundFont | OpenFileType::ReadSave => (),
+            OpenFileType::SoundFont
+            | OpenFileType::ReadSave
+            | OpenFileType::Scale
+            | OpenFileType::KeyboardMapping => (),
             _ => {
                 // Get a modifiable filename.
                 let mut filename = match &paths_state.get_filename() {
@@ -209,7 +237,12 @@ impl Panel for OpenFilePanel {
                             OpenFileType::Export => "OPEN_FILE_PANEL_INPUT_TTS_EXPORT",
                             OpenFileType::SoundFont => "OPEN_FILE_PANEL_INPUT_TTS_SOUNDFONT",
                             OpenFileType::WriteSave => "OPEN_FILE_PANEL_INPUT_TTS_WRITE_SAVE",
+                            OpenFileType::WriteBundle => "OPEN_FILE_PANEL_INPUT_TTS_WRITE_BUNDLE",
                             OpenFileType::ImportMidi => "OPEN_FILE_PANEL_INPUT_TTS_IMPORT_MIDI",
+                            OpenFileType::Scale => "OPEN_FILE_PANEL_INPUT_TTS_SCALE",
+                            OpenFileType::KeyboardMapping => {
+                                "OPEN_FILE_PANEL_INPUT_TTS_KEYBOARD_MAPPING"
+                            }
                         };
                         tts_strings.push(self.tooltips.get_tooltip_with_values(
                             open_file_key,
@@ -274,16 +307,31 @@ impl Panel for OpenFilePanel {
                 // Load a save file.
                 OpenFileType::ReadSave => {
                     if let Some(selected) = paths_state.children.selected {
+                        // Get the path. If this is a bundle, get the path to the save file in the bundle.
+                        let child = &paths_state.children.children[selected];
+                        let path = if child.is_file {
+                            child.path.clone()
+                        } else {
+                            Save::get_bundle_save_path(&child.path)
+                        };
+                        if !path.exists() {
+                            return None;
+                        }
                         // Disable the panel.
                         self.disable(state);
                         // Stop the music.
                         conn.on_new_file(state);
-                        // Get the path.
-                        let path = paths_state.children.children[selected].path.clone();
                         // Read the save file.
-                        Save::read(&path, state, conn, paths_state);
-                        // Set the saves directory.
-                        paths_state.saves = FileAndDirectory::new_path(path);
+                        match Save::read(&path, state, conn, paths_state) {
+                            Ok(errors) => {
+                                // Set the saves directory.
+                                paths_state.saves = FileAndDirectory::new_path(path);
+                                if !errors.is_empty() {
+                                    return Some(Snapshot::from_errors(errors));
+                                }
+                            }
+                            Err(error) => return Some(Snapshot::from_save_error(error)),
+                        }
                     }
                 }
                 // Load a SoundFont.
@@ -293,13 +341,34 @@ impl Panel for OpenFilePanel {
                         self.disable(state);
                         if paths_state.children.children[selected].is_file {
                             // Get the selected track's channel.
-                            let channel = state.music.get_selected_track().unwrap().channel;
-                            // To revert: unset the program.
-                            let c0 = vec![Command::UnsetProgram { channel }];
+                            let track = state.music.get_selected_track().unwrap();
+                            let channel = track.channel;
+                            // To revert: restore the program, the missing program, or unset the program.
+                            let c0 = match (
+                                conn.state.programs.get(&channel),
+                                conn.state.missing_programs.get(&channel),
+                            ) {
+                                (Some(program), _) => vec![Command::SetProgram {
+                                    channel,
+                                    path: program.path.clone(),
+                                    bank_index: program.bank_index,
+                                    preset_index: program.preset_index,
+                                }],
+                                (None, Some(path)) => vec![
+                                    Command::UnsetProgram { channel },
+                                    Command::LoadSoundFont {
+                                        channel,
+                                        path: path.clone(),
+                                        percussion: track.percussion,
+                                    },
+                                ],
+                                (None, None) => vec![Command::UnsetProgram { channel }],
+                            };
                             // A command to load the SoundFont.
                             let c1 = vec![Command::LoadSoundFont {
                                 channel,
                                 path: paths_state.children.children[selected].path.clone(),
+                                percussion: track.percussion,
                             }];
                             return Some(Snapshot::from_commands(c0, c1, conn));
                         }
@@ -324,6 +393,21 @@ impl Panel for OpenFilePanel {
                         );
                     }
                 }
+                // Write a bundle.
+                OpenFileType::WriteBundle => {
+                    // There is a filename.
+                    if let Some(filename) = &paths_state.saves.filename {
+                        // Disable the panel.
+                        self.disable(state);
+                        // Write.
+                        Save::write_bundle(
+                            &paths_state.saves.directory.path.join(filename),
+                            state,
+                            conn,
+                            paths_state,
+                        );
+                    }
+                }
                 // Write an export file.
                 OpenFileType::Export => {
                     // There is a filename.
@@ -338,12 +422,14 @@ impl Panel for OpenFilePanel {
                         );
                         // Export to a .mid file.
                         if conn.exporter.export_type.get() == ExportType::Mid {
-                            conn.exporter.mid(
+                            if let Err(error) = conn.exporter.mid(
                                 &paths_state.exports.directory.path.join(filename),
                                 &state.music,
                                 &state.time,
                                 &conn.state,
-                            );
+                            ) {
+                                return Some(Snapshot::from_export_error(error));
+                            }
                         }
                         // Export an audio file.
                         else {
@@ -354,9 +440,44 @@ impl Panel for OpenFilePanel {
                 OpenFileType::ImportMidi => {
                     if let Some(selected) = paths_state.children.selected {
                         let path = paths_state.children.children[selected].path.clone();
-                        import(&path, state, conn);
+                        let errors = import(&path, state, conn);
                         state.unsaved_changes = true;
                         self.disable(state);
+                        // Return a snapshot even if there are no errors so that the imported music is autosaved.
+                        return Some(Snapshot::from_errors(errors));
+                    }
+                }
+                // Load a scale or a keyboard mapping.
+                OpenFileType::Scale | OpenFileType::KeyboardMapping => {
+                    if let Some(selected) = paths_state.children.selected {
+                        // Disable the panel.
+                        self.disable(state);
+                        if paths_state.children.children[selected].is_file {
+                            let path = paths_state.children.children[selected].path.clone();
+                            let channel = state.music.get_selected_track().unwrap().channel;
+                            // To revert: restore the current tuning.
+                            let c0 = vec![conn.get_tuning_command(channel)];
+                            // Replace either the scale or the keyboard mapping.
+                            let (scale, keyboard_mapping) = match conn.state.tunings.get(&channel) {
+                                Some(tuning) => (
+                                    tuning.scale_path.clone(),
+                                    tuning.keyboard_mapping_path.clone(),
+                                ),
+                                None => (None, None),
+                            };
+                            let (scale, keyboard_mapping) =
+                                if paths_state.open_file_type == OpenFileType::Scale {
+                                    (Some(path), keyboard_mapping)
+                                } else {
+                                    (scale, Some(path))
+                                };
+                            let c1 = vec![Command::SetTuning {
+                                channel,
+                                scale,
+                                keyboard_mapping,
+                            }];
+                            return Some(Snapshot::from_commands(c0, c1, conn));
+                        }
                     }
                 }
             }
@@ -1,11 +1,17 @@
+mod automation;
 mod edit_mode_deltas;
 mod view;
 use edit_mode_deltas::EditModeDeltas;
+mod humanize;
+mod quantize;
+use humanize::Humanize;
+use quantize::Quantize;
 mod edit;
 mod piano_roll_panel;
 mod piano_roll_sub_panel;
 mod select;
 mod time;
+use self::automation::Automation;
 use self::edit::Edit;
 pub(crate) use piano_roll_panel::PianoRollPanel;
 pub(crate) use piano_roll_sub_panel::PianoRollSubPanel;
@@ -0,0 +1,287 @@
+use super::{
+    get_cycle_edit_mode_input_tts, get_edit_mode_status_tts, EditModeDeltas, PianoRollSubPanel,
+};
+use crate::panel::*;
+use ini::Ini;
+
+/// Edit the selected track's controller and pitch bend automation at the cursor.
+pub(super) struct Automation {
+    /// The edit mode deltas.
+    deltas: EditModeDeltas,
+    tooltips: Tooltips,
+}
+
+impl Automation {
+    pub fn new(config: &Ini) -> Self {
+        Self {
+            deltas: EditModeDeltas::new(config),
+            tooltips: Tooltips::default(),
+        }
+    }
+
+    /// Move the cursor.
+    fn set_cursor(&self, state: &mut State, add: bool) -> Option<Snapshot> {
+        let dt = self
+            .deltas
+            .get_dt(state.automation_edit_mode.get_ref(), &state.input);
+        let cursor = if add {
+            state.time.cursor + dt
+        } else {
+            state.time.cursor.saturating_sub(dt)
+        };
+        Some(Snapshot::from_state_value(
+            |s| &mut s.time.cursor,
+            cursor,
+            state,
+        ))
+    }
+
+    /// Move the cursor to the previous or next automation point.
+    fn set_cursor_to_point(state: &mut State, next: bool) -> Option<Snapshot> {
+        let cursor = state.time.cursor;
+        let automation = state
+            .music
+            .get_selected_track()?
+            .get_automation(&state.automation_type)?;
+        let time = if next {
+            automation.get_next_time(cursor)
+        } else {
+            automation.get_previous_time(cursor)
+        }?;
+        Some(Snapshot::from_state_value(
+            |s| &mut s.time.cursor,
+            time,
+            state,
+        ))
+    }
+
+    /// Raise or lower the value at the cursor. If there isn't a point at the cursor, this adds one.
+    fn set_value(&self, state: &mut State, up: bool) -> Option<Snapshot> {
+        let s0 = state.clone();
+        let automation_type = state.automation_type;
+        let dv = self.deltas.get_dv(state.automation_edit_mode.get_ref()) as u16
+            * automation_type.get_step();
+        let cursor = state.time.cursor;
+        let automation = state
+            .music
+            .get_selected_track_mut()?
+            .get_automation_mut(&automation_type);
+        let value = automation.get_value(cursor);
+        let value = if up {
+            value.saturating_add(dv)
+        } else {
+            value.saturating_sub(dv)
+        };
+        automation.set(cursor, value);
+        Some(Snapshot::from_states(s0, state))
+    }
+
+    /// Add a point at the cursor with the current value at the cursor.
+    fn add_point(state: &mut State) -> Option<Snapshot> {
+        let s0 = state.clone();
+        let automation_type = state.automation_type;
+        let cursor = state.time.cursor;
+        let automation = state
+            .music
+            .get_selected_track_mut()?
+            .get_automation_mut(&automation_type);
+        if automation.points.iter().any(|p| p.time == cursor) {
+            None
+        } else {
+            automation.set(cursor, automation.get_value(cursor));
+            Some(Snapshot::from_states(s0, state))
+        }
+    }
+
+    /// Remove the point at the cursor.
+    fn remove_point(state: &mut State) -> Option<Snapshot> {
+        let s0 = state.clone();
+        let automation_type = state.automation_type;
+        let cursor = state.time.cursor;
+        let track = state.music.get_selected_track_mut()?;
+        let removed = track.get_automation_mut(&automation_type).remove(cursor);
+        // Remove empty lanes.
+        track.automation.retain(|a| !a.points.is_empty());
+        if removed {
+            Some(Snapshot::from_states(s0, state))
+        } else {
+            None
+        }
+    }
+}
+
+impl Panel for Automation {
+    fn update(
+        &mut self,
+        state: &mut State,
+        _: &mut Conn,
+        input: &Input,
+        _: &mut TTS,
+        _: &Text,
+        _: &mut PathsState,
+    ) -> Option<Snapshot> {
+        // Do nothing if there is no track.
+        if state.music.selected.is_none() {
+            None
+        }
+        // Cycle the mode.
+        else if input.happened(&InputEvent::PianoRollCycleMode) {
+            Some(Snapshot::from_state(
+                |s| s.automation_edit_mode.index.increment(true),
+                state,
+            ))
+        }
+        // Set the automation type.
+        else if input.happened(&InputEvent::AutomationPreviousType) {
+            Some(Snapshot::from_state_value(
+                |s| &mut s.automation_type,
+                state.automation_type.cycle(false),
+                state,
+            ))
+        } else if input.happened(&InputEvent::AutomationNextType) {
+            Some(Snapshot::from_state_value(
+                |s| &mut s.automation_type,
+                state.automation_type.cycle(true),
+                state,
+            ))
+        }
+        // Move the cursor.
+        else if input.happened(&InputEvent::AutomationCursorLeft) {
+            self.set_cursor(state, false)
+        } else if input.happened(&InputEvent::AutomationCursorRight) {
+            self.set_cursor(state, true)
+        } else if input.happened(&InputEvent::AutomationPreviousPoint) {
+            Automation::set_cursor_to_point(state, false)
+        } else if input.happened(&InputEvent::AutomationNextPoint) {
+            Automation::set_cursor_to_point(state, true)
+        }
+        // Set the value.
+        else if input.happened(&InputEvent::AutomationValueUp) {
+            self.set_value(state, true)
+        } else if input.happened(&InputEvent::AutomationValueDown) {
+            self.set_value(state, false)
+        }
+        // Add or remove a point.
+        else if input.happened(&InputEvent::AutomationAddPoint) {
+            Automation::add_point(state)
+        } else if input.happened(&InputEvent::AutomationRemovePoint) {
+            Automation::remove_point(state)
+        } else {
+            None
+        }
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        true
+    }
+}
+
+impl PianoRollSubPanel for Automation {
+    fn get_status_tts(&mut self, state: &State, _: &Conn, text: &Text) -> Vec<TtsString> {
+        let mut s = vec![get_edit_mode_status_tts(
+            state.automation_edit_mode.get_ref(),
+            text,
+        )];
+        let automation_type = text.get_automation_type(&state.automation_type);
+        let cursor = state.time.cursor;
+        let (value, num_points, point) = match state
+            .music
+            .get_selected_track()
+            .and_then(|t| t.get_automation(&state.automation_type))
+        {
+            Some(automation) => (
+                automation.get_value(cursor),
+                automation.points.len(),
+                automation.points.iter().any(|p| p.time == cursor),
+            ),
+            None => (state.automation_type.get_default(), 0, false),
+        };
+        s.push(TtsString::from(text.get_with_values(
+            "PIANO_ROLL_PANEL_STATUS_TTS_AUTOMATION",
+            &[
+                &text.get_bar_beat_tick_tts(cursor, &state.time),
+                &automation_type,
+                &value.to_string(),
+                &num_points.to_string(),
+            ],
+        )));
+        if point {
+            s.push(TtsString::from(text.get_with_values(
+                "PIANO_ROLL_PANEL_STATUS_TTS_AUTOMATION_POINT",
+                &[&automation_type],
+            )));
+        }
+        s
+    }
+
+    fn get_input_tts(&mut self, state: &State, input: &Input, text: &Text) -> Vec<TtsString> {
+        vec![
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_AUTOMATION_0",
+                &[
+                    InputEvent::AutomationCursorLeft,
+                    InputEvent::AutomationCursorRight,
+                ],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_AUTOMATION_1",
+                &[
+                    InputEvent::AutomationPreviousPoint,
+                    InputEvent::AutomationNextPoint,
+                ],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_AUTOMATION_2",
+                &[
+                    InputEvent::AutomationValueUp,
+                    InputEvent::AutomationValueDown,
+                ],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_AUTOMATION_3",
+                &[
+                    InputEvent::AutomationAddPoint,
+                    InputEvent::AutomationRemovePoint,
+                ],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_AUTOMATION_4",
+                &[
+                    InputEvent::AutomationPreviousType,
+                    InputEvent::AutomationNextType,
+                ],
+                input,
+                text,
+            ),
+            get_cycle_edit_mode_input_tts(
+                &mut self.tooltips,
+                &state.automation_edit_mode,
+                input,
+                text,
+            ),
+        ]
+    }
+}
@@ -1,15 +1,33 @@
 use super::{
     get_cycle_edit_mode_input_tts, get_edit_mode_status_tts, get_no_selection_status_tts,
-    EditModeDeltas, PianoRollSubPanel,
+    EditModeDeltas, Humanize, PianoRollSubPanel, Quantize,
 };
 use crate::panel::*;
-use common::{MAX_NOTE, MAX_VOLUME, MIN_NOTE};
+use common::{EditMode, Key, MAX_NOTE, MAX_VOLUME, MIN_NOTE};
 use ini::Ini;
 
+/// Input events that edit the selected notes.
+const EDIT_EVENTS: [InputEvent; 10] = [
+    InputEvent::EditStartLeft,
+    InputEvent::EditStartRight,
+    InputEvent::EditDurationLeft,
+    InputEvent::EditDurationRight,
+    InputEvent::EditPitchUp,
+    InputEvent::EditPitchDown,
+    InputEvent::EditVolumeUp,
+    InputEvent::EditVolumeDown,
+    InputEvent::EditQuantize,
+    InputEvent::EditHumanize,
+];
+
 /// Edit selected notes.
 pub(super) struct Edit {
     /// The edit mode deltas.
     deltas: EditModeDeltas,
+    /// Snap notes to the input beat.
+    quantize: Quantize,
+    /// Randomly offset the timing and volume of notes.
+    humanize: Humanize,
     tooltips: Tooltips,
 }
 
@@ -17,9 +35,145 @@ impl Edit {
     pub fn new(config: &Ini) -> Self {
         Self {
             deltas: EditModeDeltas::new(config),
+            quantize: Quantize::new(config),
+            humanize: Humanize::new(config),
             tooltips: Tooltips::default(),
         }
     }
+
+    /// Edit the selected notes. Returns a snapshot if any notes changed.
+    ///
+    /// Mutable notes are copied if they're shared with an undo snapshot, so this only gets them after checking that the notes can be edited.
+    fn edit(
+        &self,
+        event: InputEvent,
+        state: &mut State,
+        tts: &mut TTS,
+        text: &Text,
+    ) -> Option<Snapshot> {
+        let mode = state.edit_mode.get_ref();
+        let dt = self.deltas.get_dt(mode, &state.input);
+        let dn = self.deltas.get_dn(mode);
+        let dv = self.deltas.get_dv(mode);
+        // In normal mode, move notes by scale degree. Percussion tracks don't have a key.
+        let key = Some(state.music.key).filter(|key| {
+            *mode == EditMode::Normal
+                && !key.is_chromatic()
+                && !state
+                    .music
+                    .get_selected_track()
+                    .is_some_and(|t| t.percussion)
+        });
+        // Are there notes we can edit?
+        let can_edit = match state.select_mode.get_notes(&state.music) {
+            Some(notes) => match event {
+                // Don't let any notes go to t=0.
+                InputEvent::EditStartLeft => notes.iter().all(|n| n.start >= dt),
+                // Don't let any notes go to dt<=0.
+                InputEvent::EditDurationLeft => notes.iter().all(|n| n.get_duration() >= dt),
+                // Don't let any notes go out of range.
+                InputEvent::EditPitchUp => notes
+                    .iter()
+                    .all(|n| Self::get_pitch(n.note, key, dn, true).is_some()),
+                InputEvent::EditPitchDown => notes
+                    .iter()
+                    .all(|n| Self::get_pitch(n.note, key, dn, false).is_some()),
+                // Don't let any notes go to dv>=max.
+                InputEvent::EditVolumeUp => notes.iter().all(|n| n.velocity + dv <= MAX_VOLUME),
+                // Don't let any notes go to dv<=0.
+                InputEvent::EditVolumeDown => notes.iter().all(|n| n.velocity >= dv),
+                _ => true,
+            },
+            None => false,
+        };
+        if !can_edit {
+            return None;
+        }
+        let s0 = state.clone();
+        let mut notes = state.select_mode.get_notes_mut(&mut state.music)?;
+        let edited = match event {
+            // Move the notes left.
+            InputEvent::EditStartLeft => {
+                notes.iter_mut().for_each(|n| n.set_t0_by(dt, false));
+                true
+            }
+            // Move the notes right.
+            InputEvent::EditStartRight => {
+                notes.iter_mut().for_each(|n| n.set_t0_by(dt, true));
+                true
+            }
+            // Shorten the duration.
+            InputEvent::EditDurationLeft => {
+                notes.iter_mut().for_each(|n| n.end -= dt);
+                true
+            }
+            // Lengthen the notes.
+            InputEvent::EditDurationRight => {
+                notes.iter_mut().for_each(|n| n.end += dt);
+                true
+            }
+            // Move the notes up or down.
+            InputEvent::EditPitchUp | InputEvent::EditPitchDown => {
+                let up = event == InputEvent::EditPitchUp;
+                for n in notes.iter_mut() {
+                    if let Some(pitch) = Self::get_pitch(n.note, key, dn, up) {
+                        n.note = pitch;
+                    }
+                }
+                true
+            }
+            // Increase the volume.
+            InputEvent::EditVolumeUp => {
+                notes.iter_mut().for_each(|n| n.velocity += dv);
+                true
+            }
+            // Decrease the volume.
+            InputEvent::EditVolumeDown => {
+                notes.iter_mut().for_each(|n| n.velocity -= dv);
+                true
+            }
+            // Snap the notes to the input beat.
+            InputEvent::EditQuantize => {
+                let num_notes = notes.len();
+                let moved = self.quantize.quantize(&mut notes, state.input.beat.get_u());
+                tts.enqueue(TtsString::from(text.get_with_values(
+                    "PIANO_ROLL_PANEL_TTS_QUANTIZE",
+                    &[&moved.to_string(), &num_notes.to_string()],
+                )));
+                moved > 0
+            }
+            // Randomly offset the timing and volume of the notes.
+            InputEvent::EditHumanize => {
+                let num_notes = notes.len();
+                let changed = self.humanize.humanize(&mut notes);
+                tts.enqueue(TtsString::from(text.get_with_values(
+                    "PIANO_ROLL_PANEL_TTS_HUMANIZE",
+                    &[&changed.to_string(), &num_notes.to_string()],
+                )));
+                changed > 0
+            }
+            _ => false,
+        };
+        if edited {
+            Some(Snapshot::from_states(s0, state))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the pitch of a note moved up or down, or None if the note would go out of range.
+    ///
+    /// - `note` The pitch of the note.
+    /// - `key` If not None, move by scale degrees. Otherwise, move by half-steps.
+    /// - `dn` The number of scale degrees or half-steps.
+    /// - `up` If true, move up.
+    fn get_pitch(note: u8, key: Option<Key>, dn: u8, up: bool) -> Option<u8> {
+        match key {
+            Some(key) => key.transpose(note, dn, up),
+            None if up => note.checked_add(dn).filter(|n| *n <= MAX_NOTE),
+            None => note.checked_sub(dn).filter(|n| *n >= MIN_NOTE),
+        }
+    }
 }
 
 impl Panel for Edit {
@@ -28,8 +182,8 @@ impl Panel for Edit {
         state: &mut State,
         _: &mut Conn,
         input: &Input,
-        _: &mut TTS,
-        _: &Text,
+        tts: &mut TTS,
+        text: &Text,
         _: &mut PathsState,
     ) -> Option<Snapshot> {
         // Do nothing if there is no track.
@@ -43,97 +197,11 @@ impl Panel for Edit {
                 state,
             ))
         } else {
-            let mode = state.edit_mode.get_ref();
-            let s0 = state.clone();
-            // Are there notes we can edit?
-            match state.select_mode.get_notes_mut(&mut state.music) {
-                Some(mut notes) => {
-                    // Move the notes left.
-                    if input.happened(&InputEvent::EditStartLeft) {
-                        let dt = self.deltas.get_dt(mode, &state.input);
-                        // Don't let any notes go to t=0.
-                        if !notes.iter().any(|n| n.start.checked_sub(dt).is_none()) {
-                            notes.iter_mut().for_each(|n| n.set_t0_by(dt, false));
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    }
-                    // Move the notes right.
-                    else if input.happened(&InputEvent::EditStartRight) {
-                        let dt = self.deltas.get_dt(mode, &state.input);
-                        notes.iter_mut().for_each(|n| n.set_t0_by(dt, true));
-                        Some(Snapshot::from_states(s0, state))
-                    }
-                    // Shorten the duration.
-                    else if input.happened(&InputEvent::EditDurationLeft) {
-                        let dt = self.deltas.get_dt(mode, &state.input);
-                        // Don't let any notes go to dt<=0.
-                        if notes
-                            .iter()
-                            .all(|n| n.get_duration().checked_sub(dt).is_some())
-                        {
-                            notes.iter_mut().for_each(|n| n.end -= dt);
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    }
-                    // Lengthen the notes.
-                    else if input.happened(&InputEvent::EditDurationRight) {
-                        let dt = self.deltas.get_dt(mode, &state.input);
-                        notes.iter_mut().for_each(|n| n.end += dt);
-                        Some(Snapshot::from_states(s0, state))
-                    }
-                    // Move the notes up.
-                    else if input.happened(&InputEvent::EditPitchUp) {
-                        let dn = self.deltas.get_dn(mode);
-                        // Don't let any notes go to dn>=max.
-                        if notes.iter().all(|n| (n.note + dn) <= MAX_NOTE) {
-                            notes.iter_mut().for_each(|n| n.note += dn);
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    }
-                    // Move the notes down.
-                    else if input.happened(&InputEvent::EditPitchDown) {
-                        let dn = self.deltas.get_dn(mode);
-                        // Don't let any notes go to dn<=0.
-                        if notes.iter().all(|n| (n.note - dn) >= MIN_NOTE) {
-                            notes.iter_mut().for_each(|n| n.note -= dn);
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    }
-                    // Increase the volume.
-                    else if input.happened(&InputEvent::EditVolumeUp) {
-                        let dv = self.deltas.get_dv(mode);
-                        // Don't let any notes go to dv>=max.
-                        if notes.iter().all(|n| (n.velocity + dv) <= MAX_VOLUME) {
-                            notes.iter_mut().for_each(|n| n.velocity += dv);
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    }
-                    // Decrease the volume.
-                    else if input.happened(&InputEvent::EditVolumeDown) {
-                        let dv = self.deltas.get_dv(mode);
-                        // Don't let any notes go to dv<=0.
-                        if notes.iter().all(|n| (n.velocity as i8 - dv as i8) >= 0) {
-                            notes.iter_mut().for_each(|n| n.velocity -= dv);
-                            Some(Snapshot::from_states(s0, state))
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                }
-                None => None,
-            }
+            // Only edit the notes if an edit event happened.
+            EDIT_EVENTS
+                .iter()
+                .find(|e| input.happened(e))
+                .and_then(|e| self.edit(*e, state, tts, text))
         }
     }
 
@@ -158,7 +226,7 @@ impl Panel for Edit {
 }
 
 impl PianoRollSubPanel for Edit {
-    fn get_status_tts(&mut self, state: &State, text: &Text) -> Vec<TtsString> {
+    fn get_status_tts(&mut self, state: &State, _: &Conn, text: &Text) -> Vec<TtsString> {
         vec![get_edit_mode_status_tts(state.edit_mode.get_ref(), text)]
     }
 
@@ -189,6 +257,18 @@ impl PianoRollSubPanel for Edit {
                     input,
                     text,
                 ),
+                self.tooltips.get_tooltip(
+                    "PIANO_ROLL_PANEL_INPUT_TTS_EDIT_4",
+                    &[InputEvent::EditQuantize],
+                    input,
+                    text,
+                ),
+                self.tooltips.get_tooltip(
+                    "PIANO_ROLL_PANEL_INPUT_TTS_EDIT_5",
+                    &[InputEvent::EditHumanize],
+                    input,
+                    text,
+                ),
             ],
             None => vec![get_no_selection_status_tts(text)],
         };
@@ -0,0 +1,141 @@
+use common::config::parse_or;
+use common::{Note, MAX_VOLUME};
+use ini::Ini;
+
+/// Randomly offset the timing and volume of notes.
+pub(super) struct Humanize {
+    /// Move each note's start time by up to this many PPQ earlier or later.
+    start: u64,
+    /// Change each note's duration by up to this percentage.
+    duration: u64,
+    /// Change each note's volume by up to this percentage.
+    volume: u64,
+    /// The random seed. Humanizing the same notes with the same seed always gives the same result.
+    seed: u64,
+}
+
+impl Humanize {
+    pub(super) fn new(config: &Ini) -> Self {
+        let section = config.section(Some("PIANO_ROLL")).unwrap();
+        let start = parse_or(section, "humanize_start", 8);
+        let duration = parse_or::<u64>(section, "humanize_duration", 10).min(100);
+        let volume = parse_or::<u64>(section, "humanize_volume", 10).min(100);
+        let seed = parse_or(section, "humanize_seed", 0);
+        Self {
+            start,
+            duration,
+            volume,
+            seed,
+        }
+    }
+
+    /// Randomly offset the start time, duration, and volume of each note. Returns the number of notes that changed.
+    pub(super) fn humanize(&self, notes: &mut [&mut Note]) -> usize {
+        let mut changed = 0;
+        for note in notes.iter_mut() {
+            // Seed each note with its pitch and time so that the result doesn't depend on which other notes are selected.
+            let mut rng = self.seed ^ note.start.rotate_left(16) ^ note.note as u64;
+            let start = (note.start as i64 + get_offset(&mut rng, self.start)).max(0) as u64;
+            let duration = note.get_duration();
+            let duration = (duration as i64 + get_offset(&mut rng, duration * self.duration / 100))
+                .max(1) as u64;
+            let velocity = (note.velocity as i64
+                + get_offset(&mut rng, note.velocity as u64 * self.volume / 100))
+            .clamp(1, MAX_VOLUME as i64) as u8;
+            let end = start + duration;
+            if start != note.start || end != note.end || velocity != note.velocity {
+                note.start = start;
+                note.end = end;
+                note.velocity = velocity;
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+/// Returns a random offset between `-max` and `max`.
+fn get_offset(rng: &mut u64, max: u64) -> i64 {
+    if max == 0 {
+        0
+    } else {
+        (next(rng) % (max * 2 + 1)) as i64 - max as i64
+    }
+}
+
+/// Returns the next pseudo-random number. This is SplitMix64, which is random enough for humanizing notes.
+fn next(rng: &mut u64) -> u64 {
+    *rng = rng.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *rng;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Humanize;
+    use common::{get_test_config, Note, PPQ_U};
+
+    #[test]
+    fn humanize_config() {
+        let h = Humanize::new(&get_test_config());
+        assert_eq!(h.start, 8, "{}", h.start);
+        assert_eq!(h.duration, 10, "{}", h.duration);
+        assert_eq!(h.volume, 10, "{}", h.volume);
+        assert_eq!(h.seed, 0, "{}", h.seed);
+    }
+
+    #[test]
+    fn humanize_notes() {
+        let h = Humanize {
+            start: 8,
+            duration: 10,
+            volume: 10,
+            seed: 0,
+        };
+        let n0 = get_notes();
+        let mut n1 = n0.clone();
+        let changed = h.humanize(&mut n1.iter_mut().collect::<Vec<&mut Note>>());
+        assert!(changed > 0);
+        for (a, b) in n0.iter().zip(n1.iter()) {
+            assert!(a.start.abs_diff(b.start) <= 8, "{:?} {:?}", a, b);
+            assert!(a.get_duration().abs_diff(b.get_duration()) <= PPQ_U / 10);
+            assert!(a.velocity.abs_diff(b.velocity) <= 10);
+            assert_eq!(a.note, b.note);
+        }
+        // The same seed gives the same result.
+        let mut n2 = n0.clone();
+        h.humanize(&mut n2.iter_mut().collect::<Vec<&mut Note>>());
+        assert!(n1 == n2);
+        // A different seed gives a different result.
+        let h = Humanize { seed: 1, ..h };
+        let mut n3 = n0.clone();
+        h.humanize(&mut n3.iter_mut().collect::<Vec<&mut Note>>());
+        assert!(n1 != n3);
+        // Nothing changes if the offsets are 0.
+        let h = Humanize {
+            start: 0,
+            duration: 0,
+            volume: 0,
+            seed: 0,
+        };
+        let mut n4 = n0.clone();
+        assert_eq!(
+            h.humanize(&mut n4.iter_mut().collect::<Vec<&mut Note>>()),
+            0
+        );
+        assert!(n0 == n4);
+    }
+
+    fn get_notes() -> Vec<Note> {
+        (0..16)
+            .map(|i| Note {
+                note: 60 + i as u8,
+                velocity: 100,
+                start: i * PPQ_U,
+                end: (i + 1) * PPQ_U,
+            })
+            .collect()
+    }
+}
@@ -2,7 +2,7 @@ use super::*;
 use crate::panel::*;
 use crate::select_track;
 use common::config::parse_fractions;
-use common::{Index, Note, PianoRollMode, SelectMode, U64orF32, PPQ_F};
+use common::{CountIn, Index, Note, PianoRollMode, SelectMode, U64orF32, PPQ_F};
 use ini::Ini;
 
 const TRACK_SCROLL_EVENTS: [InputEvent; 2] = [
@@ -13,6 +13,8 @@ const TRACK_SCROLL_EVENTS: [InputEvent; 2] = [
 /// The piano roll.
 /// This is divided into different "modes" for convenience, where each mode is actually a panel.
 pub struct PianoRollPanel {
+    /// The automation mode.
+    automation: Automation,
     /// The edit mode.
     edit: Edit,
     /// The select mode.
@@ -29,10 +31,13 @@ pub struct PianoRollPanel {
     copied_notes: Vec<Note>,
     /// The tooltips handler.
     tooltips: Tooltips,
+    /// Count-in and pre-roll settings for real-time recording.
+    pub(crate) count_in: CountIn,
 }
 
 impl PianoRollPanel {
     pub fn new(beat: &u64, config: &Ini) -> Self {
+        let automation = Automation::new(config);
         let edit = Edit::new(config);
         let select = Select::default();
         let time = Time::new(config);
@@ -53,6 +58,7 @@ impl PianoRollPanel {
         };
         let beat = Index::new(beat_index, beats.len());
         Self {
+            automation,
             edit,
             select,
             time,
@@ -61,6 +67,7 @@ impl PianoRollPanel {
             beat,
             copied_notes: vec![],
             tooltips: Tooltips::default(),
+            count_in: CountIn::new(config),
         }
     }
 
@@ -91,6 +98,7 @@ impl PianoRollPanel {
     /// Returns the sub-panel corresponding to the current piano roll mode.
     fn get_sub_panel<'a>(&'a mut self, state: &State) -> &'a mut dyn PianoRollSubPanel {
         match state.piano_roll_mode {
+            PianoRollMode::Automation => &mut self.automation,
             PianoRollMode::Edit => &mut self.edit,
             PianoRollMode::Select => &mut self.select,
             PianoRollMode::Time => &mut self.time,
@@ -159,6 +167,8 @@ impl Panel for PianoRollPanel {
             let track = state.music.get_selected_track_mut().unwrap();
             match conn.state.programs.get(&track.channel) {
                 Some(_) => {
+                    // Percussion notes have a fixed duration.
+                    let duration = track.get_new_note_duration(state.input.beat.get_u());
                     // Get the notes.
                     let notes: Vec<Note> = input
                         .new_notes
@@ -167,7 +177,7 @@ impl Panel for PianoRollPanel {
                             note: n[1],
                             velocity: n[2],
                             start: state.time.cursor,
-                            end: state.time.cursor + state.input.beat.get_u(),
+                            end: state.time.cursor + duration,
                         })
                         .collect();
                     // Add the notes.
@@ -207,12 +217,83 @@ impl Panel for PianoRollPanel {
                                     "PIANO_ROLL_PANEL_STATUS_TTS_ARMED",
                                     &[&beat, &volume],
                                 )));
+                                // Real-time recording.
+                                tts_strings.push(TtsString::from(text.get_ref(
+                                    if state.input.record {
+                                        "PIANO_ROLL_PANEL_STATUS_TTS_RECORD"
+                                    } else {
+                                        "PIANO_ROLL_PANEL_STATUS_TTS_NO_RECORD"
+                                    },
+                                )));
+                                if state.input.record {
+                                    // Count-in.
+                                    if self.count_in.count_in > 0 {
+                                        tts_strings.push(TtsString::from(text.get_with_values(
+                                            if self.count_in.count_in_bars {
+                                                "PIANO_ROLL_PANEL_STATUS_TTS_COUNT_IN_BARS"
+                                            } else {
+                                                "PIANO_ROLL_PANEL_STATUS_TTS_COUNT_IN_BEATS"
+                                            },
+                                            &[&self.count_in.count_in.to_string()],
+                                        )));
+                                    }
+                                    // Pre-roll.
+                                    if self.count_in.pre_roll > 0 {
+                                        tts_strings.push(TtsString::from(text.get_with_values(
+                                            "PIANO_ROLL_PANEL_STATUS_TTS_PRE_ROLL",
+                                            &[&self.count_in.pre_roll.to_string()],
+                                        )));
+                                    }
+                                }
+                                // Snap to key.
+                                tts_strings.push(TtsString::from(if state.input.snap_to_key {
+                                    text.get_with_values(
+                                        "PIANO_ROLL_PANEL_STATUS_TTS_SNAP_TO_KEY",
+                                        &[&text.get_key(&state.music.key)],
+                                    )
+                                } else {
+                                    text.get("PIANO_ROLL_PANEL_STATUS_TTS_NO_SNAP_TO_KEY")
+                                }));
                             }
                             // Not armed.
                             false => tts_strings.push(TtsString::from(
                                 text.get_ref("PIANO_ROLL_PANEL_STATUS_TTS_NOT_ARMED"),
                             )),
                         }
+                        // Loop.
+                        tts_strings.push(TtsString::from(match state.get_loop_range() {
+                            Some(range) => text.get_with_values(
+                                "PIANO_ROLL_PANEL_STATUS_TTS_LOOP",
+                                &[
+                                    &text.get_bar_beat_tick_tts(range[0], &state.time),
+                                    &text.get_bar_beat_tick_tts(range[1], &state.time),
+                                ],
+                            ),
+                            None => text.get("PIANO_ROLL_PANEL_STATUS_TTS_NO_LOOP"),
+                        }));
+                        // Metronome.
+                        let metronome = conn.metronome.lock();
+                        if metronome.enabled {
+                            tts_strings.push(TtsString::from(text.get_with_values(
+                                "PIANO_ROLL_PANEL_STATUS_TTS_METRONOME",
+                                &[&metronome.gain.get().to_string()],
+                            )));
+                            if metronome.accent {
+                                tts_strings.push(TtsString::from(
+                                    text.get_ref("PIANO_ROLL_PANEL_STATUS_TTS_METRONOME_ACCENT"),
+                                ));
+                            }
+                            if metronome.export {
+                                tts_strings.push(TtsString::from(
+                                    text.get_ref("PIANO_ROLL_PANEL_STATUS_TTS_METRONOME_EXPORT"),
+                                ));
+                            }
+                        } else {
+                            tts_strings.push(TtsString::from(
+                                text.get_ref("PIANO_ROLL_PANEL_STATUS_TTS_NO_METRONOME"),
+                            ));
+                        }
+                        drop(metronome);
                         // How many tracks?
                         let tracks_key = if state.view.single_track {
                             "PIANO_ROLL_PANEL_STATUS_TTS_SINGLE_TRACK"
@@ -224,8 +305,9 @@ impl Panel for PianoRollPanel {
                             &[&state.music.selected.unwrap().to_string()],
                         )));
                         // Panel-specific status.
-                        tts_strings
-                            .append(&mut self.get_sub_panel(state).get_status_tts(state, text));
+                        tts_strings.append(
+                            &mut self.get_sub_panel(state).get_status_tts(state, conn, text),
+                        );
                     }
                     None => tts_strings.append(&mut PianoRollPanel::tts_no_track(text)),
                 },
@@ -240,12 +322,32 @@ impl Panel for PianoRollPanel {
                 Some(track) => match conn.state.programs.get(&track.channel) {
                     // Here we go...
                     Some(_) => {
-                        let mut tts_strings = vec![self.tooltips.get_tooltip(
-                            "PIANO_ROLL_PANEL_INPUT_TTS_PLAY",
-                            &[InputEvent::PlayStop],
-                            input,
-                            text,
-                        )];
+                        let mut tts_strings = vec![
+                            self.tooltips.get_tooltip(
+                                "PIANO_ROLL_PANEL_INPUT_TTS_PLAY",
+                                &[InputEvent::PlayStop],
+                                input,
+                                text,
+                            ),
+                            self.tooltips.get_tooltip(
+                                "PIANO_ROLL_PANEL_INPUT_TTS_LOOP",
+                                &[InputEvent::ToggleLoop],
+                                input,
+                                text,
+                            ),
+                            self.tooltips.get_tooltip(
+                                "PIANO_ROLL_PANEL_INPUT_TTS_METRONOME",
+                                &[
+                                    InputEvent::ToggleMetronome,
+                                    InputEvent::IncreaseMetronomeGain,
+                                    InputEvent::DecreaseMetronomeGain,
+                                    InputEvent::ToggleMetronomeAccent,
+                                    InputEvent::ToggleMetronomeExport,
+                                ],
+                                input,
+                                text,
+                            ),
+                        ];
                         // Armed state, beat, volume.
                         match state.input.armed {
                             true => {
@@ -259,6 +361,18 @@ impl Panel for PianoRollPanel {
                                     input,
                                     text,
                                 ));
+                                tts_strings.push(self.tooltips.get_tooltip(
+                                    "PIANO_ROLL_PANEL_INPUT_TTS_RECORD",
+                                    &[InputEvent::ToggleRecord],
+                                    input,
+                                    text,
+                                ));
+                                tts_strings.push(self.tooltips.get_tooltip(
+                                    "PIANO_ROLL_PANEL_INPUT_TTS_SNAP_TO_KEY",
+                                    &[InputEvent::ToggleSnapToKey],
+                                    input,
+                                    text,
+                                ));
                                 match state.input.use_volume {
                                     true => tts_strings.push(self.tooltips.get_tooltip(
                                         "PIANO_ROLL_PANEL_INPUT_TTS_DO_NOT_USE_VOLUME",
@@ -339,6 +453,7 @@ impl Panel for PianoRollPanel {
                                 InputEvent::PianoRollSetView,
                                 InputEvent::PianoRollSetSelect,
                                 InputEvent::PianoRollSetEdit,
+                                InputEvent::PianoRollSetAutomation,
                             ],
                             input,
                             text,
@@ -437,6 +552,56 @@ impl Panel for PianoRollPanel {
             state.input.armed = !state.input.armed;
             Some(Snapshot::from_states(s0, state))
         }
+        // Toggle real-time recording.
+        else if input.happened(&InputEvent::ToggleRecord) {
+            Some(Snapshot::from_state_value(
+                |s| &mut s.input.record,
+                !state.input.record,
+                state,
+            ))
+        }
+        // Toggle snapping input to the key.
+        else if input.happened(&InputEvent::ToggleSnapToKey) {
+            Some(Snapshot::from_state_value(
+                |s| &mut s.input.snap_to_key,
+                !state.input.snap_to_key,
+                state,
+            ))
+        }
+        // Toggle looping.
+        else if input.happened(&InputEvent::ToggleLoop) {
+            Some(Snapshot::from_state_value(
+                |s| &mut s.time.loop_playback,
+                !state.time.loop_playback,
+                state,
+            ))
+        }
+        // Toggle the metronome. The metronome isn't part of the state, so this can't be undone.
+        else if input.happened(&InputEvent::ToggleMetronome) {
+            let mut metronome = conn.metronome.lock();
+            metronome.enabled = !metronome.enabled;
+            None
+        }
+        // Toggle metronome accents.
+        else if input.happened(&InputEvent::ToggleMetronomeAccent) {
+            let mut metronome = conn.metronome.lock();
+            metronome.accent = !metronome.accent;
+            None
+        }
+        // Toggle whether the metronome is exported.
+        else if input.happened(&InputEvent::ToggleMetronomeExport) {
+            let mut metronome = conn.metronome.lock();
+            metronome.export = !metronome.export;
+            None
+        }
+        // Set the metronome gain.
+        else if input.happened(&InputEvent::IncreaseMetronomeGain) {
+            conn.metronome.lock().gain.increment_no_loop(true);
+            None
+        } else if input.happened(&InputEvent::DecreaseMetronomeGain) {
+            conn.metronome.lock().gain.increment_no_loop(false);
+            None
+        }
         // Toggle tracks view.
         else if input.happened(&InputEvent::PianoRollToggleTracks) {
             Some(Snapshot::from_state_value(
@@ -478,10 +643,16 @@ impl Panel for PianoRollPanel {
             PianoRollPanel::set_mode(PianoRollMode::Time, state)
         } else if input.happened(&InputEvent::PianoRollSetView) {
             PianoRollPanel::set_mode(PianoRollMode::View, state)
+        } else if input.happened(&InputEvent::PianoRollSetAutomation) {
+            PianoRollPanel::set_mode(PianoRollMode::Automation, state)
         } else {
             // Sub-panel actions.
             let mode = state.piano_roll_mode;
             match mode {
+                PianoRollMode::Automation => {
+                    self.automation
+                        .update(state, conn, input, tts, text, paths_state)
+                }
                 PianoRollMode::Edit => self.edit.update(state, conn, input, tts, text, paths_state),
                 PianoRollMode::Select => {
                     self.select
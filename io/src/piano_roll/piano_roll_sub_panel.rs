@@ -4,7 +4,7 @@ use common::{EditMode, IndexedEditModes};
 /// A sub-panel (a mode) of the piano roll panel.
 pub(crate) trait PianoRollSubPanel {
     /// Returns the status text-to-speech text.
-    fn get_status_tts(&mut self, state: &State, text: &Text) -> Vec<TtsString>;
+    fn get_status_tts(&mut self, state: &State, conn: &Conn, text: &Text) -> Vec<TtsString>;
 
     /// Returns the input text-to-speech text.
     fn get_input_tts(&mut self, state: &State, input: &Input, text: &Text) -> Vec<TtsString>;
@@ -0,0 +1,139 @@
+use common::config::{parse_bool_or, parse_or};
+use common::Note;
+use ini::Ini;
+
+/// Snap notes to a grid.
+pub(super) struct Quantize {
+    /// Move each note this percentage of the distance to the nearest grid line.
+    strength: u64,
+    /// Delay every other grid line by this percentage of half of a grid step.
+    swing: u64,
+    /// If true, snap the end times too. If false, preserve the durations.
+    ends: bool,
+}
+
+impl Quantize {
+    pub(super) fn new(config: &Ini) -> Self {
+        let section = config.section(Some("PIANO_ROLL")).unwrap();
+        let strength = parse_or::<u64>(section, "quantize_strength", 100).min(100);
+        let swing = parse_or::<u64>(section, "quantize_swing", 0).min(100);
+        let ends = parse_bool_or(section, "quantize_ends", false);
+        Self {
+            strength,
+            swing,
+            ends,
+        }
+    }
+
+    /// Snap notes to a grid. Returns the number of notes that moved.
+    ///
+    /// - `notes` The notes.
+    /// - `grid` The grid step in PPQ.
+    pub(super) fn quantize(&self, notes: &mut [&mut Note], grid: u64) -> usize {
+        if grid == 0 {
+            return 0;
+        }
+        let mut moved = 0;
+        for note in notes.iter_mut() {
+            let start = self.get_time(note.start, grid);
+            let end = if self.ends {
+                let end = self.get_time(note.end, grid);
+                // If the note would have no duration, preserve its duration instead.
+                if end > start {
+                    end
+                } else {
+                    start + note.get_duration()
+                }
+            } else {
+                start + note.get_duration()
+            };
+            if start != note.start || end != note.end {
+                note.start = start;
+                note.end = end;
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Returns a time moved towards the nearest grid line.
+    fn get_time(&self, t: u64, grid: u64) -> u64 {
+        // Swing delays every other grid line.
+        let swing = grid * self.swing / 200;
+        let pair = grid * 2;
+        let t0 = t / pair * pair;
+        let target = [t0, t0 + grid + swing, t0 + pair]
+            .into_iter()
+            .min_by_key(|g| g.abs_diff(t))
+            .unwrap();
+        if target > t {
+            t + (target - t) * self.strength / 100
+        } else {
+            t - (t - target) * self.strength / 100
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quantize;
+    use common::{get_test_config, Note, PPQ_U};
+
+    #[test]
+    fn quantize_config() {
+        let q = Quantize::new(&get_test_config());
+        assert_eq!(q.strength, 100, "{}", q.strength);
+        assert_eq!(q.swing, 0, "{}", q.swing);
+        assert!(!q.ends);
+    }
+
+    #[test]
+    fn quantize_notes() {
+        let mut q = Quantize {
+            strength: 100,
+            swing: 0,
+            ends: false,
+        };
+        let grid = PPQ_U;
+        // Snap the start and preserve the duration.
+        assert_eq!(quantize(&q, 10, 200, grid), (0, 190));
+        assert_eq!(
+            quantize(&q, PPQ_U - 10, PPQ_U + 50, grid),
+            (PPQ_U, PPQ_U + 60)
+        );
+        // This note is already on the grid.
+        assert_eq!(
+            quantize(&q, PPQ_U * 3, PPQ_U * 4, grid),
+            (PPQ_U * 3, PPQ_U * 4)
+        );
+        // Snap the end too.
+        q.ends = true;
+        assert_eq!(quantize(&q, 10, PPQ_U + 30, grid), (0, PPQ_U));
+        // Don't let a note have no duration.
+        assert_eq!(quantize(&q, 10, 20, grid), (0, 10));
+        // Move halfway to the grid line.
+        q.ends = false;
+        q.strength = 50;
+        assert_eq!(quantize(&q, 40, 140, grid), (20, 120));
+        // Swing delays the off-beat grid lines.
+        q.strength = 100;
+        q.swing = 50;
+        let off_beat = PPQ_U + PPQ_U / 4;
+        assert_eq!(quantize(&q, PPQ_U + 10, PPQ_U + 20, grid).0, off_beat);
+        assert_eq!(
+            quantize(&q, PPQ_U * 2 + 10, PPQ_U * 2 + 20, grid).0,
+            PPQ_U * 2
+        );
+    }
+
+    fn quantize(q: &Quantize, start: u64, end: u64, grid: u64) -> (u64, u64) {
+        let mut note = Note {
+            note: 60,
+            velocity: 127,
+            start,
+            end,
+        };
+        q.quantize(&mut [&mut note], grid);
+        (note.start, note.end)
+    }
+}
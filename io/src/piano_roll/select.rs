@@ -336,7 +336,7 @@ impl Panel for Select {
 }
 
 impl PianoRollSubPanel for Select {
-    fn get_status_tts(&mut self, state: &State, text: &Text) -> Vec<TtsString> {
+    fn get_status_tts(&mut self, state: &State, _: &Conn, text: &Text) -> Vec<TtsString> {
         let tts_string = match &state.select_mode {
             SelectMode::Single(index) => match index {
                 Some(index) => match state.select_mode.get_notes(&state.music) {
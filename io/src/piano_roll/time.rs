@@ -1,5 +1,6 @@
 use super::{get_edit_mode_status_tts, EditModeDeltas, PianoRollSubPanel};
 use crate::panel::*;
+use common::{MAX_TIME_SIGNATURE_NUMERATOR, TIME_SIGNATURE_DENOMINATORS};
 use ini::Ini;
 
 /// The piano roll time sub-panel.
@@ -54,6 +55,32 @@ impl Time {
         Some(Snapshot::from_states(s0, state))
     }
 
+    /// Increment or decrement the numerator or denominator of the time signature at the cursor.
+    fn set_time_signature(state: &mut State, numerator: bool, up: bool) -> Option<Snapshot> {
+        let s0 = state.clone();
+        let cursor = state.time.cursor;
+        let time_signature = state.time.get_time_signature_mut(cursor);
+        if numerator {
+            time_signature.numerator = if up {
+                u8::min(time_signature.numerator + 1, MAX_TIME_SIGNATURE_NUMERATOR)
+            } else {
+                u8::max(time_signature.numerator - 1, 1)
+            };
+        } else {
+            let index = TIME_SIGNATURE_DENOMINATORS
+                .iter()
+                .position(|d| *d == time_signature.denominator)
+                .unwrap();
+            let index = if up {
+                usize::min(index + 1, TIME_SIGNATURE_DENOMINATORS.len() - 1)
+            } else {
+                index.saturating_sub(1)
+            };
+            time_signature.denominator = TIME_SIGNATURE_DENOMINATORS[index];
+        }
+        Some(Snapshot::from_states(s0, state))
+    }
+
     /// Round a time off to the nearest beat.
     fn get_nearest_beat(t: u64, state: &State) -> u64 {
         ((t as f32 / state.input.beat.get_f()).ceil() * state.input.beat.get_f()) as u64
@@ -136,6 +163,46 @@ impl Panel for Time {
                 Time::get_nearest_beat(state.time.playback, state),
                 state,
             ))
+        }
+        // Add or remove a tempo change.
+        else if input.happened(&InputEvent::TimeAddTempo) {
+            let s0 = state.clone();
+            if state.time.add_tempo(state.time.cursor) {
+                Some(Snapshot::from_states(s0, state))
+            } else {
+                None
+            }
+        } else if input.happened(&InputEvent::TimeRemoveTempo) {
+            let s0 = state.clone();
+            if state.time.remove_tempo(state.time.cursor) {
+                Some(Snapshot::from_states(s0, state))
+            } else {
+                None
+            }
+        }
+        // Add, remove, or set a time signature change.
+        else if input.happened(&InputEvent::TimeAddTimeSignature) {
+            let s0 = state.clone();
+            if state.time.add_time_signature(state.time.cursor) {
+                Some(Snapshot::from_states(s0, state))
+            } else {
+                None
+            }
+        } else if input.happened(&InputEvent::TimeRemoveTimeSignature) {
+            let s0 = state.clone();
+            if state.time.remove_time_signature(state.time.cursor) {
+                Some(Snapshot::from_states(s0, state))
+            } else {
+                None
+            }
+        } else if input.happened(&InputEvent::TimeSignatureNumeratorUp) {
+            Time::set_time_signature(state, true, true)
+        } else if input.happened(&InputEvent::TimeSignatureNumeratorDown) {
+            Time::set_time_signature(state, true, false)
+        } else if input.happened(&InputEvent::TimeSignatureDenominatorUp) {
+            Time::set_time_signature(state, false, true)
+        } else if input.happened(&InputEvent::TimeSignatureDenominatorDown) {
+            Time::set_time_signature(state, false, false)
         } else {
             None
         }
@@ -162,13 +229,17 @@ impl Panel for Time {
 }
 
 impl PianoRollSubPanel for Time {
-    fn get_status_tts(&mut self, state: &State, text: &Text) -> Vec<TtsString> {
+    fn get_status_tts(&mut self, state: &State, _: &Conn, text: &Text) -> Vec<TtsString> {
         let mut s = vec![get_edit_mode_status_tts(state.time.mode.get_ref(), text)];
+        let time_signature = state.time.get_time_signature(state.time.cursor);
         s.push(TtsString::from(text.get_with_values(
             "PIANO_ROLL_PANEL_STATUS_TTS_TIME",
             &[
-                &text.get_ppq_tts(&state.time.cursor),
-                &text.get_ppq_tts(&state.time.playback),
+                &text.get_bar_beat_tick_tts(state.time.cursor, &state.time),
+                &text.get_bar_beat_tick_tts(state.time.playback, &state.time),
+                &state.time.get_bpm(state.time.cursor).to_string(),
+                &time_signature.numerator.to_string(),
+                &time_signature.denominator.to_string(),
             ],
         )));
         s
@@ -224,6 +295,32 @@ impl PianoRollSubPanel for Time {
                 input,
                 text,
             ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_TIME_8",
+                &[InputEvent::TimeAddTempo, InputEvent::TimeRemoveTempo],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_TIME_9",
+                &[
+                    InputEvent::TimeAddTimeSignature,
+                    InputEvent::TimeRemoveTimeSignature,
+                ],
+                input,
+                text,
+            ),
+            self.tooltips.get_tooltip(
+                "PIANO_ROLL_PANEL_INPUT_TTS_TIME_10",
+                &[
+                    InputEvent::TimeSignatureNumeratorDown,
+                    InputEvent::TimeSignatureNumeratorUp,
+                    InputEvent::TimeSignatureDenominatorDown,
+                    InputEvent::TimeSignatureDenominatorUp,
+                ],
+                input,
+                text,
+            ),
         ]
     }
 }
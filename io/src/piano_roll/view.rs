@@ -158,14 +158,28 @@ impl Panel for View {
 }
 
 impl PianoRollSubPanel for View {
-    fn get_status_tts(&mut self, state: &State, text: &Text) -> Vec<TtsString> {
+    fn get_status_tts(&mut self, state: &State, conn: &Conn, text: &Text) -> Vec<TtsString> {
+        // Say the drum names of a percussion track or the scale degrees of a retuned track.
+        let (n0, n1) = match state.music.get_selected_track() {
+            Some(track) => {
+                let tuning = conn.state.tunings.get(&track.channel);
+                (
+                    text.get_pitch_tts(state.view.dn[0], track.percussion, tuning),
+                    text.get_pitch_tts(state.view.dn[1], track.percussion, tuning),
+                )
+            }
+            None => (
+                text.get_note_name(state.view.dn[0]).to_string(),
+                text.get_note_name(state.view.dn[1]).to_string(),
+            ),
+        };
         let mut s = vec![TtsString::from(text.get_with_values(
             "PIANO_ROLL_PANEL_STATUS_TTS_VIEW",
             &[
                 &text.get_ppq_tts(&state.view.dt[0]),
                 &text.get_ppq_tts(&state.view.dt[1]),
-                text.get_note_name(state.view.dn[0]),
-                text.get_note_name(state.view.dn[1]),
+                &n0,
+                &n1,
             ],
         ))];
         s.push(get_edit_mode_status_tts(state.view.mode.get_ref(), text));
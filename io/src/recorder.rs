@@ -0,0 +1,153 @@
+use crate::Snapshot;
+use audio::play_state::PlayState;
+use audio::Conn;
+use common::{AutomationPoint, AutomationType, Note, State, PERCUSSION_NOTE_DURATION};
+use hashbrown::HashMap;
+use input::Input;
+
+/// Records notes in real time while music is playing.
+///
+/// Note-on and note-off events are timestamped against the playback time, as are controller, channel pressure, and pitch bend messages.
+/// When recording stops, the recorded notes and automation are added to the selected track as a single undoable snapshot.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    /// Notes that are held down. Key = The MIDI note. Value = The start time in PPQ and the velocity.
+    held: HashMap<u8, (u64, u8)>,
+    /// The recorded notes.
+    notes: Vec<Note>,
+    /// The recorded automation points.
+    automation: Vec<(AutomationType, AutomationPoint)>,
+    /// The most recent playback time in PPQ.
+    time: u64,
+}
+
+impl Recorder {
+    /// Record new notes. Returns a snapshot if recording stopped on this frame.
+    ///
+    /// - `state` The state of the app.
+    /// - `conn` The audio connection. This is used to get the playback time.
+    /// - `input` The input state. This is used to get the note-on and note-off events.
+    pub(crate) fn update(
+        &mut self,
+        state: &mut State,
+        conn: &Conn,
+        input: &Input,
+    ) -> Option<Snapshot> {
+        let play_state = *conn.play_state.lock();
+        match play_state {
+            PlayState::Playing(samples) if Recorder::can_record(state, conn) => {
+                // Don't record during the count-in.
+                if conn.metronome.lock().is_counting_in() {
+                    return None;
+                }
+                self.time = state.time.samples_to_ppq(samples, conn.framerate);
+                // End held notes.
+                for note in input.note_off_keys.iter() {
+                    if let Some((start, velocity)) = self.held.remove(note) {
+                        self.push(*note, velocity, start, state);
+                    }
+                }
+                // Don't record during the pre-roll.
+                if self.time < state.get_playback_start() {
+                    return None;
+                }
+                // Start new notes.
+                for note_on in input.note_on_messages.iter() {
+                    // The same note was pressed twice without a note-off.
+                    if let Some((start, velocity)) = self.held.remove(&note_on[1]) {
+                        self.push(note_on[1], velocity, start, state);
+                    }
+                    self.held.insert(note_on[1], (self.time, note_on[2]));
+                }
+                // Record automation.
+                for (automation_type, value) in input
+                    .control_messages
+                    .iter()
+                    .filter_map(AutomationType::from_midi_message)
+                {
+                    self.automation.push((
+                        automation_type,
+                        AutomationPoint {
+                            time: self.time,
+                            value,
+                        },
+                    ));
+                }
+                None
+            }
+            _ => self.stop(state),
+        }
+    }
+
+    /// Stop recording. End any held notes and add the recorded notes to the selected track.
+    fn stop(&mut self, state: &mut State) -> Option<Snapshot> {
+        let held: Vec<(u8, (u64, u8))> = self.held.drain().collect();
+        for (note, (start, velocity)) in held {
+            self.push(note, velocity, start, state);
+        }
+        if self.notes.is_empty() && self.automation.is_empty() {
+            return None;
+        }
+        let s0 = state.clone();
+        let notes: Vec<Note> = self.notes.drain(..).collect();
+        let automation: Vec<(AutomationType, AutomationPoint)> =
+            self.automation.drain(..).collect();
+        match state.music.get_selected_track_mut() {
+            Some(track) => {
+                track.notes.extend(notes);
+                // If there are multiple points at the same time, the most recent point is used.
+                for (automation_type, point) in automation {
+                    track
+                        .get_automation_mut(&automation_type)
+                        .set(point.time, point.value);
+                }
+                Some(Snapshot::from_states(s0, state))
+            }
+            None => None,
+        }
+    }
+
+    /// Add a recorded note that starts at `start` and ends now.
+    /// If the selected track is a percussion track, the note has a fixed duration instead.
+    fn push(&mut self, note: u8, velocity: u8, start: u64, state: &State) {
+        if let Some(track) = state.music.get_selected_track() {
+            if track.percussion {
+                self.notes.push(Note {
+                    note,
+                    velocity,
+                    start,
+                    end: start + PERCUSSION_NOTE_DURATION,
+                });
+                return;
+            }
+        }
+        let end = if self.time < start {
+            // Playback looped while the note was held, so the note ends at the end of the loop.
+            match state.get_loop_range() {
+                Some(range) => range[1],
+                None => return,
+            }
+        } else {
+            self.time
+        };
+        // Ignore notes that didn't last long enough to have a duration.
+        if end > start {
+            self.notes.push(Note {
+                note,
+                velocity,
+                start,
+                end,
+            });
+        }
+    }
+
+    /// Returns true if the selected track is armed for real-time recording and has a program.
+    fn can_record(state: &State, conn: &Conn) -> bool {
+        state.input.armed
+            && state.input.record
+            && match state.music.get_selected_track() {
+                Some(track) => conn.state.programs.contains_key(&track.channel),
+                None => false,
+            }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::panel::*;
+use crate::Save;
+use common::{PanelType, Paths};
+
+/// Do you want to recover the music that wasn't saved when the app last quit?
+#[derive(Default)]
+pub(crate) struct RecoverPanel {
+    popup: Popup,
+    tooltips: Tooltips,
+}
+
+impl RecoverPanel {
+    pub fn enable(&mut self, state: &mut State) {
+        self.popup.enable(state, vec![PanelType::Recover]);
+    }
+}
+
+impl Panel for RecoverPanel {
+    fn update(
+        &mut self,
+        state: &mut State,
+        conn: &mut Conn,
+        input: &Input,
+        tts: &mut TTS,
+        text: &Text,
+        paths_state: &mut PathsState,
+    ) -> Option<Snapshot> {
+        if input.happened(&InputEvent::RecoverPanelYes) {
+            // Stop the music.
+            conn.on_new_file(state);
+            // Read the recovery file. This replaces the state, including the active panels.
+            match Save::read(&Paths::get().recovery_path, state, conn, paths_state) {
+                Ok(errors) => {
+                    // The recovered music hasn't been saved.
+                    state.unsaved_changes = true;
+                    // Return a snapshot even if there are no errors so that the recovered music is autosaved.
+                    return Some(Snapshot::from_errors(errors));
+                }
+                Err(error) => {
+                    self.popup.disable(state);
+                    return Some(Snapshot::from_save_error(error));
+                }
+            }
+        } else if input.happened(&InputEvent::InputTTS) {
+            tts.enqueue(self.tooltips.get_tooltip(
+                "RECOVER_PANEL_INPUT_TTS",
+                &[InputEvent::RecoverPanelYes, InputEvent::RecoverPanelNo],
+                input,
+                text,
+            ));
+        } else if input.happened(&InputEvent::RecoverPanelNo) {
+            Save::delete_recovery();
+            self.popup.disable(state);
+        }
+        None
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        false
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+}
@@ -0,0 +1,213 @@
+use crate::panel::*;
+use common::open_file::OpenFileType;
+use common::{find_soundfont, get_soundfont_search_paths, PanelType, Paths, Relocation};
+use ini::Ini;
+use std::path::PathBuf;
+use text::get_file_name;
+
+/// Find SoundFonts that were missing when a save file was loaded.
+pub(crate) struct RelocatePanel {
+    /// User-defined directories that will be searched for missing SoundFonts.
+    search_paths: Vec<PathBuf>,
+    /// The popup.
+    popup: Popup,
+    /// The tooltips handler.
+    tooltips: Tooltips,
+}
+
+impl RelocatePanel {
+    pub fn new(config: &Ini) -> Self {
+        Self {
+            search_paths: get_soundfont_search_paths(config),
+            popup: Popup::default(),
+            tooltips: Tooltips::default(),
+        }
+    }
+
+    /// Search for each missing SoundFont and enable the panel.
+    pub fn enable(
+        &mut self,
+        state: &mut State,
+        conn: &Conn,
+        tts: &mut TTS,
+        text: &Text,
+        paths_state: &PathsState,
+    ) {
+        let mut directories = vec![
+            paths_state.saves.directory.path.clone(),
+            Paths::get().soundfonts_directory.clone(),
+            paths_state.soundfonts.directory.path.clone(),
+        ];
+        directories.extend(self.search_paths.iter().cloned());
+        directories.dedup();
+        for relocation in state.relocations.iter_mut() {
+            relocation.candidate = find_soundfont(
+                &relocation.path,
+                conn.state.hashes.get(&relocation.path).copied(),
+                &directories,
+            );
+        }
+        self.popup.enable(state, vec![PanelType::Relocate]);
+        tts.stop();
+        Self::enqueue_status_tts(&state.relocations[0], tts, text);
+    }
+
+    /// Say which SoundFont is missing and whether a replacement was found.
+    fn enqueue_status_tts(relocation: &Relocation, tts: &mut TTS, text: &Text) {
+        let channel = relocation.channel.to_string();
+        let path = get_file_name(&relocation.path);
+        let s = match &relocation.candidate {
+            Some(candidate) => text.get_with_values(
+                "RELOCATE_PANEL_STATUS_TTS_FOUND",
+                &[&channel, path, &candidate.display().to_string()],
+            ),
+            None => text.get_with_values("RELOCATE_PANEL_STATUS_TTS_NOT_FOUND", &[&channel, path]),
+        };
+        tts.enqueue(TtsString::from(s));
+    }
+
+    /// Say whether future saves will use relative paths.
+    fn enqueue_relative_paths_tts(paths_state: &PathsState, tts: &mut TTS, text: &Text) {
+        let key = if paths_state.relative_soundfont_paths {
+            "RELOCATE_PANEL_STATUS_TTS_RELATIVE_PATHS_TRUE"
+        } else {
+            "RELOCATE_PANEL_STATUS_TTS_RELATIVE_PATHS_FALSE"
+        };
+        tts.enqueue(TtsString::from(text.get_ref(key)));
+    }
+
+    /// Stop relocating the current SoundFont. If there are no more missing SoundFonts, disable the panel.
+    fn next(&self, state: &mut State) {
+        state.relocations.remove(0);
+        if state.relocations.is_empty() {
+            self.popup.disable(state);
+        }
+    }
+}
+
+impl Panel for RelocatePanel {
+    fn update(
+        &mut self,
+        state: &mut State,
+        conn: &mut Conn,
+        input: &Input,
+        tts: &mut TTS,
+        text: &Text,
+        paths_state: &mut PathsState,
+    ) -> Option<Snapshot> {
+        // Tracks that were given a SoundFont via the open-file panel don't need to be relocated.
+        state
+            .relocations
+            .retain(|r| !conn.state.programs.contains_key(&r.channel));
+        if state.relocations.is_empty() {
+            self.popup.disable(state);
+            return None;
+        }
+        let relocation = state.relocations[0].clone();
+        if input.happened(&InputEvent::StatusTTS) {
+            Self::enqueue_status_tts(&relocation, tts, text);
+            Self::enqueue_relative_paths_tts(paths_state, tts, text);
+        } else if input.happened(&InputEvent::InputTTS) {
+            if relocation.candidate.is_some() {
+                tts.enqueue(self.tooltips.get_tooltip(
+                    "RELOCATE_PANEL_INPUT_TTS_USE",
+                    &[InputEvent::RelocateSoundFont],
+                    input,
+                    text,
+                ));
+            }
+            tts.enqueue(self.tooltips.get_tooltip(
+                "RELOCATE_PANEL_INPUT_TTS",
+                &[
+                    InputEvent::BrowseSoundFont,
+                    InputEvent::SkipSoundFont,
+                    InputEvent::ToggleRelativeSoundFontPaths,
+                ],
+                input,
+                text,
+            ));
+        }
+        // Use the replacement.
+        else if input.happened(&InputEvent::RelocateSoundFont) {
+            if let Some(candidate) = &relocation.candidate {
+                let channel = relocation.channel;
+                let percussion = state
+                    .music
+                    .midi_tracks
+                    .iter()
+                    .any(|t| t.channel == channel && t.percussion);
+                // To revert: the SoundFont is missing again.
+                let c0 = vec![
+                    Command::UnsetProgram { channel },
+                    Command::LoadSoundFont {
+                        channel,
+                        path: relocation.path.clone(),
+                        percussion,
+                    },
+                ];
+                // Load the replacement and try to set the program that the track was using.
+                let c1 = vec![
+                    Command::LoadSoundFont {
+                        channel,
+                        path: candidate.clone(),
+                        percussion,
+                    },
+                    Command::SetProgram {
+                        channel,
+                        path: candidate.clone(),
+                        bank_index: relocation.bank_index,
+                        preset_index: relocation.preset_index,
+                    },
+                ];
+                self.next(state);
+                return Some(Snapshot::from_commands(c0, c1, conn));
+            }
+        }
+        // Browse for a replacement for the track.
+        else if input.happened(&InputEvent::BrowseSoundFont) {
+            if let Some(index) = state
+                .music
+                .midi_tracks
+                .iter()
+                .position(|t| t.channel == relocation.channel)
+            {
+                state.music.selected = Some(index);
+                return Some(Snapshot::from_io_commands(vec![IOCommand::EnableOpenFile(
+                    OpenFileType::SoundFont,
+                )]));
+            }
+        }
+        // Leave the track without a SoundFont.
+        else if input.happened(&InputEvent::SkipSoundFont) {
+            self.next(state);
+        }
+        // Toggle whether future saves use relative paths.
+        else if input.happened(&InputEvent::ToggleRelativeSoundFontPaths) {
+            paths_state.relative_soundfont_paths = !paths_state.relative_soundfont_paths;
+            state.unsaved_changes = true;
+            Self::enqueue_relative_paths_tts(paths_state, tts, text);
+            // Return a snapshot so that the change is autosaved.
+            return Some(Snapshot::default());
+        }
+        None
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        false
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+}
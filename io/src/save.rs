@@ -1,19 +1,23 @@
+use crate::migrations::{migrate, SCHEMA_VERSION};
+use crate::SaveError;
 use audio::exporter::Exporter;
 use audio::*;
-use common::{PathsState, State};
-use regex::Regex;
+use common::{get_relative_path, Paths, PathsState, Relocation, State};
+use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
-use serde_json::{from_str, to_string, Error};
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use serde_json::{from_str, from_value, to_string, Value};
+use std::fs::{copy, create_dir_all, read_to_string, remove_file, rename, write, OpenOptions};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use text::{get_file_name, get_file_name_no_ex};
 
-const READ_ERROR: &str = "Error reading file: ";
 const WRITE_ERROR: &str = "Error writing file: ";
+/// The name of the directory in a bundle that contains copies of the SoundFonts.
+const BUNDLE_SOUNDFONTS_DIRECTORY: &str = "soundfonts";
 
 /// Serializable save data.
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Save {
+pub struct Save {
     /// The app state.
     state: State,
     /// The synthesizer state.
@@ -23,8 +27,9 @@ pub(crate) struct Save {
     /// The exporter state.
     exporter: Exporter,
     /// The version string.
-    #[serde(default = "default_version")]
     version: String,
+    /// The version of the save file format. See `migrations`.
+    schema_version: u64,
 }
 
 impl Save {
@@ -35,14 +40,177 @@ impl Save {
     /// - `conn` The audio connection. Its `SynthState` will be serialized.
     /// - `paths_state` The paths state.
     pub fn write(path: &PathBuf, state: &State, conn: &Conn, paths_state: &PathsState) {
-        // Convert the state to something that can be serialized.
-        let save = Save {
+        let synth_state = match path.parent() {
+            // Write SoundFont paths relative to the save file.
+            Some(directory) if paths_state.relative_soundfont_paths => {
+                Self::get_synth_state(conn, |p| {
+                    get_relative_path(p, directory).unwrap_or_else(|| p.clone())
+                })
+            }
+            _ => Self::get_synth_state(conn, |p| p.clone()),
+        };
+        Self::write_synth_state(path, state, synth_state, conn, paths_state);
+    }
+
+    /// Write this state to a bundle: a directory that contains a save file and copies of each of its SoundFonts.
+    /// The save file has the same name as the directory and its SoundFont paths are relative to the directory.
+    ///
+    /// - `directory` The path to the bundle directory.
+    /// - `state` The app state.
+    /// - `conn` The audio connection. Its `SynthState` will be serialized.
+    /// - `paths_state` The paths state.
+    pub fn write_bundle(directory: &Path, state: &State, conn: &Conn, paths_state: &PathsState) {
+        let soundfonts_directory = directory.join(BUNDLE_SOUNDFONTS_DIRECTORY);
+        if let Err(error) = create_dir_all(&soundfonts_directory) {
+            panic!("{} {}", WRITE_ERROR, error)
+        }
+        // Copy each SoundFont into the bundle. Key: The SoundFont path. Value: The path relative to the bundle.
+        let mut bundled: HashMap<PathBuf, PathBuf> = HashMap::new();
+        for program in conn.state.programs.values() {
+            if bundled.contains_key(&program.path) {
+                continue;
+            }
+            // Don't overwrite a different SoundFont that has the same file name.
+            let stem = get_file_name_no_ex(&program.path);
+            let extension = program
+                .path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default();
+            let mut file_name = get_file_name(&program.path).to_string();
+            let mut index = 0;
+            while bundled
+                .values()
+                .any(|p| p.file_name().and_then(|f| f.to_str()) == Some(&file_name))
+            {
+                index += 1;
+                file_name = format!("{}_{}.{}", stem, index, extension);
+            }
+            // Copy the file. If this is already a bundle, the file might already be here.
+            let bundle_path = soundfonts_directory.join(&file_name);
+            if bundle_path != program.path {
+                if let Err(error) = copy(&program.path, &bundle_path) {
+                    panic!("{} {}", WRITE_ERROR, error)
+                }
+            }
+            bundled.insert(
+                program.path.clone(),
+                PathBuf::from(BUNDLE_SOUNDFONTS_DIRECTORY).join(file_name),
+            );
+        }
+        let synth_state = Self::get_synth_state(conn, |p| bundled.get(p).unwrap_or(p).clone());
+        Self::write_synth_state(
+            &Self::get_bundle_save_path(directory),
+            state,
+            synth_state,
+            conn,
+            paths_state,
+        );
+    }
+
+    /// Returns the path to the save file in a bundle directory.
+    pub fn get_bundle_save_path(directory: &Path) -> PathBuf {
+        directory
+            .join(get_file_name(directory))
+            .with_extension("cac")
+    }
+
+    /// Write this state to the recovery file.
+    /// The state is written to a temporary file which then replaces the recovery file, so that the recovery file is never partially written.
+    /// Unlike `write`, this never panics: if the file can't be written, the user can still save manually.
+    ///
+    /// - `state` The app state.
+    /// - `conn` The audio connection. Its `SynthState` will be serialized.
+    /// - `paths_state` The paths state.
+    pub fn write_recovery(state: &State, conn: &Conn, paths_state: &PathsState) {
+        let save = Self::new(
+            state,
+            Self::get_synth_state(conn, |p| p.clone()),
+            conn,
+            paths_state,
+        );
+        let path = &Paths::get().recovery_path;
+        let temp_path = path.with_extension("tmp");
+        if let Ok(s) = to_string(&save) {
+            if write(&temp_path, s).is_ok() {
+                let _ = rename(&temp_path, path);
+            }
+        }
+    }
+
+    /// Delete the recovery file, if any.
+    pub fn delete_recovery() {
+        let path = &Paths::get().recovery_path;
+        if path.exists() {
+            let _ = remove_file(path);
+        }
+    }
+
+    /// Returns serializable save data.
+    ///
+    /// - `state` The app state.
+    /// - `synth_state` The synth state.
+    /// - `conn` The audio connection. Its `Exporter` will be serialized.
+    /// - `paths_state` The paths state.
+    fn new(state: &State, synth_state: SynthState, conn: &Conn, paths_state: &PathsState) -> Self {
+        Self {
             state: state.clone(),
-            synth_state: conn.state.clone(),
+            synth_state,
             paths_state: paths_state.clone(),
             exporter: conn.exporter.clone(),
             version: common::VERSION.to_string(),
-        };
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
+    /// Returns a copy of the synth state that can be serialized.
+    ///
+    /// - `conn` The audio connection.
+    /// - `f` A function that converts each SoundFont path, e.g. to make it a relative path.
+    fn get_synth_state<F>(conn: &Conn, f: F) -> SynthState
+    where
+        F: Fn(&PathBuf) -> PathBuf,
+    {
+        let mut synth_state = conn.state.clone();
+        // Only remember the hashes of SoundFonts that are used.
+        synth_state.hashes.retain(|p, _| {
+            synth_state
+                .programs
+                .values()
+                .any(|program| &program.path == p)
+                || synth_state.missing_programs.values().any(|m| m == p)
+        });
+        synth_state
+            .programs
+            .values_mut()
+            .for_each(|program| program.path = f(&program.path));
+        synth_state
+            .missing_programs
+            .values_mut()
+            .for_each(|m| *m = f(m));
+        synth_state.hashes = synth_state
+            .hashes
+            .iter()
+            .map(|(p, hash)| (f(p), *hash))
+            .collect();
+        synth_state
+    }
+
+    /// Serialize and write a save file.
+    ///
+    /// - `path` The path we will write to.
+    /// - `state` The app state.
+    /// - `synth_state` The synth state that will be serialized.
+    /// - `conn` The audio connection. Its `Exporter` will be serialized.
+    /// - `paths_state` The paths state.
+    fn write_synth_state(
+        path: &Path,
+        state: &State,
+        synth_state: SynthState,
+        conn: &Conn,
+        paths_state: &PathsState,
+    ) {
+        let save = Self::new(state, synth_state, conn, paths_state);
         // Try to open the file.
         match OpenOptions::new()
             .write(true)
@@ -66,85 +234,162 @@ impl Save {
 
     /// Load a file and deserialize.
     ///
-    /// - `path` The path we read from.
+    /// - `path` The path we read from. This can be a save file or a bundle directory.
     /// - `state` The app state, which will be set to a deserialized version.
     /// - `conn` The audio connection. Its `SynthState` will be set via commands derived from a deserialized version.
     /// - `paths_state` The paths state, which will be set to a deserialized version.
-    pub fn read(path: &Path, state: &mut State, conn: &mut Conn, paths_state: &mut PathsState) {
-        match File::open(path) {
-            Ok(mut file) => {
-                let mut string = String::new();
-                match file.read_to_string(&mut string) {
-                    Ok(_) => {
-                        // Repair the save file if needed.
-                        let string = Self::fix_no_flac(&string);
-                        let q: Result<Save, Error> = from_str(&string);
-                        match q {
-                            Ok(s) => {
-                                // Set the app state.
-                                *state = s.state;
-
-                                // Set the paths.
-                                *paths_state = s.paths_state;
-
-                                // Set the exporter.
-                                conn.exporter = s.exporter;
-
-                                // Set the synthesizer.
-                                // Set the gain.
-                                let mut commands = vec![Command::SetGain {
-                                    gain: s.synth_state.gain,
-                                }];
-                                // Load each SoundFont.
-                                for program in s.synth_state.programs.iter() {
-                                    if !program.1.path.exists() {
-                                        continue;
-                                    }
-                                    let channel = *program.0;
-                                    commands.push(Command::LoadSoundFont {
-                                        channel,
-                                        path: program.1.path.clone(),
-                                    });
-                                }
-                                // Set each program.                            // Load each SoundFont.
-                                for program in s.synth_state.programs.iter() {
-                                    if !program.1.path.exists() {
-                                        continue;
-                                    }
-                                    let channel = *program.0;
-                                    commands.push(Command::SetProgram {
-                                        channel,
-                                        path: program.1.path.clone(),
-                                        bank_index: program.1.bank_index,
-                                        preset_index: program.1.preset_index,
-                                    });
-                                }
-
-                                // Set the synth state.
-                                conn.state = s.synth_state;
-
-                                // Send the commands.
-                                conn.do_commands(&commands);
-                            }
-                            Err(error) => panic!("{} {}", READ_ERROR, error),
-                        }
-                    }
-                    Err(error) => panic!("{} {}", READ_ERROR, error),
+    ///
+    /// Returns a list of SoundFonts and tuning files that couldn't be loaded, or an error if the save file couldn't be read. If there is an error, nothing is set.
+    pub fn read(
+        path: &Path,
+        state: &mut State,
+        conn: &mut Conn,
+        paths_state: &mut PathsState,
+    ) -> Result<Vec<LoadError>, SaveError> {
+        // Read the save file in a bundle.
+        let path = &if path.is_dir() {
+            Self::get_bundle_save_path(path)
+        } else {
+            path.to_path_buf()
+        };
+        let string = read_to_string(path).map_err(|error| SaveError::Read {
+            path: path.clone(),
+            error: error.to_string(),
+        })?;
+        let invalid = |error: serde_json::Error| SaveError::Invalid {
+            path: path.clone(),
+            error: error.to_string(),
+        };
+        // Upgrade the save file to the current format.
+        let value: Value = from_str(&string).map_err(invalid)?;
+        let value = migrate(value, path)?;
+        let mut s: Save = from_value(value).map_err(invalid)?;
+
+        // Resolve SoundFont paths that are relative to the save file.
+        if let Some(directory) = path.parent() {
+            let absolute = |p: &PathBuf| {
+                if p.is_relative() {
+                    directory.join(p)
+                } else {
+                    p.clone()
                 }
+            };
+            s.synth_state
+                .programs
+                .values_mut()
+                .for_each(|program| program.path = absolute(&program.path));
+            s.synth_state
+                .missing_programs
+                .values_mut()
+                .for_each(|m| *m = absolute(m));
+            s.synth_state.hashes = s
+                .synth_state
+                .hashes
+                .iter()
+                .map(|(p, hash)| (absolute(p), *hash))
+                .collect();
+        }
+
+        // Set the app state.
+        *state = s.state;
+
+        // Set the paths.
+        *paths_state = s.paths_state;
+
+        // Set the exporter.
+        conn.exporter = s.exporter;
+
+        // Set the synthesizer.
+        // Set the gain.
+        let mut commands = vec![Command::SetGain {
+            gain: s.synth_state.gain,
+        }];
+        // Load each SoundFont, including SoundFonts that were missing the last time the file was saved.
+        for (channel, path) in s
+            .synth_state
+            .programs
+            .iter()
+            .map(|(channel, program)| (channel, &program.path))
+            .chain(s.synth_state.missing_programs.iter())
+        {
+            let channel = *channel;
+            commands.push(Command::LoadSoundFont {
+                channel,
+                path: path.clone(),
+                percussion: state
+                    .music
+                    .midi_tracks
+                    .iter()
+                    .any(|t| t.channel == channel && t.percussion),
+            });
+        }
+        // Set each program.
+        for program in s.synth_state.programs.iter() {
+            let channel = *program.0;
+            commands.push(Command::SetProgram {
+                channel,
+                path: program.1.path.clone(),
+                bank_index: program.1.bank_index,
+                preset_index: program.1.preset_index,
+            });
+        }
+        // Reset the tunings of channels that are no longer retuned.
+        for channel in conn.state.tunings.keys() {
+            if !s.synth_state.tunings.contains_key(channel) {
+                commands.push(Command::SetTuning {
+                    channel: *channel,
+                    scale: None,
+                    keyboard_mapping: None,
+                });
             }
-            Err(error) => panic!("{} {}", READ_ERROR, error),
         }
-    }
+        // Set each tuning.
+        for (channel, tuning) in s.synth_state.tunings.iter() {
+            commands.push(Command::SetTuning {
+                channel: *channel,
+                scale: tuning.scale_path.clone(),
+                keyboard_mapping: tuning.keyboard_mapping_path.clone(),
+            });
+        }
 
-    /// Fix the export types if this is pre-0.1.3, which didn't have Flac exporting.
-    /// This apparently isn't possible to fix in Exporter via serde.
-    fn fix_no_flac(string: &str) -> String {
-        let re = Regex::new(r#"(("export_type":\{"values":\["Wav","Mid","MP3","Ogg"\],"index":\{"index":)([0-9]),"length":4\}\})"#).unwrap();
-        re.replace(string, r#""export_type":{"values":["Wav","Mid","MP3","Ogg","Flac"],"index":{"index":0,"length":5}}"#).into()
-    }
-}
+        // Remember which program each channel had, in case its SoundFont is missing.
+        let programs: Vec<(u8, usize, usize)> = s
+            .synth_state
+            .programs
+            .iter()
+            .map(|(channel, program)| (*channel, program.bank_index, program.preset_index))
+            .collect();
 
-/// Pre-0.1.3, the version isn't in the save file. This is the default version.
-fn default_version() -> String {
-    "0.1.2".to_string()
+        // Set the synth state.
+        // The programs will be set by the commands; programs that can't be loaded will be missing.
+        conn.state = s.synth_state;
+        conn.state.programs.clear();
+        conn.state.missing_programs.clear();
+
+        // Send the commands.
+        let errors = conn.do_commands(&commands);
+
+        // Missing SoundFonts might be found elsewhere.
+        state.relocations = errors
+            .iter()
+            .filter_map(|error| match error {
+                LoadError::SoundFont(SoundFontError::Missing { channel, path }) => {
+                    let (bank_index, preset_index) = programs
+                        .iter()
+                        .find(|p| p.0 == *channel)
+                        .map(|p| (p.1, p.2))
+                        .unwrap_or_default();
+                    Some(Relocation {
+                        channel: *channel,
+                        path: path.clone(),
+                        candidate: None,
+                        bank_index,
+                        preset_index,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(errors)
+    }
 }
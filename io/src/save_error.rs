@@ -0,0 +1,41 @@
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+/// An error that prevented a save file from being read.
+#[derive(Debug, Clone)]
+pub enum SaveError {
+    /// The file couldn't be opened or read.
+    Read { path: PathBuf, error: String },
+    /// The file isn't a valid save file.
+    Invalid { path: PathBuf, error: String },
+    /// The file was written by a newer version of Cacophony with a save file format that this version doesn't understand.
+    Newer { path: PathBuf, version: String },
+}
+
+impl SaveError {
+    /// Returns the path to the save file.
+    pub fn get_path(&self) -> &Path {
+        match self {
+            Self::Read { path, .. } | Self::Invalid { path, .. } | Self::Newer { path, .. } => path,
+        }
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, error } => {
+                write!(f, "Error reading {}: {}", path.display(), error)
+            }
+            Self::Invalid { path, error } => {
+                write!(f, "{} isn't a valid save file: {}", path.display(), error)
+            }
+            Self::Newer { path, version } => write!(
+                f,
+                "{} was saved by a newer version of Cacophony ({}). Update Cacophony to open it.",
+                path.display(),
+                version
+            ),
+        }
+    }
+}
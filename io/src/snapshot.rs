@@ -1,5 +1,7 @@
-use crate::{IOCommand, IOCommands, State};
-use audio::{CommandsMessage, Conn};
+use crate::undo_label::get_undo_label;
+use crate::{IOCommand, IOCommands, SaveError, State};
+use audio::{CommandsMessage, Conn, LoadError};
+use text::Text;
 
 /// A snapshot of a state delta.
 #[derive(Default)]
@@ -14,6 +16,14 @@ pub(crate) struct Snapshot {
     to_commands: Option<CommandsMessage>,
     /// A list of commands to send to the `IO` state.
     pub(crate) io_commands: IOCommands,
+    /// SoundFonts and tuning files that couldn't be loaded while applying changes.
+    pub(crate) errors: Vec<LoadError>,
+    /// A save file that couldn't be read.
+    pub(crate) save_error: Option<SaveError>,
+    /// A file that couldn't be exported.
+    pub(crate) export_error: Option<String>,
+    /// A human-readable description of the action. This is shown in the undo history popup.
+    pub(crate) label: String,
 }
 
 impl Snapshot {
@@ -66,13 +76,13 @@ impl Snapshot {
         to_commands: CommandsMessage,
         conn: &mut Conn,
     ) -> Self {
-        let snapshot = Self {
+        let errors = conn.do_commands(&to_commands);
+        Self {
             from_commands: Some(from_commands),
-            to_commands: Some(to_commands.clone()),
+            to_commands: Some(to_commands),
+            errors,
             ..Default::default()
-        };
-        conn.do_commands(&to_commands);
-        snapshot
+        }
     }
 
     /// Returns a snapshot of the delta between two states as well as two synth states.
@@ -89,15 +99,18 @@ impl Snapshot {
         to_commands: CommandsMessage,
         conn: &mut Conn,
     ) -> Self {
-        let snapshot = Self {
+        let errors = conn.do_commands(&to_commands);
+        Self {
             from_state: Some(from_state),
             to_state: Some(to_state.clone()),
             from_commands: Some(from_commands),
-            to_commands: Some(to_commands.clone()),
+            to_commands: Some(to_commands),
             io_commands: None,
-        };
-        conn.do_commands(&to_commands);
-        snapshot
+            errors,
+            save_error: None,
+            export_error: None,
+            label: String::new(),
+        }
     }
 
     /// Returns a snapshot that just contains IOCommands.
@@ -110,6 +123,36 @@ impl Snapshot {
         }
     }
 
+    /// Returns a snapshot that just contains load errors. This won't go on the undo stack.
+    ///
+    /// - `errors` SoundFonts and tuning files that couldn't be loaded.
+    pub fn from_errors(errors: Vec<LoadError>) -> Self {
+        Self {
+            errors,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a snapshot that just contains an error that occurred while reading a save file. This won't go on the undo stack.
+    ///
+    /// - `save_error` The error.
+    pub fn from_save_error(save_error: SaveError) -> Self {
+        Self {
+            save_error: Some(save_error),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a snapshot that just contains an error that occurred while exporting. This won't go on the undo stack.
+    ///
+    /// - `export_error` The error.
+    pub fn from_export_error(export_error: String) -> Self {
+        Self {
+            export_error: Some(export_error),
+            ..Default::default()
+        }
+    }
+
     /// Returns a snapshot that flips the from/to of `snapshot`. This is used for undo/redo.
     ///
     /// - The Snapshot. Its `from_state` will become the returned Snapshot's `to_state` and vice-versa. Its `from_commands` will become the returned Snapshot's `to_commands` and vice-versa.
@@ -120,6 +163,20 @@ impl Snapshot {
             from_commands: snapshot.to_commands.clone(),
             to_commands: snapshot.from_commands.clone(),
             io_commands: None,
+            errors: vec![],
+            save_error: None,
+            export_error: None,
+            label: snapshot.label.clone(),
         }
     }
+
+    /// Describe the action. This is called when the snapshot is pushed to the undo stack.
+    pub fn set_label(&mut self, text: &Text) {
+        self.label = get_undo_label(
+            self.from_state.as_ref(),
+            self.to_state.as_ref(),
+            self.to_commands.as_deref(),
+            text,
+        );
+    }
 }
@@ -1,7 +1,7 @@
 use crate::panel::*;
 use crate::select_track;
 use common::open_file::OpenFileType;
-use common::{MidiTrack, Paths, SelectMode, MAX_VOLUME};
+use common::{MidiTrack, Paths, SelectMode, MAX_PAN, MAX_VOLUME};
 use std::path::PathBuf;
 use text::get_file_name_no_ex;
 
@@ -63,6 +63,49 @@ impl TracksPanel {
         }
     }
 
+    /// Add a track and load the default SoundFont. Returns a new undo-redo state.
+    fn add_track(&self, state: &mut State, conn: &mut Conn, percussion: bool) -> Option<Snapshot> {
+        let s0 = state.clone();
+        // Get all channels currently being used.
+        let track_channels: Vec<u8> = state.music.midi_tracks.iter().map(|t| t.channel).collect();
+        // Get all available channels and get the minimum availabe channel.
+        match (0u8..255u8).filter(|c| !track_channels.contains(c)).min() {
+            Some(channel) => {
+                // Deselect.
+                state.select_mode = match &state.select_mode {
+                    SelectMode::Single(_) => SelectMode::Single(None),
+                    SelectMode::Many(_) => SelectMode::Many(None),
+                };
+                // Set the selection.
+                state.music.selected = Some(state.music.midi_tracks.len());
+                // Add a track.
+                state.music.midi_tracks.push(if percussion {
+                    MidiTrack::new_percussion(channel)
+                } else {
+                    MidiTrack::new(channel)
+                });
+                // Set the soundfont to the default.
+                let c0 = vec![Command::UnsetProgram { channel }];
+                let c1 = vec![Command::LoadSoundFont {
+                    channel,
+                    path: self.default_soundfont_path.clone(),
+                    percussion,
+                }];
+                Some(Snapshot::from_states_and_commands(s0, state, c0, c1, conn))
+            }
+            None => None,
+        }
+    }
+
+    /// Returns a command that resets a channel's tuning to 12-tone equal temperament.
+    fn get_reset_tuning_command(channel: u8) -> Command {
+        Command::SetTuning {
+            channel,
+            scale: None,
+            keyboard_mapping: None,
+        }
+    }
+
     /// Increment or decrement the track gain. Returns a new undo-redo state.
     fn set_gain(state: &mut State, up: bool) -> Option<Snapshot> {
         let s0 = state.clone();
@@ -73,6 +116,19 @@ impl TracksPanel {
         track.gain = gain;
         Some(Snapshot::from_states(s0, state))
     }
+
+    /// Increment or decrement the track pan. Returns a new undo-redo state.
+    fn set_pan(state: &mut State, up: bool) -> Option<Snapshot> {
+        let s0 = state.clone();
+        let track = state.music.get_selected_track_mut().unwrap();
+        let mut index = Index::new(track.pan, MAX_PAN + 1);
+        if index.increment_no_loop(up) {
+            track.pan = index.get();
+            Some(Snapshot::from_states(s0, state))
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for TracksPanel {
@@ -118,6 +174,25 @@ impl Panel for TracksPanel {
                                     get_file_name_no_ex(&program.path),
                                 ],
                             ));
+                            // Pan.
+                            s.push(' ');
+                            s.push_str(&text.get_with_values(
+                                "TRACKS_PANEL_STATUS_TTS_PAN",
+                                &[&text.get_pan_tts(track.pan)],
+                            ));
+                            // Tuning.
+                            if let Some(tuning) = conn.state.tunings.get(&track.channel) {
+                                s.push(' ');
+                                s.push_str(&text.get_with_values(
+                                    "TRACKS_PANEL_STATUS_TTS_TUNING",
+                                    &[&tuning.scale.description],
+                                ));
+                            }
+                            // Percussion.
+                            if track.percussion {
+                                s.push(' ');
+                                s.push_str(text.get_ref("TRACKS_PANEL_STATUS_TTS_PERCUSSION"))
+                            }
                             // Muted.
                             if track.mute {
                                 s.push(' ');
@@ -129,8 +204,17 @@ impl Panel for TracksPanel {
                                 s.push_str(text.get_ref("TRACKS_PANEL_STATUS_TTS_SOLOED"))
                             }
                         }
-                        // No SoundFont.
-                        None => s.push_str(text.get_ref("TRACKS_PANEL_STATUS_TTS_NO_SOUNDFONT")),
+                        None => match conn.state.missing_programs.get(&track.channel) {
+                            // The SoundFont couldn't be loaded.
+                            Some(path) => s.push_str(&text.get_with_values(
+                                "TRACKS_PANEL_STATUS_TTS_MISSING_SOUNDFONT",
+                                &[get_file_name_no_ex(path)],
+                            )),
+                            // No SoundFont.
+                            None => {
+                                s.push_str(text.get_ref("TRACKS_PANEL_STATUS_TTS_NO_SOUNDFONT"))
+                            }
+                        },
                     }
                     tts.enqueue(s)
                 }
@@ -140,12 +224,20 @@ impl Panel for TracksPanel {
         }
         // Input TTS.
         else if input.happened(&InputEvent::InputTTS) {
-            let mut s = vec![self.tooltips.get_tooltip(
-                "TRACKS_PANEL_INPUT_TTS_ADD",
-                &[InputEvent::AddTrack],
-                input,
-                text,
-            )];
+            let mut s = vec![
+                self.tooltips.get_tooltip(
+                    "TRACKS_PANEL_INPUT_TTS_ADD",
+                    &[InputEvent::AddTrack],
+                    input,
+                    text,
+                ),
+                self.tooltips.get_tooltip(
+                    "TRACKS_PANEL_INPUT_TTS_ADD_PERCUSSION",
+                    &[InputEvent::AddPercussionTrack],
+                    input,
+                    text,
+                ),
+            ];
             // There is a selected track.
             if let Some(track) = state.music.get_selected_track() {
                 s.push(self.tooltips.get_tooltip(
@@ -166,6 +258,26 @@ impl Panel for TracksPanel {
                     input,
                     text,
                 ));
+                s.push(self.tooltips.get_tooltip(
+                    "TRACKS_PANEL_INPUT_TTS_SCALE",
+                    &[InputEvent::EnableScalePanel],
+                    input,
+                    text,
+                ));
+                s.push(self.tooltips.get_tooltip(
+                    "TRACKS_PANEL_INPUT_TTS_KEYBOARD_MAPPING",
+                    &[InputEvent::EnableKeyboardMappingPanel],
+                    input,
+                    text,
+                ));
+                if conn.state.tunings.contains_key(&track.channel) {
+                    s.push(self.tooltips.get_tooltip(
+                        "TRACKS_PANEL_INPUT_TTS_RESET_TUNING",
+                        &[InputEvent::ResetTuning],
+                        input,
+                        text,
+                    ));
+                }
                 // Is there a program?
                 if conn.state.programs.get(&track.channel).is_some() {
                     // Preset, bank, gain.
@@ -187,6 +299,12 @@ impl Panel for TracksPanel {
                         input,
                         text,
                     ));
+                    s.push(self.tooltips.get_tooltip(
+                        "TRACKS_PANEL_INPUT_TTS_TRACK_SUFFIX_3",
+                        &[InputEvent::PanTrackLeft, InputEvent::PanTrackRight],
+                        input,
+                        text,
+                    ));
                     // Mute.
                     let mute_key = if track.mute {
                         "TRACKS_PANEL_INPUT_TTS_UNMUTE"
@@ -218,32 +336,11 @@ impl Panel for TracksPanel {
         }
         // Add a track.
         else if input.happened(&InputEvent::AddTrack) {
-            let s0 = state.clone();
-            // Get all channels currently being used.
-            let track_channels: Vec<u8> =
-                state.music.midi_tracks.iter().map(|t| t.channel).collect();
-            // Get all available channels and get the minimum availabe channel.
-            match (0u8..255u8).filter(|c| !track_channels.contains(c)).min() {
-                Some(channel) => {
-                    // Deselect.
-                    state.select_mode = match &state.select_mode {
-                        SelectMode::Single(_) => SelectMode::Single(None),
-                        SelectMode::Many(_) => SelectMode::Many(None),
-                    };
-                    // Set the selection.
-                    state.music.selected = Some(state.music.midi_tracks.len());
-                    // Add a track.
-                    state.music.midi_tracks.push(MidiTrack::new(channel));
-                    // Set the soundfont to the default.
-                    let c0 = vec![Command::UnsetProgram { channel }];
-                    let c1 = vec![Command::LoadSoundFont {
-                        channel,
-                        path: self.default_soundfont_path.clone(),
-                    }];
-                    Some(Snapshot::from_states_and_commands(s0, state, c0, c1, conn))
-                }
-                None => None,
-            }
+            self.add_track(state, conn, false)
+        }
+        // Add a percussion track.
+        else if input.happened(&InputEvent::AddPercussionTrack) {
+            self.add_track(state, conn, true)
         }
         // There is a selected track.
         else if let Some(selected) = state.music.selected {
@@ -268,25 +365,67 @@ impl Panel for TracksPanel {
                 };
                 // Remove the track.
                 state.music.midi_tracks.retain(|t| t.channel != channel);
+                let mut c0 = vec![];
+                let mut c1 = vec![];
                 // This track has a program that needs to be unset.
-                match conn.state.programs.get(&channel) {
-                    Some(program) => {
-                        // Undo: Set the program.
-                        let c0 = vec![Command::SetProgram {
-                            channel,
-                            path: program.path.clone(),
-                            bank_index: program.bank_index,
-                            preset_index: program.preset_index,
-                        }];
-                        let c1 = vec![Command::UnsetProgram { channel }];
-                        Some(Snapshot::from_states_and_commands(s0, state, c0, c1, conn))
-                    }
-                    None => Some(Snapshot::from_states(s0, state)),
+                if let Some(program) = conn.state.programs.get(&channel) {
+                    // Undo: Set the program.
+                    c0.push(Command::SetProgram {
+                        channel,
+                        path: program.path.clone(),
+                        bank_index: program.bank_index,
+                        preset_index: program.preset_index,
+                    });
+                    c1.push(Command::UnsetProgram { channel });
+                }
+                // This track has a missing program that needs to be unset.
+                else if let Some(path) = conn.state.missing_programs.get(&channel) {
+                    // Undo: Try to load the SoundFont again.
+                    c0.push(Command::LoadSoundFont {
+                        channel,
+                        path: path.clone(),
+                        percussion: s0
+                            .music
+                            .midi_tracks
+                            .iter()
+                            .any(|t| t.channel == channel && t.percussion),
+                    });
+                    c1.push(Command::UnsetProgram { channel });
+                }
+                // This track has a tuning that needs to be reset.
+                if conn.state.tunings.contains_key(&channel) {
+                    // Undo: Set the tuning.
+                    c0.push(conn.get_tuning_command(channel));
+                    c1.push(Self::get_reset_tuning_command(channel));
+                }
+                if c0.is_empty() {
+                    Some(Snapshot::from_states(s0, state))
+                } else {
+                    Some(Snapshot::from_states_and_commands(s0, state, c0, c1, conn))
                 }
             } else if input.happened(&InputEvent::EnableSoundFontPanel) {
                 return Some(Snapshot::from_io_commands(vec![IOCommand::EnableOpenFile(
                     OpenFileType::SoundFont,
                 )]));
+            } else if input.happened(&InputEvent::EnableScalePanel) {
+                return Some(Snapshot::from_io_commands(vec![IOCommand::EnableOpenFile(
+                    OpenFileType::Scale,
+                )]));
+            } else if input.happened(&InputEvent::EnableKeyboardMappingPanel) {
+                return Some(Snapshot::from_io_commands(vec![IOCommand::EnableOpenFile(
+                    OpenFileType::KeyboardMapping,
+                )]));
+            }
+            // Reset the tuning to 12-tone equal temperament.
+            else if input.happened(&InputEvent::ResetTuning) {
+                let channel = state.music.get_selected_track().unwrap().channel;
+                return if conn.state.tunings.contains_key(&channel) {
+                    let c0 = vec![conn.get_tuning_command(channel)];
+                    let c1 = vec![Self::get_reset_tuning_command(channel)];
+                    Some(Snapshot::from_commands(c0, c1, conn))
+                } else {
+                    None
+                };
             }
             // Select a track.
             else if let Some(snapshot) = select_track(state, input, TRACK_SCROLL_EVENTS) {
@@ -311,6 +450,10 @@ impl Panel for TracksPanel {
                             TracksPanel::set_gain(state, true)
                         } else if input.happened(&InputEvent::DecreaseTrackGain) {
                             TracksPanel::set_gain(state, false)
+                        } else if input.happened(&InputEvent::PanTrackLeft) {
+                            TracksPanel::set_pan(state, false)
+                        } else if input.happened(&InputEvent::PanTrackRight) {
+                            TracksPanel::set_pan(state, true)
                         } else if input.happened(&InputEvent::Mute) {
                             let s0 = state.clone();
                             let track = state.music.get_selected_track_mut().unwrap();
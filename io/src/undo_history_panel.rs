@@ -0,0 +1,139 @@
+use crate::panel::*;
+use common::{PanelType, UndoHistory};
+use std::cmp::Ordering;
+
+/// Browse the undo history and jump to any point in it.
+#[derive(Default)]
+pub(crate) struct UndoHistoryPanel {
+    /// The popup.
+    popup: Popup,
+    /// The tooltips handler.
+    tooltips: Tooltips,
+    /// The undo history. This is only set while the panel is enabled.
+    undo_history: UndoHistory,
+}
+
+impl UndoHistoryPanel {
+    /// Enable the panel. If the panel is already enabled, refresh the history.
+    ///
+    /// - `state` The app state.
+    /// - `labels` The label of each action from oldest to newest, including actions that can be redone.
+    /// - `position` The number of actions that are applied.
+    /// - `tts` Text-to-speech.
+    /// - `text` The text.
+    pub fn enable(
+        &mut self,
+        state: &mut State,
+        labels: Vec<String>,
+        position: usize,
+        tts: &mut TTS,
+        text: &Text,
+    ) {
+        if !state.panels.contains(&PanelType::UndoHistory) {
+            self.popup.enable(state, vec![PanelType::UndoHistory]);
+        }
+        self.undo_history = UndoHistory {
+            labels,
+            position,
+            selected: position,
+        };
+        tts.stop();
+        Self::enqueue_entry_tts(&self.undo_history, tts, text);
+    }
+
+    /// Returns the undo history.
+    pub fn get_undo_history(&self) -> &UndoHistory {
+        &self.undo_history
+    }
+
+    /// Say the label of the selected entry and whether it's the current state, an earlier state, or a state that can be redone.
+    fn enqueue_entry_tts(undo_history: &UndoHistory, tts: &mut TTS, text: &Text) {
+        let selected = undo_history.selected;
+        let label = if selected == 0 {
+            text.get("UNDO_HISTORY_PANEL_START")
+        } else {
+            undo_history.labels[selected - 1].clone()
+        };
+        let key = match selected.cmp(&undo_history.position) {
+            Ordering::Less => "UNDO_HISTORY_PANEL_STATUS_TTS_UNDO",
+            Ordering::Equal => "UNDO_HISTORY_PANEL_STATUS_TTS_CURRENT",
+            Ordering::Greater => "UNDO_HISTORY_PANEL_STATUS_TTS_REDO",
+        };
+        tts.enqueue(TtsString::from(text.get_with_values(key, &[&label])));
+    }
+}
+
+impl Panel for UndoHistoryPanel {
+    fn update(
+        &mut self,
+        state: &mut State,
+        _: &mut Conn,
+        input: &Input,
+        tts: &mut TTS,
+        text: &Text,
+        _: &mut PathsState,
+    ) -> Option<Snapshot> {
+        if input.happened(&InputEvent::StatusTTS) {
+            Self::enqueue_entry_tts(&self.undo_history, tts, text);
+        } else if input.happened(&InputEvent::InputTTS) {
+            tts.enqueue(self.tooltips.get_tooltip(
+                "UNDO_HISTORY_PANEL_INPUT_TTS",
+                &[
+                    InputEvent::PreviousUndoHistoryEntry,
+                    InputEvent::NextUndoHistoryEntry,
+                    InputEvent::SelectUndoHistoryEntry,
+                    InputEvent::CloseUndoHistoryPanel,
+                ],
+                input,
+                text,
+            ));
+        }
+        // Select an earlier entry.
+        else if input.happened(&InputEvent::PreviousUndoHistoryEntry) {
+            if self.undo_history.selected > 0 {
+                self.undo_history.selected -= 1;
+                tts.stop();
+                Self::enqueue_entry_tts(&self.undo_history, tts, text);
+            }
+        }
+        // Select a later entry.
+        else if input.happened(&InputEvent::NextUndoHistoryEntry) {
+            if self.undo_history.selected + 1 < self.undo_history.get_num_entries() {
+                self.undo_history.selected += 1;
+                tts.stop();
+                Self::enqueue_entry_tts(&self.undo_history, tts, text);
+            }
+        }
+        // Undo or redo to the selected entry.
+        else if input.happened(&InputEvent::SelectUndoHistoryEntry) {
+            if self.undo_history.selected != self.undo_history.position {
+                return Some(Snapshot::from_io_commands(vec![
+                    IOCommand::SetUndoPosition(self.undo_history.selected),
+                ]));
+            }
+        } else if input.happened(&InputEvent::CloseUndoHistoryPanel) {
+            self.popup.disable(state);
+            self.undo_history = UndoHistory::default();
+        }
+        None
+    }
+
+    fn allow_alphanumeric_input(&self, _: &State, _: &Conn) -> bool {
+        false
+    }
+
+    fn allow_play_music(&self) -> bool {
+        false
+    }
+
+    fn on_disable_abc123(&mut self, _: &mut State, _: &mut Conn) {}
+
+    fn update_abc123(
+        &mut self,
+        _: &mut State,
+        _: &Input,
+        _: &mut Conn,
+    ) -> (Option<Snapshot>, bool) {
+        (None, false)
+    }
+}
@@ -0,0 +1,198 @@
+use audio::Command;
+use common::{Note, State};
+use text::{get_file_name, Text};
+
+/// Returns a human-readable description of an action, e.g. "Moved 4 notes right on track 2".
+/// The description is inferred from the state before and after the action and from the synthesizer commands that applied it.
+///
+/// - `from_state` The state before the action, if the action changed the state.
+/// - `to_state` The state after the action, if the action changed the state.
+/// - `to_commands` The commands that applied the action, if any.
+/// - `text` The text.
+pub(crate) fn get_undo_label(
+    from_state: Option<&State>,
+    to_state: Option<&State>,
+    to_commands: Option<&[Command]>,
+    text: &Text,
+) -> String {
+    let states = from_state.zip(to_state);
+    // Tracks and notes are described first because they're what users are most likely to want to undo.
+    if let Some(label) = states.and_then(|(s0, s1)| get_tracks_label(s0, s1, text)) {
+        label
+    } else if let Some(label) = to_commands.and_then(|commands| get_commands_label(commands, text))
+    {
+        label
+    } else if let Some(label) = states.and_then(|(s0, s1)| get_state_label(s0, s1, text)) {
+        label
+    } else {
+        text.get("UNDO_LABEL_OTHER")
+    }
+}
+
+/// Describe a change to the tracks or to their notes.
+fn get_tracks_label(s0: &State, s1: &State, text: &Text) -> Option<String> {
+    let t0 = &s0.music.midi_tracks;
+    let t1 = &s1.music.midi_tracks;
+    // A track was added or removed. Find its channel.
+    if t0.len() != t1.len() {
+        let (key, more, fewer) = if t1.len() > t0.len() {
+            ("UNDO_LABEL_ADD_TRACK", t1, t0)
+        } else {
+            ("UNDO_LABEL_REMOVE_TRACK", t0, t1)
+        };
+        return more
+            .iter()
+            .find(|t| !fewer.iter().any(|f| f.channel == t.channel))
+            .map(|t| text.get_with_values(key, &[&t.channel.to_string()]));
+    }
+    for (a, b) in t0.iter().zip(t1.iter()) {
+        let channel = b.channel.to_string();
+        // Unmodified notes are shared between states, so this is usually a cheap check.
+        if !a.notes.ptr_eq(&b.notes) && a.notes != b.notes {
+            return Some(get_notes_label(&a.notes, &b.notes, &channel, text));
+        }
+        let key = if a.automation != b.automation {
+            "UNDO_LABEL_AUTOMATION"
+        } else if a.gain != b.gain {
+            "UNDO_LABEL_TRACK_GAIN"
+        } else if a.pan != b.pan {
+            "UNDO_LABEL_PAN"
+        } else if a.mute != b.mute {
+            if b.mute {
+                "UNDO_LABEL_MUTE"
+            } else {
+                "UNDO_LABEL_UNMUTE"
+            }
+        } else if a.solo != b.solo {
+            if b.solo {
+                "UNDO_LABEL_SOLO"
+            } else {
+                "UNDO_LABEL_UNSOLO"
+            }
+        } else {
+            continue;
+        };
+        return Some(text.get_with_values(key, &[&channel]));
+    }
+    None
+}
+
+/// Describe a change to the notes of a track.
+fn get_notes_label(n0: &[Note], n1: &[Note], channel: &str, text: &Text) -> String {
+    let (key, count) = if n1.len() > n0.len() {
+        ("ADD", n1.len() - n0.len())
+    } else if n1.len() < n0.len() {
+        ("REMOVE", n0.len() - n1.len())
+    } else {
+        // The change in pitch, volume, start time, and end time.
+        let delta = |a: &Note, b: &Note| {
+            (
+                b.note as i16 - a.note as i16,
+                b.velocity as i16 - a.velocity as i16,
+                b.start as i64 - a.start as i64,
+                b.end as i64 - a.end as i64,
+            )
+        };
+        let changed: Vec<(i16, i16, i64, i64)> = n0
+            .iter()
+            .zip(n1.iter())
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| delta(a, b))
+            .collect();
+        // If every note changed in the same way, describe how.
+        let key = if changed.iter().all(|d| *d == changed[0]) {
+            match changed[0] {
+                (note, 0, 0, 0) if note > 0 => "MOVE_UP",
+                (_, 0, 0, 0) => "MOVE_DOWN",
+                (0, 0, start, end) if start == end && start > 0 => "MOVE_RIGHT",
+                (0, 0, start, end) if start == end => "MOVE_LEFT",
+                (0, 0, 0, end) if end > 0 => "LENGTHEN",
+                (0, 0, 0, _) => "SHORTEN",
+                (0, _, 0, 0) => "VOLUME",
+                _ => "EDIT",
+            }
+        } else {
+            "EDIT"
+        };
+        (key, changed.len())
+    };
+    let key = format!(
+        "UNDO_LABEL_{}_{}",
+        key,
+        if count == 1 { "NOTE" } else { "NOTES" }
+    );
+    text.get_with_values(&key, &[channel, &count.to_string()])
+}
+
+/// Describe a change to the synthesizer.
+fn get_commands_label(commands: &[Command], text: &Text) -> Option<String> {
+    // If a SoundFont was loaded, that's the most important change.
+    if let Some((channel, path)) = commands.iter().find_map(|command| match command {
+        Command::LoadSoundFont { channel, path, .. } => Some((channel, path)),
+        _ => None,
+    }) {
+        return Some(text.get_with_values(
+            "UNDO_LABEL_LOAD_SOUNDFONT",
+            &[&channel.to_string(), get_file_name(path)],
+        ));
+    }
+    commands.iter().find_map(|command| match command {
+        Command::SetProgram { channel, .. } => {
+            Some(text.get_with_values("UNDO_LABEL_SET_PROGRAM", &[&channel.to_string()]))
+        }
+        Command::UnsetProgram { channel } => {
+            Some(text.get_with_values("UNDO_LABEL_UNSET_PROGRAM", &[&channel.to_string()]))
+        }
+        Command::SetTuning { channel, .. } => {
+            Some(text.get_with_values("UNDO_LABEL_SET_TUNING", &[&channel.to_string()]))
+        }
+        Command::SetGain { .. } => Some(text.get("UNDO_LABEL_SET_GAIN")),
+        _ => None,
+    })
+}
+
+/// Describe any other change to the state.
+fn get_state_label(s0: &State, s1: &State, text: &Text) -> Option<String> {
+    if s0.music.selected != s1.music.selected {
+        if let Some(track) = s1.music.get_selected_track() {
+            return Some(
+                text.get_with_values("UNDO_LABEL_SELECT_TRACK", &[&track.channel.to_string()]),
+            );
+        }
+    }
+    [
+        (s0.music.key != s1.music.key, "UNDO_LABEL_KEY"),
+        (
+            s0.time.tempos != s1.time.tempos || s0.time.bpm != s1.time.bpm,
+            "UNDO_LABEL_TEMPO",
+        ),
+        (
+            s0.time.time_signatures != s1.time.time_signatures,
+            "UNDO_LABEL_TIME_SIGNATURE",
+        ),
+        (
+            s0.time.loop_playback != s1.time.loop_playback,
+            "UNDO_LABEL_LOOP",
+        ),
+        (s0.time.cursor != s1.time.cursor, "UNDO_LABEL_CURSOR"),
+        (s0.time.playback != s1.time.playback, "UNDO_LABEL_PLAYBACK"),
+        (s0.select_mode != s1.select_mode, "UNDO_LABEL_SELECTION"),
+        (s0.view != s1.view, "UNDO_LABEL_VIEW"),
+        (s0.input != s1.input, "UNDO_LABEL_INPUT"),
+        (
+            s0.panels != s1.panels || s0.focus != s1.focus,
+            "UNDO_LABEL_PANEL",
+        ),
+        (
+            s0.piano_roll_mode != s1.piano_roll_mode
+                || s0.edit_mode != s1.edit_mode
+                || s0.time.mode != s1.time.mode
+                || s0.automation_type != s1.automation_type
+                || s0.automation_edit_mode != s1.automation_edit_mode,
+            "UNDO_LABEL_MODE",
+        ),
+    ]
+    .iter()
+    .find(|(changed, _)| *changed)
+    .map(|(_, key)| text.get(key))
+}
@@ -0,0 +1,89 @@
+use crate::panel::*;
+use crate::Popup;
+use audio::audio_devices::AudioDeviceSetting;
+
+/// The keys of the settings, in the order that they're drawn.
+const KEYS: [&str; 4] = [
+    "AUDIO_DEVICE_PANEL_HOST",
+    "AUDIO_DEVICE_PANEL_DEVICE",
+    "AUDIO_DEVICE_PANEL_FRAMERATE",
+    "AUDIO_DEVICE_PANEL_BUFFER_SIZE",
+];
+
+/// Set the audio output device.
+pub(crate) struct AudioDevicePanel {
+    /// The panel background.
+    panel: Panel,
+    /// The host field.
+    host: KeyListCorners,
+    /// The device field.
+    device: KeyListCorners,
+    /// The framerate field.
+    framerate: KeyListCorners,
+    /// The buffer size field.
+    buffer_size: KeyListCorners,
+    /// The popup.
+    pub popup: Popup,
+}
+
+impl AudioDevicePanel {
+    pub fn new(config: &Ini, renderer: &Renderer, text: &Text) -> Self {
+        // Get the panel size and position.
+        let window_grid_size = get_window_grid_size(config);
+        let w = window_grid_size[0] / 2;
+        let h = KEYS.len() as u32 + 2;
+        let x = window_grid_size[0] / 2 - w / 2;
+        let y = MAIN_MENU_HEIGHT;
+        // The values fill the space that the keys don't.
+        let key_width = KEYS
+            .iter()
+            .map(|key| text.get_ref(key).chars().count() as u32)
+            .max()
+            .unwrap();
+        let value_width = w - key_width - 6;
+        let mut fields = KEYS.iter().enumerate().map(|(i, key)| {
+            KeyListCorners::new(
+                text.get(key),
+                [x + 1, y + 1 + i as u32],
+                w - 2,
+                value_width,
+                renderer,
+            )
+        });
+        let host = fields.next().unwrap();
+        let device = fields.next().unwrap();
+        let framerate = fields.next().unwrap();
+        let buffer_size = fields.next().unwrap();
+        let panel = Panel::new(PanelType::AudioDevice, [x, y], [w, h], renderer, text);
+        let popup = Popup::new(PanelType::AudioDevice);
+        Self {
+            panel,
+            host,
+            device,
+            framerate,
+            buffer_size,
+            popup,
+        }
+    }
+}
+
+impl Drawable for AudioDevicePanel {
+    fn update(&self, renderer: &Renderer, _: &State, conn: &Conn, text: &Text, _: &PathsState) {
+        self.popup.update(renderer);
+        self.panel.update(true, renderer);
+        let (settings, values) = conn.audio_devices.setting.get_values();
+        for (setting, selected) in settings.iter().zip(values) {
+            let field = match setting {
+                AudioDeviceSetting::Host => &self.host,
+                AudioDeviceSetting::Device => &self.device,
+                AudioDeviceSetting::Framerate => &self.framerate,
+                AudioDeviceSetting::BufferSize => &self.buffer_size,
+            };
+            let value = conn
+                .audio_devices
+                .get_value(setting)
+                .unwrap_or_else(|| text.get("AUDIO_DEVICE_PANEL_DEFAULT"));
+            renderer.key_list_corners(&value, field, [true, selected]);
+        }
+    }
+}
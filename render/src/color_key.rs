@@ -34,4 +34,5 @@ pub enum ColorKey {
     Track5Focus,
     Track5NoFocus,
     SubtitleBackground,
+    OutOfKeyRow,
 }
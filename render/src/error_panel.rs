@@ -0,0 +1,75 @@
+use crate::{panel::*, popup::Popup};
+use input::InputEvent;
+use text::{wrap, Tooltips};
+
+const LABEL_COLOR: ColorKey = ColorKey::Value;
+
+/// Show an error message.
+pub(crate) struct ErrorPanel {
+    /// The panel.
+    panel: Panel,
+    /// The position of the first line of the error message.
+    position: [u32; 2],
+    /// The maximum width and number of lines of the error message.
+    size: [u32; 2],
+    /// The close label.
+    close: Label,
+    /// The popup.
+    pub popup: Popup,
+}
+
+impl ErrorPanel {
+    pub fn new(config: &Ini, renderer: &Renderer, text: &Text, input: &Input) -> Self {
+        // Get the size and position of the panel.
+        let window_grid_size = get_window_grid_size(config);
+        let w = window_grid_size[0] / 2;
+        let h = window_grid_size[1] / 3;
+        let x = window_grid_size[0] / 2 - w / 2;
+        let y = window_grid_size[1] / 2 - h / 2;
+        let panel = Panel::new(PanelType::Error, [x, y], [w, h], renderer, text);
+        let position = [x + 2, y + 1];
+        let size = [w - 4, h - 4];
+        // Define the close label.
+        let mut tooltips = Tooltips::default();
+        let close = tooltips
+            .get_tooltip(
+                "ERROR_PANEL_CLOSE",
+                &[InputEvent::CloseErrorPanel],
+                input,
+                text,
+            )
+            .seen;
+        let close = Label::new([position[0], y + h - 2], close, renderer);
+        let popup = Popup::new(PanelType::Error);
+        Self {
+            panel,
+            position,
+            size,
+            close,
+            popup,
+        }
+    }
+}
+
+impl Drawable for ErrorPanel {
+    fn update(&self, renderer: &Renderer, state: &State, _: &Conn, _: &Text, _: &PathsState) {
+        self.popup.update(renderer);
+        self.panel.update(true, renderer);
+        if let Some(error) = &state.error {
+            // Draw as many lines as will fit.
+            for (i, line) in wrap(error, self.size[0] as usize)
+                .iter()
+                .take(self.size[1] as usize)
+                .enumerate()
+            {
+                let label = LabelRef::new(
+                    [self.position[0], self.position[1] + i as u32],
+                    line,
+                    renderer,
+                );
+                renderer.text_ref(&label, &LABEL_COLOR);
+            }
+        }
+        renderer.text(&self.close, &LABEL_COLOR);
+    }
+}
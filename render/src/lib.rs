@@ -40,10 +40,15 @@ use audio::Conn;
 use common::State;
 pub(crate) use page::Page;
 pub(crate) use page_position::PagePosition;
+mod audio_device_panel;
+mod error_panel;
 mod links_panel;
 mod quit_panel;
+mod recover_panel;
+mod relocate_panel;
+mod undo_history_panel;
 
-pub(crate) const TRACK_HEIGHT_SOUNDFONT: u32 = 4;
+pub(crate) const TRACK_HEIGHT_SOUNDFONT: u32 = 5;
 pub(crate) const TRACK_HEIGHT_NO_SOUNDFONT: u32 = 1;
 
 /// If subtitles are enabled and Casey is speaking, draw the subtitles.
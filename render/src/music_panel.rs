@@ -17,6 +17,10 @@ pub(crate) struct MusicPanel {
     gain: KeyList,
     /// The rectangle of the background of the name field.
     gain_rect: RectanglePixel,
+    /// The key field.
+    key: KeyList,
+    /// The rectangle of the background of the key field.
+    key_rect: RectanglePixel,
 }
 
 impl MusicPanel {
@@ -51,6 +55,12 @@ impl MusicPanel {
         y += 1;
         let gain = KeyList::new(text.get("TITLE_GAIN"), [x + 1, y], width - 2, 3, renderer);
         let gain_rect = RectanglePixel::new_from_u([x, y], [width, 1], renderer);
+        y += 1;
+        // The key is as wide as possible.
+        let key_title = text.get("TITLE_KEY");
+        let key_w = width - 2 - (key_title.chars().count() as u32 + 3);
+        let key = KeyList::new(key_title, [x + 1, y], width - 2, key_w, renderer);
+        let key_rect = RectanglePixel::new_from_u([x, y], [width, 1], renderer);
 
         // Return.
         Self {
@@ -61,12 +71,14 @@ impl MusicPanel {
             bpm,
             gain,
             gain_rect,
+            key,
+            key_rect,
         }
     }
 }
 
 impl Drawable for MusicPanel {
-    fn update(&self, renderer: &Renderer, state: &State, conn: &Conn, _: &Text, _: &PathsState) {
+    fn update(&self, renderer: &Renderer, state: &State, conn: &Conn, text: &Text, _: &PathsState) {
         // Get the focus,
         let focus = self.panel.has_focus(state);
         // Draw the rect.
@@ -104,7 +116,7 @@ impl Drawable for MusicPanel {
         }
         // Draw the BPM.
         renderer.key_value(
-            &state.time.bpm.to_string(),
+            &state.time.get_bpm(state.time.cursor).to_string(),
             &self.bpm.key_width,
             [&key_color, &Renderer::get_value_color([focus, bpm_focus])],
         );
@@ -118,6 +130,17 @@ impl Drawable for MusicPanel {
             &conn.state.gain.to_string(),
             &self.gain,
             [focus, gain_focus],
+        );
+
+        // Key.
+        let key_focus = focused_field == MusicPanelField::Key;
+        if key_focus {
+            renderer.corners(&self.key_rect, focus);
+        }
+        renderer.key_list(
+            &text.get_key(&state.music.key),
+            &self.key,
+            [focus, key_focus],
         )
     }
 }
@@ -76,6 +76,14 @@ impl OpenFilePanel {
                 renderer,
             ),
         );
+        titles.insert(
+            OpenFileType::WriteBundle,
+            LabelRectangle::new(
+                title_position,
+                text.get("OPEN_FILE_PANEL_TITLE_WRITE_BUNDLE"),
+                renderer,
+            ),
+        );
         titles.insert(
             OpenFileType::Export,
             LabelRectangle::new(
@@ -92,6 +100,22 @@ impl OpenFilePanel {
                 renderer,
             ),
         );
+        titles.insert(
+            OpenFileType::Scale,
+            LabelRectangle::new(
+                title_position,
+                text.get("OPEN_FILE_PANEL_TITLE_SCALE"),
+                renderer,
+            ),
+        );
+        titles.insert(
+            OpenFileType::KeyboardMapping,
+            LabelRectangle::new(
+                title_position,
+                text.get("OPEN_FILE_PANEL_TITLE_KEYBOARD_MAPPING"),
+                renderer,
+            ),
+        );
 
         // Get the scroll labels.
         let mut scroll_labels = HashMap::new();
@@ -265,10 +289,14 @@ impl Drawable for OpenFilePanel {
             // Draw the extension.
             let mut extension = String::from(".");
             let ext = match paths_state.open_file_type {
-                OpenFileType::ReadSave | OpenFileType::WriteSave => Extension::Cac,
+                OpenFileType::ReadSave | OpenFileType::WriteSave | OpenFileType::WriteBundle => {
+                    Extension::Cac
+                }
                 OpenFileType::SoundFont => Extension::Sf2,
                 OpenFileType::Export => conn.exporter.export_type.get().into(),
                 OpenFileType::ImportMidi => Extension::Mid,
+                OpenFileType::Scale => Extension::Scl,
+                OpenFileType::KeyboardMapping => Extension::Kbm,
             };
             extension.push_str(ext.to_str(true));
             renderer.text_ref(
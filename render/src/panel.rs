@@ -35,7 +35,12 @@ impl Panel {
             PanelType::ExportState => text.get("TITLE_EXPORT_STATE"),
             PanelType::ExportSettings => text.get("TITLE_EXPORT_SETTINGS"),
             PanelType::Quit => text.get("TITLE_QUIT"),
+            PanelType::Error => text.get("TITLE_ERROR"),
+            PanelType::Relocate => text.get("TITLE_RELOCATE"),
+            PanelType::Recover => text.get("TITLE_RECOVER"),
+            PanelType::UndoHistory => text.get("TITLE_UNDO_HISTORY"),
             PanelType::Links => text.get("TITLE_LINKS"),
+            PanelType::AudioDevice => text.get("TITLE_AUDIO_DEVICE"),
         };
         let title_position = [position[0] + 2, position[1]];
         let title = LabelRectangle::new(title_position, title, renderer);
@@ -1,3 +1,5 @@
+use crate::audio_device_panel::AudioDevicePanel;
+use crate::error_panel::ErrorPanel;
 use crate::export_panel::ExportPanel;
 use crate::export_settings_panel::ExportSettingsPanel;
 use crate::links_panel::LinksPanel;
@@ -7,8 +9,11 @@ use crate::open_file_panel::OpenFilePanel;
 use crate::panel::*;
 use crate::piano_roll_panel::PianoRollPanel;
 use crate::quit_panel::QuitPanel;
+use crate::recover_panel::RecoverPanel;
+use crate::relocate_panel::RelocatePanel;
 use crate::tracks_panel::TracksPanel;
-use common::State;
+use crate::undo_history_panel::UndoHistoryPanel;
+use common::{State, UndoHistory};
 
 /// Every panel.
 pub struct Panels {
@@ -30,6 +35,16 @@ pub struct Panels {
     quit_panel: QuitPanel,
     /// The links panel.
     links_panel: LinksPanel,
+    /// The audio device panel.
+    audio_device_panel: AudioDevicePanel,
+    /// The error panel.
+    error_panel: ErrorPanel,
+    /// The relocate panel.
+    relocate_panel: RelocatePanel,
+    /// The recover panel.
+    recover_panel: RecoverPanel,
+    /// The undo history panel.
+    undo_history_panel: UndoHistoryPanel,
 }
 
 impl Panels {
@@ -52,6 +67,11 @@ impl Panels {
             ExportSettingsPanel::new(config, renderer, &conn.exporter, text);
         let quit_panel = QuitPanel::new(config, renderer, text, input);
         let links_panel = LinksPanel::new(config, renderer, text, input);
+        let audio_device_panel = AudioDevicePanel::new(config, renderer, text);
+        let error_panel = ErrorPanel::new(config, renderer, text, input);
+        let relocate_panel = RelocatePanel::new(config, renderer, text, input);
+        let recover_panel = RecoverPanel::new(config, renderer, text, input);
+        let undo_history_panel = UndoHistoryPanel::new(config, renderer, text);
         Self {
             music_panel,
             main_menu,
@@ -62,6 +82,11 @@ impl Panels {
             export_settings_panel,
             quit_panel,
             links_panel,
+            audio_device_panel,
+            error_panel,
+            relocate_panel,
+            recover_panel,
+            undo_history_panel,
         }
     }
 
@@ -72,6 +97,7 @@ impl Panels {
     /// - `conn` The synthesizer-player connection.
     /// - `text` The text.
     /// - `paths_state` The state of the file paths.
+    /// - `undo_history` The undo history, which is drawn if the undo history panel is enabled.
     pub fn update(
         &self,
         renderer: &Renderer,
@@ -79,6 +105,7 @@ impl Panels {
         conn: &Conn,
         text: &Text,
         paths_state: &PathsState,
+        undo_history: &UndoHistory,
     ) {
         // Draw the main panel.
         self.main_menu
@@ -95,6 +122,14 @@ impl Panels {
                 PanelType::ExportSettings => &self.export_settings_panel,
                 PanelType::Quit => &self.quit_panel,
                 PanelType::Links => &self.links_panel,
+                PanelType::AudioDevice => &self.audio_device_panel,
+                PanelType::Error => &self.error_panel,
+                PanelType::Relocate => &self.relocate_panel,
+                PanelType::Recover => &self.recover_panel,
+                PanelType::UndoHistory => {
+                    self.undo_history_panel.update(renderer, undo_history);
+                    continue;
+                }
             };
             // Draw the panel.
             panel.update(renderer, state, conn, text, paths_state);
@@ -107,6 +142,11 @@ impl Panels {
         self.export_panel.popup.late_update(state, renderer);
         self.quit_panel.popup.late_update(state, renderer);
         self.links_panel.popup.late_update(state, renderer);
+        self.audio_device_panel.popup.late_update(state, renderer);
+        self.error_panel.popup.late_update(state, renderer);
+        self.relocate_panel.popup.late_update(state, renderer);
+        self.recover_panel.popup.late_update(state, renderer);
+        self.undo_history_panel.popup.late_update(state, renderer);
         self.main_menu.late_update(renderer, conn);
         self.piano_roll_panel.late_update(state, renderer);
     }
@@ -7,15 +7,19 @@ mod multi_track;
 mod top_bar;
 mod viewable_notes;
 mod volume;
-use common::{SelectMode, State, U64orF32, NOTE_NAMES, PPQ_U};
+use common::{get_drum_name, PianoRollMode, SelectMode, State, U64orF32, NOTE_NAMES, PPQ_U};
 use hashbrown::HashSet;
 use multi_track::MultiTrack;
-use text::ppq_to_string;
+use text::{bar_beat_tick_to_string, ppq_to_string};
 use top_bar::TopBar;
 use viewable_notes::{ViewableNote, ViewableNotes};
 use volume::Volume;
 
 const TIME_PADDING: u32 = 3;
+/// If beats are closer together than this many pixels, only draw bar lines.
+const MIN_BEAT_LINE_SPACING: f32 = 6.0;
+/// The width and height of an automation point in pixels.
+const AUTOMATION_POINT_SIZE: f32 = 6.0;
 
 /// Draw the piano roll panel.
 pub struct PianoRollPanel {
@@ -167,6 +171,81 @@ impl PianoRollPanel {
         }
     }
 
+    /// Draw a vertical line at the start of each bar and, if there is enough space, at each beat.
+    fn draw_bar_lines(&self, state: &State, focus: bool, renderer: &Renderer, dt: &[U64orF32; 2]) {
+        let ppp = ViewableNotes::get_pulses_per_pixel(dt, self.piano_roll_rows_rect[2]);
+        let bottom = self.piano_roll_rows_rect[1] + self.piano_roll_rows_rect[3];
+        let bar_color = if focus {
+            ColorKey::Separator
+        } else {
+            ColorKey::NoFocus
+        };
+        for (t, bar) in state.time.get_beats(dt[0].get_u(), dt[1].get_u()) {
+            // Don't draw beat lines if there isn't enough space or if the panel doesn't have focus.
+            let color = if bar {
+                &bar_color
+            } else if focus
+                && state.time.get_time_signature(t).get_beat_ppq() as f32 / ppp as f32
+                    >= MIN_BEAT_LINE_SPACING
+            {
+                &ColorKey::NoFocus
+            } else {
+                continue;
+            };
+            let x = ViewableNotes::get_note_x(t, ppp, self.piano_roll_rows_rect[0], dt);
+            renderer.vertical_line_pixel(x, self.piano_roll_rows_rect[1], bottom, color);
+        }
+    }
+
+    /// Draw the selected track's automation lane as a line over the piano roll rows.
+    /// The top of the rows is the maximum value and the bottom of the rows is zero.
+    fn draw_automation(&self, state: &State, focus: bool, renderer: &Renderer, dt: &[U64orF32; 2]) {
+        let automation = match state
+            .music
+            .get_selected_track()
+            .and_then(|t| t.get_automation(&state.automation_type))
+        {
+            Some(automation) => automation,
+            None => return,
+        };
+        let color = if focus {
+            ColorKey::Value
+        } else {
+            ColorKey::NoFocus
+        };
+        let [x, y, w, h] = self.piano_roll_rows_rect;
+        let ppp = ViewableNotes::get_pulses_per_pixel(dt, w);
+        let max = automation.automation_type.get_max() as f32;
+        let get_y = |value: u16| y + h * (1.0 - value as f32 / max);
+        // Start with the value at the start of the view.
+        let mut x0 = x;
+        let mut y0 = get_y(automation.get_value(dt[0].get_u()));
+        for point in automation
+            .points
+            .iter()
+            .filter(|p| p.time >= dt[0].get_u() && p.time <= dt[1].get_u())
+        {
+            let x1 = ViewableNotes::get_note_x(point.time, ppp, x, dt);
+            let y1 = get_y(point.value);
+            // Hold the previous value until this point.
+            renderer.horizontal_line_pixel(x0, x1, y0, &color);
+            renderer.vertical_line_pixel(x1, y0, y1, &color);
+            // Draw the point.
+            renderer.rectangle_note(
+                [
+                    x1 - AUTOMATION_POINT_SIZE / 2.0,
+                    y1 - AUTOMATION_POINT_SIZE / 2.0,
+                ],
+                [AUTOMATION_POINT_SIZE, AUTOMATION_POINT_SIZE],
+                &color,
+            );
+            x0 = x1;
+            y0 = y1;
+        }
+        // Hold the last value until the end of the view.
+        renderer.horizontal_line_pixel(x0, x + w, y0, &color);
+    }
+
     /// If music isn't playing, this returns `state.view.dt`.
     /// Otherwise, this returns a view delta that has been moved to include the current playback time.
     fn get_view_dt(state: &State, conn: &Conn) -> [u64; 2] {
@@ -222,7 +301,7 @@ impl Drawable for PianoRollPanel {
 
         if state.view.single_track {
             // Piano roll rows.
-            self.piano_roll_rows.update(renderer);
+            self.piano_roll_rows.update(state, renderer);
             // Get the viewable notes.
             let notes = ViewableNotes::new(
                 self.piano_roll_rows_rect[0],
@@ -263,6 +342,9 @@ impl Drawable for PianoRollPanel {
                 }
             }
 
+            // Draw the bar and beat lines.
+            self.draw_bar_lines(state, focus, renderer, &dt);
+
             let in_pitch_range: Vec<&ViewableNote> =
                 notes.notes.iter().filter(|n| n.in_pitch_range).collect();
             let selected_pitches: Vec<u8> = selected
@@ -280,6 +362,10 @@ impl Drawable for PianoRollPanel {
                     + ((state.view.dn[0] - note.note.note) as f32) * self.cell_size[1];
                 renderer.rectangle_note([note.x, y], [w, self.cell_size[1]], &note.color)
             }
+            // Automation.
+            if state.piano_roll_mode == PianoRollMode::Automation {
+                self.draw_automation(state, focus, renderer, &dt);
+            }
             // Volume.
             self.volume.update(&notes, renderer, state);
             // Note names.
@@ -288,13 +374,37 @@ impl Drawable for PianoRollPanel {
             } else {
                 &ColorKey::NoFocus
             };
+            // Show drum names instead of note names in a percussion track.
+            let percussion = state
+                .music
+                .get_selected_track()
+                .is_some_and(|t| t.percussion);
+            // Show scale degrees instead of note names in a retuned track.
+            let tuning = state
+                .music
+                .get_selected_track()
+                .and_then(|t| conn.state.tunings.get(&t.channel))
+                .filter(|t| !t.is_12_tet());
             for (position, pitch) in self
                 .note_name_positions
                 .iter()
                 .zip((state.view.dn[1] + 1..state.view.dn[0] + 1).rev())
             {
-                let note_name =
-                    LabelRef::new(*position, NOTE_NAMES[127 - pitch as usize], renderer);
+                let degree;
+                let note_name = match tuning {
+                    Some(tuning) => {
+                        degree = tuning.get_label(pitch);
+                        &degree
+                    }
+                    None => {
+                        if percussion {
+                            get_drum_name(pitch)
+                        } else {
+                            NOTE_NAMES[127 - pitch as usize]
+                        }
+                    }
+                };
+                let note_name = LabelRef::new(*position, note_name, renderer);
                 let note_name_color = if selected_pitches.contains(&pitch) {
                     &ColorKey::NoteSelected
                 } else {
@@ -314,7 +424,7 @@ impl Drawable for PianoRollPanel {
             panel.background.grid_rect.position[0] + PIANO_ROLL_PANEL_NOTE_NAMES_WIDTH + 1;
         let cursor_string = text.get_with_values(
             "PIANO_ROLL_PANEL_CURSOR_TIME",
-            &[&ppq_to_string(state.time.cursor)],
+            &[&bar_beat_tick_to_string(state.time.cursor, &state.time)],
         );
         let cursor_string_width = cursor_string.chars().count() as u32;
         let playback_x = cursor_x + cursor_string_width + TIME_PADDING;
@@ -332,7 +442,7 @@ impl Drawable for PianoRollPanel {
         };
         let playback_string = text.get_with_values(
             "PIANO_ROLL_PANEL_PLAYBACK_TIME",
-            &[&ppq_to_string(state.time.playback)],
+            &[&bar_beat_tick_to_string(state.time.playback, &state.time)],
         );
         let playback_string_width = playback_string.chars().count() as u32;
         let playback_line_x0 = playback_x + playback_string_width / 2;
@@ -374,8 +484,10 @@ impl Drawable for PianoRollPanel {
         // Current playback time.
         let play_state = Self::get_play_state(&conn.play_state);
         if let PlayState::Playing(samples) = play_state {
-            let music_time_string =
-                ppq_to_string(state.time.samples_to_ppq(samples, conn.framerate));
+            let music_time_string = bar_beat_tick_to_string(
+                state.time.samples_to_ppq(samples, conn.framerate),
+                &state.time,
+            );
             let music_time_x =
                 selection_x + selection_label.text.chars().count() as u32 + TIME_PADDING;
             let music_time_label =
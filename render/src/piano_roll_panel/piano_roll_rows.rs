@@ -69,9 +69,31 @@ impl PianoRollRows {
     }
 
     /// Draw the rows.
-    pub fn update(&self, renderer: &Renderer) {
+    pub fn update(&self, state: &State, renderer: &Renderer) {
         // Draw the background.
         renderer.rectangle(&self.rect, &BACKGROUND_COLOR);
+        // Shade the rows that are out of the key. Percussion tracks don't have a key.
+        let key = &state.music.key;
+        if !key.is_chromatic()
+            && !state
+                .music
+                .get_selected_track()
+                .is_some_and(|t| t.percussion)
+        {
+            for (y, pitch) in
+                (0..self.rect.size[1]).zip((state.view.dn[1] + 1..state.view.dn[0] + 1).rev())
+            {
+                if !key.contains(pitch) {
+                    renderer.rectangle(
+                        &Rectangle::new(
+                            [self.rect.position[0], self.rect.position[1] + y],
+                            [self.rect.size[0], 1],
+                        ),
+                        &ColorKey::OutOfKeyRow,
+                    );
+                }
+            }
+        }
         // Draw each row.
         self.positions
             .iter()
@@ -91,7 +91,7 @@ impl TopBar {
 
         // Get the modes.
         let total_modes_width = (((piano_roll_panel_size[0] - 2) - (x - x0)) as f64 * 0.75) as u32;
-        let dx = total_modes_width / 4;
+        let dx = total_modes_width / 5;
         let mut modes = HashMap::new();
         TopBar::insert_mode(
             "PIANO_ROLL_PANEL_TOP_BAR_TIME",
@@ -129,6 +129,15 @@ impl TopBar {
             text,
         );
         x += dx;
+        TopBar::insert_mode(
+            "PIANO_ROLL_PANEL_TOP_BAR_AUTOMATION",
+            PianoRollMode::Automation,
+            [x, y],
+            &mut modes,
+            renderer,
+            text,
+        );
+        x += dx;
 
         // Get the separator position.
         let modes_separator = Line::vertical_line_separator([x, y], renderer);
@@ -199,6 +208,9 @@ impl TopBar {
             },
             PianoRollMode::Time => Self::get_edit_mode_text(&state.time.mode, text),
             PianoRollMode::View => Self::get_edit_mode_text(&state.view.mode, text),
+            PianoRollMode::Automation => {
+                Self::get_edit_mode_text(&state.automation_edit_mode, text)
+            }
         };
         let edit_mode = LabelRef::new(self.edit_mode_position, edit_mode, renderer);
         let edit_mode_color = if focus {
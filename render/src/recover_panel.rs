@@ -0,0 +1,72 @@
+use crate::{panel::*, popup::Popup};
+use input::InputEvent;
+use text::Tooltips;
+
+const LABEL_PADDING: u32 = 8;
+const LABEL_COLOR: ColorKey = ColorKey::Value;
+
+/// Do you want to recover the music that wasn't saved when the app last quit?
+pub(crate) struct RecoverPanel {
+    /// The panel.
+    panel: Panel,
+    /// Yes and no.
+    labels: [Label; 2],
+    /// The popup.
+    pub popup: Popup,
+}
+
+impl RecoverPanel {
+    pub fn new(config: &Ini, renderer: &Renderer, text: &Text, input: &Input) -> Self {
+        let mut tooltips = Tooltips::default();
+        // Get the width of the panel.
+        let yes = tooltips
+            .get_tooltip(
+                "RECOVER_PANEL_YES",
+                &[InputEvent::RecoverPanelYes],
+                input,
+                text,
+            )
+            .seen;
+        let no = tooltips
+            .get_tooltip(
+                "RECOVER_PANEL_NO",
+                &[InputEvent::RecoverPanelNo],
+                input,
+                text,
+            )
+            .seen;
+        let yes_w = yes.chars().count() as u32 + LABEL_PADDING;
+        // The panel must be wide enough for the title.
+        let w = (yes_w + no.chars().count() as u32)
+            .max(text.get_ref("TITLE_RECOVER").chars().count() as u32)
+            + 4;
+        let h = 3;
+        // Get the position of the panel.
+        let window_grid_size = get_window_grid_size(config);
+        let x = window_grid_size[0] / 2 - w / 2;
+        let y = window_grid_size[1] / 2 - h / 2;
+        // Define the panel.
+        let panel = Panel::new(PanelType::Recover, [x, y], [w, h], renderer, text);
+        // Define the labels.
+        let yes_x = x + 2;
+        let yes_y = y + 1;
+        let yes = Label::new([yes_x, yes_y], yes, renderer);
+        let no = Label::new([yes_x + yes_w, yes_y], no, renderer);
+        let labels = [yes, no];
+        let popup = Popup::new(PanelType::Recover);
+        Self {
+            panel,
+            labels,
+            popup,
+        }
+    }
+}
+
+impl Drawable for RecoverPanel {
+    fn update(&self, renderer: &Renderer, _: &State, _: &Conn, _: &Text, _: &PathsState) {
+        self.popup.update(renderer);
+        self.panel.update(true, renderer);
+        renderer.text(&self.labels[0], &LABEL_COLOR);
+        renderer.text(&self.labels[1], &LABEL_COLOR);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{panel::*, popup::Popup};
+use input::InputEvent;
+use text::{get_file_name, truncate, Tooltips};
+
+const LABEL_COLOR: ColorKey = ColorKey::Value;
+const PATH_COLOR: ColorKey = ColorKey::Key;
+
+/// Find SoundFonts that were missing when a save file was loaded.
+pub(crate) struct RelocatePanel {
+    /// The panel.
+    panel: Panel,
+    /// The position of the first line of text.
+    position: [u32; 2],
+    /// The maximum width of a line of text.
+    width: usize,
+    /// Use the replacement, browse, and skip.
+    labels: [Label; 3],
+    /// Toggle relative paths. The first label is for absolute paths, the second for relative paths.
+    relative_paths: [Label; 2],
+    /// The popup.
+    pub popup: Popup,
+}
+
+impl RelocatePanel {
+    pub fn new(config: &Ini, renderer: &Renderer, text: &Text, input: &Input) -> Self {
+        // Get the size and position of the panel.
+        let window_grid_size = get_window_grid_size(config);
+        let w = window_grid_size[0] / 2;
+        let h = 9;
+        let x = window_grid_size[0] / 2 - w / 2;
+        let y = window_grid_size[1] / 2 - h / 2;
+        let panel = Panel::new(PanelType::Relocate, [x, y], [w, h], renderer, text);
+        let position = [x + 2, y + 1];
+        let width = (w - 4) as usize;
+        // Define the labels.
+        let mut tooltips = Tooltips::default();
+        let mut label_y = y + 4;
+        let labels = [
+            ("RELOCATE_PANEL_USE", InputEvent::RelocateSoundFont),
+            ("RELOCATE_PANEL_BROWSE", InputEvent::BrowseSoundFont),
+            ("RELOCATE_PANEL_SKIP", InputEvent::SkipSoundFont),
+        ]
+        .map(|(key, event)| {
+            let tooltip = tooltips.get_tooltip(key, &[event], input, text).seen;
+            let label = Label::new(
+                [position[0], label_y],
+                truncate(&tooltip, width, false).to_string(),
+                renderer,
+            );
+            label_y += 1;
+            label
+        });
+        let relative_paths = ["FALSE", "TRUE"].map(|value| {
+            Label::new(
+                [position[0], label_y],
+                tooltips
+                    .get_tooltip_with_values(
+                        "RELOCATE_PANEL_RELATIVE_PATHS",
+                        &[InputEvent::ToggleRelativeSoundFontPaths],
+                        &[text.get_ref(value)],
+                        input,
+                        text,
+                    )
+                    .seen,
+                renderer,
+            )
+        });
+        let popup = Popup::new(PanelType::Relocate);
+        Self {
+            panel,
+            position,
+            width,
+            labels,
+            relative_paths,
+            popup,
+        }
+    }
+}
+
+impl Drawable for RelocatePanel {
+    fn update(
+        &self,
+        renderer: &Renderer,
+        state: &State,
+        _: &Conn,
+        text: &Text,
+        paths_state: &PathsState,
+    ) {
+        self.popup.update(renderer);
+        self.panel.update(true, renderer);
+        let relocation = match state.relocations.first() {
+            Some(relocation) => relocation,
+            None => return,
+        };
+        // The missing SoundFont.
+        let missing = text.get_with_values(
+            "RELOCATE_PANEL_MISSING",
+            &[
+                &relocation.channel.to_string(),
+                get_file_name(&relocation.path),
+            ],
+        );
+        let label = LabelRef::new(
+            self.position,
+            truncate(&missing, self.width, false),
+            renderer,
+        );
+        renderer.text_ref(&label, &LABEL_COLOR);
+        // The replacement.
+        let found = match &relocation.candidate {
+            Some(candidate) => {
+                text.get_with_values("RELOCATE_PANEL_FOUND", &[&candidate.display().to_string()])
+            }
+            None => text.get("RELOCATE_PANEL_NOT_FOUND"),
+        };
+        let label = LabelRef::new(
+            [self.position[0], self.position[1] + 1],
+            truncate(&found, self.width, true),
+            renderer,
+        );
+        renderer.text_ref(&label, &PATH_COLOR);
+        // Only show the option to use the replacement if there is one.
+        let labels = if relocation.candidate.is_some() {
+            &self.labels[..]
+        } else {
+            &self.labels[1..]
+        };
+        for label in labels.iter() {
+            renderer.text(label, &LABEL_COLOR);
+        }
+        renderer.text(
+            &self.relative_paths[paths_state.relative_soundfont_paths as usize],
+            &LABEL_COLOR,
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::panel::*;
 use crate::{get_track_heights, Page, TRACK_HEIGHT_NO_SOUNDFONT, TRACK_HEIGHT_SOUNDFONT};
-use text::{get_file_name, truncate};
+use text::{get_file_name, pan_to_string, truncate};
 
 const MUTE_OFFSET: u32 = 6;
 
@@ -16,6 +16,8 @@ pub struct TracksPanel {
     bank_key: String,
     /// The gain key string.
     gain_key: String,
+    /// The pan key string.
+    pan_key: String,
     /// The mute string.
     mute_text: String,
     /// The solo string.
@@ -49,6 +51,7 @@ impl TracksPanel {
         let field_width = width - 4;
         let bank_key = text.get("TRACKS_PANEL_BANK");
         let gain_key = text.get("TRACKS_PANEL_GAIN");
+        let pan_key = text.get("TRACKS_PANEL_PAN");
         let mute_text = text.get("TRACKS_PANEL_MUTE");
         let solo_text = text.get("TRACKS_PANEL_SOLO");
         let page_height = height - 2;
@@ -59,6 +62,7 @@ impl TracksPanel {
             track_size_no_sf,
             bank_key,
             gain_key,
+            pan_key,
             mute_text,
             solo_text,
             page_height,
@@ -115,11 +119,19 @@ impl Drawable for TracksPanel {
             match conn.state.programs.get(&channel) {
                 // No program. No SoundFont.
                 None => {
-                    let label = Label::new(
-                        [x + 1, y],
-                        text.get_with_values("TRACKS_PANEL_TRACK_TITLE", &[&channel.to_string()]),
-                        renderer,
-                    );
+                    let title = match conn.state.missing_programs.get(&channel) {
+                        // The SoundFont couldn't be loaded.
+                        Some(path) => {
+                            let title = text.get_with_values(
+                                "TRACKS_PANEL_TRACK_TITLE_MISSING",
+                                &[&channel.to_string(), get_file_name(path)],
+                            );
+                            truncate(&title, self.field_width as usize, false).to_string()
+                        }
+                        None => text
+                            .get_with_values("TRACKS_PANEL_TRACK_TITLE", &[&channel.to_string()]),
+                    };
+                    let label = Label::new([x + 1, y], title, renderer);
                     renderer.text(&label, &Renderer::get_key_color(focus));
                     y += 1;
                 }
@@ -174,6 +186,16 @@ impl Drawable for TracksPanel {
                         );
                     }
                     y += 1;
+                    // Draw the pan.
+                    let pan = KeyList::new(
+                        self.pan_key.clone(),
+                        [x + 1, y],
+                        self.field_width,
+                        3,
+                        renderer,
+                    );
+                    renderer.key_list(&pan_to_string(track.pan), &pan, f);
+                    y += 1;
                     // Draw the file.
                     let file_text = truncate(
                         get_file_name(&program.path),
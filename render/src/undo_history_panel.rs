@@ -0,0 +1,96 @@
+use crate::panel::*;
+use crate::{Page, PagePosition, Popup};
+use common::UndoHistory;
+use hashbrown::HashMap;
+use text::truncate;
+
+/// A list of every action that can be undone or redone.
+pub(crate) struct UndoHistoryPanel {
+    /// The panel.
+    panel: Panel,
+    /// The label of the first entry, before any actions.
+    start: String,
+    /// Labels for scrolling through pages.
+    scroll_labels: HashMap<PagePosition, Label>,
+    /// The popup handler.
+    pub popup: Popup,
+}
+
+impl UndoHistoryPanel {
+    pub fn new(config: &Ini, renderer: &Renderer, text: &Text) -> Self {
+        let window_grid_size = get_window_grid_size(config);
+        let size = [window_grid_size[0] / 2, window_grid_size[1] / 2];
+        let position = [
+            window_grid_size[0] / 2 - size[0] / 2,
+            window_grid_size[1] / 2 - size[1] / 2,
+        ];
+        let panel = Panel::new(PanelType::UndoHistory, position, size, renderer, text);
+        let start = text.get("UNDO_HISTORY_PANEL_START");
+        // Get the scroll labels.
+        let label_y = position[1] + size[1] - 2;
+        let scroll_labels = [
+            (PagePosition::First, "OPEN_FILE_PANEL_DOWN"),
+            (PagePosition::Mid, "OPEN_FILE_PANEL_UP_DOWN"),
+            (PagePosition::Last, "OPEN_FILE_PANEL_UP"),
+        ]
+        .iter()
+        .map(|(page_position, key)| {
+            let string = text.get(key);
+            let x = position[0] + size[0] - (string.chars().count() as u32 + 2);
+            (*page_position, Label::new([x, label_y], string, renderer))
+        })
+        .collect();
+        let popup = Popup::new(PanelType::UndoHistory);
+        Self {
+            panel,
+            start,
+            scroll_labels,
+            popup,
+        }
+    }
+
+    /// Draw the panel. This isn't `Drawable` because the undo history isn't in the `State`.
+    ///
+    /// - `renderer` The renderer.
+    /// - `undo_history` The undo history.
+    pub fn update(&self, renderer: &Renderer, undo_history: &UndoHistory) {
+        self.popup.update(renderer);
+        self.panel.update(true, renderer);
+        let x = self.panel.background.grid_rect.position[0] + 1;
+        let mut y = self.panel.background.grid_rect.position[1] + 1;
+        let width = self.panel.background.grid_rect.size[0] - 2;
+        // Leave room for the scroll labels.
+        let height = self.panel.background.grid_rect.size[1] - 3;
+        // Get a page of entries.
+        let elements = vec![1; undo_history.get_num_entries()];
+        let page = Page::new(&Some(undo_history.selected), &elements, height);
+        for index in page.visible {
+            // Actions that can be redone are grayed out.
+            let c = if index <= undo_history.position {
+                ColorKey::Value
+            } else {
+                ColorKey::NoFocus
+            };
+            let position = [x, y];
+            // Flip the fg/bg colors for the selected entry.
+            let text_color = if index == undo_history.selected {
+                renderer.rectangle(&Rectangle::new(position, [width, 1]), &c);
+                ColorKey::Background
+            } else {
+                c
+            };
+            let label = if index == 0 {
+                &self.start
+            } else {
+                &undo_history.labels[index - 1]
+            };
+            let label = LabelRef::new(position, truncate(label, width as usize, false), renderer);
+            renderer.text_ref(&label, &text_color);
+            y += 1;
+        }
+        // Possibly draw a scroll indicator.
+        if page.position != PagePosition::Only {
+            renderer.text(&self.scroll_labels[&page.position], &ColorKey::Value);
+        }
+    }
+}
@@ -1,24 +1,48 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use audio::export::ExportType;
+use audio::exporter::FRAMERATES;
 use audio::Conn;
 use clap::Parser;
-use common::args::Args;
+use common::args::{Args, Command, ExportArgs};
 use common::config::{load, parse_bool};
+use common::open_file::{Extension, FileOrDirectory};
 use common::sizes::get_window_pixel_size;
-use common::{get_bytes, Paths, PathsState, State, VERSION};
+use common::{get_bytes, AudioDeviceSettings, Paths, PathsState, State, U64orF32, VERSION};
 use ini::Ini;
 use input::Input;
-use io::IO;
+use io::{Save, IO};
 use macroquad::prelude::*;
+use macroquad::Window;
 use regex::Regex;
 use render::{draw_subtitles, Panels, Renderer};
+use std::fs::create_dir_all;
+use std::path::Path;
+use std::process::exit;
+use std::str::FromStr;
 use text::{Text, TTS};
 use ureq::get;
 
 const CLEAR_COLOR: macroquad::color::Color = macroquad::color::BLACK;
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn main() {
+    // Parse and load the command line arguments.
+    let args = Args::parse();
+    match &args.command {
+        // Export without opening a window.
+        Some(Command::Export(export_args)) => {
+            if let Err(error) = export(export_args, &args.data_directory) {
+                eprintln!("{}", error);
+                exit(1);
+            }
+        }
+        // Open the window.
+        None => Window::from_config(window_conf(), run()),
+    }
+}
+
+/// Open the window and run the app.
+async fn run() {
     // Parse and load the command line arguments.
     let args = Args::parse();
 
@@ -63,7 +87,9 @@ async fn main() {
     let mut input = Input::new(&config, &args);
 
     // Create the audio connection.
-    let mut conn = Conn::default();
+    let mut audio_device = AudioDeviceSettings::new(&config);
+    audio_device.backend = args.get_audio_backend();
+    let mut conn = Conn::new(Some(audio_device));
 
     // Create the state.
     let mut state = State::new(&config);
@@ -107,9 +133,19 @@ async fn main() {
 
     // Open the initial save file if set.
     if let Some(save_path) = args.file {
-        io.load_save(&save_path, &mut state, &mut conn, &mut paths_state);
+        io.load_save(
+            &save_path,
+            &mut state,
+            &mut conn,
+            &mut tts,
+            &text,
+            &mut paths_state,
+        );
     }
 
+    // Offer to recover unsaved music.
+    io.recover(&mut state);
+
     // Begin.
     let mut done: bool = false;
     while !done {
@@ -117,7 +153,14 @@ async fn main() {
         clear_background(CLEAR_COLOR);
 
         // Draw.
-        panels.update(&renderer, &state, &conn, &text, &paths_state);
+        panels.update(
+            &renderer,
+            &state,
+            &conn,
+            &text,
+            &paths_state,
+            io.get_undo_history(),
+        );
 
         // Draw subtitles.
         draw_subtitles(&renderer, &tts);
@@ -149,6 +192,83 @@ async fn main() {
             next_frame().await;
         }
     }
+
+    // The app quit cleanly, so there's nothing to recover.
+    Save::delete_recovery();
+}
+
+/// Load a save file and export it without opening a window or an audio device.
+/// Returns an error message if the export failed.
+fn export(args: &ExportArgs, data_directory: &Path) -> Result<(), String> {
+    if !args.file.is_file() && !Save::get_bundle_save_path(&args.file).is_file() {
+        return Err(format!("Save file not found: {}", args.file.display()));
+    }
+
+    // Initialize the paths.
+    Paths::init(data_directory);
+
+    // Load the config file.
+    let config = load();
+
+    // Create an audio connection that doesn't open an audio device.
+    let mut conn = Conn::new(None);
+    let mut state = State::new(&config);
+    let mut paths_state = PathsState::new(Paths::get());
+
+    // Load the save file.
+    let errors = Save::read(&args.file, &mut state, &mut conn, &mut paths_state)
+        .map_err(|error| error.to_string())?;
+    if !errors.is_empty() {
+        return Err(errors
+            .iter()
+            .map(|error| format!("Error loading {}", error.get_path().display()))
+            .collect::<Vec<String>>()
+            .join("\n"));
+    }
+
+    // Override the save file's export settings.
+    if let Some(format) = &args.format {
+        conn.exporter.set_export_type(ExportType::from_str(format)?);
+    }
+    if let Some(framerate) = args.framerate {
+        if !FRAMERATES.contains(&framerate) {
+            return Err(format!("Unsupported framerate: {}", framerate));
+        }
+        conn.exporter.framerate = U64orF32::from(framerate);
+    }
+    if args.multi_file {
+        conn.exporter.multi_file = true;
+    } else if args.single_file {
+        conn.exporter.multi_file = false;
+    }
+
+    // Set the export path. The filename is the name of the save file.
+    if let Some(out) = &args.out {
+        if let Err(error) = create_dir_all(out) {
+            return Err(format!("Error creating {}: {}", out.display(), error));
+        }
+        paths_state.exports.directory = FileOrDirectory::new(out);
+    }
+    let filename = args
+        .file
+        .file_stem()
+        .and_then(|filename| filename.to_str())
+        .ok_or_else(|| format!("Invalid file name: {}", args.file.display()))?;
+    paths_state.exports.filename = Some(filename.to_string());
+
+    // Export.
+    let export_type = conn.exporter.export_type.get();
+    if export_type == ExportType::Mid {
+        let mut path = paths_state.exports.get_path();
+        path.set_extension(<ExportType as Into<Extension>>::into(export_type).to_str(false));
+        conn.exporter
+            .mid(&path, &state.music, &state.time, &conn.state)
+    } else {
+        match conn.start_export(&state, &paths_state).join() {
+            Ok(result) => result,
+            Err(_) => Err(format!("Error exporting {}", args.file.display())),
+        }
+    }
 }
 
 /// Configure the window.
@@ -8,11 +8,16 @@ mod tooltips;
 mod tts;
 mod value_map;
 pub use self::tts::{Enqueable, TTS};
+use std::cmp::Ordering;
 use std::path::Path;
 pub use value_map::ValueMap;
 mod tts_string;
 use common::config::parse;
-use common::{EditMode, Paths, PianoRollMode, Time, MIN_NOTE, PPQ_F, PPQ_U};
+use common::{
+    AutomationType, EditMode, Key, Paths, PianoRollMode, ScaleType, Time, Tuning, CENTER_PAN,
+    EXPRESSION_CONTROLLER, MAX_DRUM, MIN_DRUM, MIN_NOTE, MODULATION_CONTROLLER, PPQ_F, PPQ_U,
+    SUSTAIN_CONTROLLER,
+};
 use csv::Reader;
 use hashbrown::HashMap;
 use ini::Ini;
@@ -162,6 +167,8 @@ pub struct Text {
     piano_roll_modes: HashMap<PianoRollMode, String>,
     /// The name of each MIDI note.
     note_names: Vec<String>,
+    /// The name of each General MIDI percussion note.
+    drum_names: Vec<String>,
     /// Boolean dislay
     booleans: ValueMap<bool>,
 }
@@ -187,6 +194,12 @@ impl Text {
             .split(", ")
             .map(|s| s.to_string())
             .collect();
+        let drum_names: Vec<String> = text
+            .remove("DRUM_NAMES")
+            .unwrap()
+            .split(", ")
+            .map(|s| s.to_string())
+            .collect();
         let keycodes_spoken = Text::get_keycode_map(&text, true);
         let keycodes_seen = Text::get_keycode_map(&text, false);
         let edit_modes = Text::get_edit_mode_map(&text);
@@ -205,6 +218,7 @@ impl Text {
             edit_modes,
             piano_roll_modes,
             note_names,
+            drum_names,
             booleans,
         }
     }
@@ -330,6 +344,70 @@ impl Text {
         }
     }
 
+    /// Returns a text-to-speech string of the `ppq` value as a bar, a beat, and a tick.
+    pub fn get_bar_beat_tick_tts(&self, ppq: u64, time: &Time) -> String {
+        let [bar, beat, tick] = time.get_bar_beat_tick(ppq);
+        if tick == 0 {
+            self.get_with_values("BAR_BEAT_TTS", &[&bar.to_string(), &beat.to_string()])
+        } else {
+            self.get_with_values(
+                "BAR_BEAT_TICK_TTS",
+                &[&bar.to_string(), &beat.to_string(), &tick.to_string()],
+            )
+        }
+    }
+
+    /// Returns a text-to-speech string of a pan value.
+    pub fn get_pan_tts(&self, pan: u8) -> String {
+        match pan.cmp(&CENTER_PAN) {
+            Ordering::Less => {
+                self.get_with_values("PAN_TTS_LEFT", &[&(CENTER_PAN - pan).to_string()])
+            }
+            Ordering::Equal => self.get("PAN_TTS_CENTER"),
+            Ordering::Greater => {
+                self.get_with_values("PAN_TTS_RIGHT", &[&(pan - CENTER_PAN).to_string()])
+            }
+        }
+    }
+
+    /// Returns the name of an automation type.
+    pub fn get_automation_type(&self, automation_type: &AutomationType) -> String {
+        match automation_type {
+            AutomationType::Controller(MODULATION_CONTROLLER) => {
+                self.get("AUTOMATION_TYPE_MODULATION")
+            }
+            AutomationType::Controller(EXPRESSION_CONTROLLER) => {
+                self.get("AUTOMATION_TYPE_EXPRESSION")
+            }
+            AutomationType::Controller(SUSTAIN_CONTROLLER) => self.get("AUTOMATION_TYPE_SUSTAIN"),
+            AutomationType::Controller(controller) => {
+                self.get_with_values("AUTOMATION_TYPE_CONTROLLER", &[&controller.to_string()])
+            }
+            AutomationType::ChannelPressure => self.get("AUTOMATION_TYPE_CHANNEL_PRESSURE"),
+            AutomationType::PitchBend => self.get("AUTOMATION_TYPE_PITCH_BEND"),
+        }
+    }
+
+    /// Returns the name of a key, e.g. "D Dorian".
+    pub fn get_key(&self, key: &Key) -> String {
+        let scale_type = match key.scale_type {
+            ScaleType::Chromatic => return self.get("SCALE_TYPE_CHROMATIC"),
+            ScaleType::Major => "SCALE_TYPE_MAJOR",
+            ScaleType::Minor => "SCALE_TYPE_MINOR",
+            ScaleType::HarmonicMinor => "SCALE_TYPE_HARMONIC_MINOR",
+            ScaleType::MelodicMinor => "SCALE_TYPE_MELODIC_MINOR",
+            ScaleType::Dorian => "SCALE_TYPE_DORIAN",
+            ScaleType::Phrygian => "SCALE_TYPE_PHRYGIAN",
+            ScaleType::Lydian => "SCALE_TYPE_LYDIAN",
+            ScaleType::Mixolydian => "SCALE_TYPE_MIXOLYDIAN",
+            ScaleType::Locrian => "SCALE_TYPE_LOCRIAN",
+            ScaleType::MajorPentatonic => "SCALE_TYPE_MAJOR_PENTATONIC",
+            ScaleType::MinorPentatonic => "SCALE_TYPE_MINOR_PENTATONIC",
+            ScaleType::Custom => "SCALE_TYPE_CUSTOM",
+        };
+        self.get_with_values("KEY_NAME", &[key.get_root_name(), self.get_ref(scale_type)])
+    }
+
     /// Returns an error text-to-speech string.
     pub fn get_error(&self, error: &str) -> String {
         self.get_with_values("ERROR", &[error])
@@ -340,6 +418,38 @@ impl Text {
         &self.note_names[(note - MIN_NOTE) as usize]
     }
 
+    /// Returns the General MIDI percussion name of the note, or the note name if it isn't a percussion note.
+    pub fn get_drum_name(&self, note: u8) -> &str {
+        if (MIN_DRUM..=MAX_DRUM).contains(&note) {
+            &self.drum_names[(note - MIN_DRUM) as usize]
+        } else {
+            self.get_note_name(note)
+        }
+    }
+
+    /// Returns the text-to-speech name of a note.
+    /// If the track is retuned, this is the scale degree. If it's a percussion track, this is the drum name.
+    ///
+    /// - `note` The note.
+    /// - `percussion` If true, this is a percussion track.
+    /// - `tuning` The track's tuning, if any.
+    pub fn get_pitch_tts(&self, note: u8, percussion: bool, tuning: Option<&Tuning>) -> String {
+        match tuning.filter(|tuning| !tuning.is_12_tet()) {
+            Some(tuning) => match tuning.get_degree(note) {
+                Some((degree, period)) => self
+                    .get_with_values("SCALE_DEGREE", &[&degree.to_string(), &period.to_string()]),
+                None => self.get("SCALE_DEGREE_UNMAPPED"),
+            },
+            None => {
+                if percussion {
+                    self.get_drum_name(note).to_string()
+                } else {
+                    self.get_note_name(note).to_string()
+                }
+            }
+        }
+    }
+
     /// Returns a map of keycodes to displayable/sayable text (NOT string keys).
     fn get_keycode_map(text: &HashMap<String, String>, spoken: bool) -> HashMap<KeyCode, String> {
         let suffix = if spoken { "_SPOKEN" } else { "_SEEN" };
@@ -371,6 +481,10 @@ impl Text {
         );
         piano_roll_modes.insert(PianoRollMode::Time, text["PIANO_ROLL_MODE_TIME"].clone());
         piano_roll_modes.insert(PianoRollMode::View, text["PIANO_ROLL_MODE_VIEW"].clone());
+        piano_roll_modes.insert(
+            PianoRollMode::Automation,
+            text["PIANO_ROLL_MODE_AUTOMATION"].clone(),
+        );
         piano_roll_modes
     }
 }
@@ -395,6 +509,21 @@ pub fn ppq_to_string(ppq: u64) -> String {
     }
 }
 
+/// Converts a PPQ value into a bar:beat:tick string.
+pub fn bar_beat_tick_to_string(ppq: u64, time: &Time) -> String {
+    let [bar, beat, tick] = time.get_bar_beat_tick(ppq);
+    format!("{}:{}:{}", bar, beat, tick)
+}
+
+/// Converts a pan value into a string, for example: `"L12"`, `"C"`, or `"R12"`.
+pub fn pan_to_string(pan: u8) -> String {
+    match pan.cmp(&CENTER_PAN) {
+        Ordering::Less => format!("L{}", CENTER_PAN - pan),
+        Ordering::Equal => "C".to_string(),
+        Ordering::Greater => format!("R{}", pan - CENTER_PAN),
+    }
+}
+
 /// Truncate a string to fit a specified length.
 ///
 /// - `string` The string.
@@ -437,9 +566,43 @@ pub fn get_file_name_no_ex(path: &Path) -> &str {
     }
 }
 
+/// Wrap a string into lines. Words that are longer than a line are split.
+///
+/// - `string` The string.
+/// - `width` The maximum length of each line.
+pub fn wrap(string: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in string.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let line_len = line.chars().count();
+        // Start a new line.
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        // Split long words.
+        while word.len() > width {
+            lines.push(word.drain(0..width).collect());
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{get_file_name, get_file_name_no_ex, ppq_to_string, truncate};
+    use crate::{
+        bar_beat_tick_to_string, get_file_name, get_file_name_no_ex, pan_to_string, ppq_to_string,
+        truncate, wrap,
+    };
+    use common::{Time, CENTER_PAN, MAX_PAN, PPQ_U};
     use std::path::PathBuf;
 
     #[test]
@@ -469,4 +632,30 @@ mod tests {
         assert_eq!(ppq_to_string(3), "0.02");
         assert_eq!(ppq_to_string(0), "0");
     }
+
+    #[test]
+    fn bar_beat_tick() {
+        let mut time = Time::default();
+        assert_eq!(bar_beat_tick_to_string(0, &time), "1:1:0");
+        assert_eq!(bar_beat_tick_to_string(PPQ_U * 5 + 3, &time), "2:2:3");
+        time.get_time_signature_mut(0).numerator = 3;
+        assert_eq!(bar_beat_tick_to_string(PPQ_U * 5 + 3, &time), "2:3:3");
+    }
+
+    #[test]
+    fn pan() {
+        assert_eq!(pan_to_string(0), "L64");
+        assert_eq!(pan_to_string(CENTER_PAN), "C");
+        assert_eq!(pan_to_string(MAX_PAN), "R63");
+    }
+
+    #[test]
+    fn wrap_test() {
+        assert_eq!(
+            wrap("This is a moderately long string!", 10),
+            vec!["This is a", "moderately", "long", "string!"]
+        );
+        assert_eq!(wrap("abcdefghij klm", 4), vec!["abcd", "efgh", "ij", "klm"]);
+        assert!(wrap("", 4).is_empty());
+    }
 }